@@ -1,6 +1,7 @@
 /*
 RUST_BACKTRACE=1 RUST_LOG=trace cargo run -p tiktok-api-demo --bin tiktok_api_demo_video_upload -- 'YOUR_ACCESS_TOKEN' '/path/x.mp4'
-RUST_BACKTRACE=1 RUST_LOG=trace cargo run -p tiktok-api-demo --bin tiktok_api_demo_video_upload -- 'YOUR_ACCESS_TOKEN' 'https://example.com/x.mp4'
+VERIFIED_DOMAIN='example.com' RUST_BACKTRACE=1 RUST_LOG=trace cargo run -p tiktok-api-demo --bin tiktok_api_demo_video_upload -- 'YOUR_ACCESS_TOKEN' 'https://example.com/x.mp4'
+cat /path/x.mp4 | RUST_BACKTRACE=1 RUST_LOG=trace cargo run -p tiktok-api-demo --bin tiktok_api_demo_video_upload -- 'YOUR_ACCESS_TOKEN' '-'
 */
 
 use std::env;
@@ -11,7 +12,13 @@ use tiktok_api::{
         video_upload_init::VideoUploadInitRequestBodySourceInfo, EndpointRet,
         VideoUploadInitEndpoint,
     },
-    media_transfer::upload_from_file,
+    media_inspect::{inspect_file, MediaLimits},
+    media_transfer::{
+        integrity::to_hex, spool, spool::upload_from_spooled, spool::MEMORY_LIMIT_DEFAULT,
+        spool::SIZE_MAX_DEFAULT, upload_from_file, UploadOptions,
+    },
+    media_type::{sniff_file, MediaType},
+    verified_sources::VerifiedSources,
 };
 
 #[tokio::main]
@@ -26,17 +33,24 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
         .nth(2)
         .ok_or_else(|| "arg path_or_url missing".to_string())?;
     let chunk_size = env::args().nth(3).and_then(|x| x.parse::<usize>().ok());
+    let upload_options = UploadOptions {
+        chunk_size,
+        ..Default::default()
+    };
 
     let client = IsahcClient::new()?;
 
     //
     if path_or_url.starts_with("http://") || path_or_url.starts_with("https://") {
         let video_url = path_or_url.parse()?;
+        let verified_sources = VerifiedSources::new()
+            .with_domain(env::var("VERIFIED_DOMAIN").map_err(|_| "VERIFIED_DOMAIN missing")?);
 
         let video_upload_init = VideoUploadInitEndpoint::new(
             &access_token,
             VideoUploadInitRequestBodySourceInfo::PullFromUrl { video_url },
-        );
+            &verified_sources,
+        )?;
         let ret = client.respond_endpoint(&video_upload_init).await?;
         match &ret {
             EndpointRet::Ok(ok_json) => {
@@ -46,9 +60,67 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
                 panic!("{ret:?}");
             }
         }
+    } else if path_or_url == "-" {
+        let spooled_video = spool(
+            tokio::io::stdin(),
+            MEMORY_LIMIT_DEFAULT,
+            SIZE_MAX_DEFAULT,
+            None,
+        )
+        .await?;
+
+        let media_type = spooled_video.media_type().await?;
+        println!("{media_type:?}");
+
+        let video_upload_init =
+            VideoUploadInitEndpoint::with_spooled(&access_token, &spooled_video, chunk_size);
+        let ret = client.respond_endpoint(&video_upload_init).await?;
+        let upload_url = match &ret {
+            EndpointRet::Ok(ok_json) => {
+                println!("{ok_json:?}");
+                ok_json
+                    .data
+                    .upload_url
+                    .to_owned()
+                    .ok_or("upload_url missing")?
+            }
+            EndpointRet::Other(_) => {
+                panic!("{ret:?}");
+            }
+        };
+
+        match upload_from_spooled(
+            &client.http_client,
+            upload_url,
+            media_type.mime(),
+            &spooled_video,
+            &upload_options,
+        )
+        .await
+        {
+            Ok(report) => {
+                println!("{report:?}");
+                if let Some(sha256) = &report.sha256 {
+                    println!("sha256: {}", to_hex(sha256));
+                }
+            }
+            Err(err) => {
+                panic!("{err:?}");
+            }
+        }
     } else {
         let path = path_or_url.parse()?;
 
+        let media_type = sniff_file(&path).await?;
+        println!("{media_type:?}");
+        if media_type != MediaType::WebM {
+            let media_info = inspect_file(&path)?;
+            println!("{media_info:?}");
+            if let Err(violations) = MediaLimits::default().validate(&media_info) {
+                panic!("{violations:?}");
+            }
+        }
+
         let video_upload_init =
             VideoUploadInitEndpoint::with_file(&access_token, &path, chunk_size).await?;
         let ret = client.respond_endpoint(&video_upload_init).await?;
@@ -66,11 +138,25 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
             }
         };
 
-        let client = reqwest::Client::new();
+        let upload_options = match video_upload_init.file_fingerprint {
+            Some(file_fingerprint) => upload_options.with_expected_fingerprint(file_fingerprint),
+            None => upload_options,
+        };
 
-        match upload_from_file(client, upload_url, "video/mp4", &path, chunk_size).await {
-            Ok(ret_list) => {
-                println!("{ret_list:?}");
+        match upload_from_file(
+            &client.http_client,
+            upload_url,
+            media_type.mime(),
+            &path,
+            &upload_options,
+        )
+        .await
+        {
+            Ok(report) => {
+                println!("{report:?}");
+                if let Some(sha256) = &report.sha256 {
+                    println!("sha256: {}", to_hex(sha256));
+                }
             }
             Err(err) => {
                 panic!("{err:?}");
@@ -0,0 +1,240 @@
+use chrono::Duration;
+use http_api_client_endpoint::http::StatusCode;
+
+use crate::{
+    error::{Error, ErrorSource},
+    objects::{oauth::LegacyErrorCode, v2::ErrorCode},
+};
+
+//
+/// TikTok's rate limits are per one-minute window.
+pub const RATE_LIMIT_RETRY_AFTER_SECS: i64 = 60;
+/// The daily caps, posts per user and posting users per app, are per rolling 24 hours.
+pub const POST_CAP_RETRY_AFTER_SECS: i64 = 24 * 60 * 60;
+
+//
+//
+//
+/// What to do about a failure, the same decision for retry middleware and job queues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Classification {
+    /// Send the same request again, not before `after` when known.
+    Retryable { after: Option<Duration> },
+    /// Refresh the access token, or have the user log in again when the refresh token is gone.
+    Reauthenticate,
+    /// Only the user can unblock it in TikTok, e.g. by posting or deleting pending shares.
+    UserActionRequired,
+    /// Retrying as is fails the same way.
+    Permanent,
+}
+
+impl Classification {
+    fn rate_limited() -> Self {
+        Self::Retryable {
+            after: Some(Duration::seconds(RATE_LIMIT_RETRY_AFTER_SECS)),
+        }
+    }
+
+    /// For error bodies without a known code.
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => Self::Reauthenticate,
+            StatusCode::TOO_MANY_REQUESTS => Self::rate_limited(),
+            x if x.is_server_error() => Self::Retryable { after: None },
+            _ => Self::Permanent,
+        }
+    }
+
+    pub fn from_v2_error_code(code: &ErrorCode, status: StatusCode) -> Self {
+        match code {
+            ErrorCode::AccessTokenInvalid
+            | ErrorCode::ScopeNotAuthorized
+            | ErrorCode::TokenNotAuthorizedForSpecifiedDeviceid
+            | ErrorCode::AuthRemoved => Self::Reauthenticate,
+            ErrorCode::RateLimitExceeded => Self::rate_limited(),
+            ErrorCode::SpamRiskTooManyPosts | ErrorCode::ReachedActiveUserCap => Self::Retryable {
+                after: Some(Duration::seconds(POST_CAP_RETRY_AFTER_SECS)),
+            },
+            ErrorCode::InternalError | ErrorCode::VideoPullFailed | ErrorCode::PhotoPullFailed => {
+                Self::Retryable { after: None }
+            }
+            ErrorCode::SpamRiskTooManyPendingShare
+            | ErrorCode::SpamRiskUserBannedFromPosting
+            | ErrorCode::UnauditedClientCanOnlyPostToPrivateAccounts => Self::UserActionRequired,
+            // The app was not granted the scope, not the user.
+            ErrorCode::ScopePermissionMissed
+            | ErrorCode::InvalidParams
+            | ErrorCode::InvalidFileUpload
+            | ErrorCode::SpamRisk
+            | ErrorCode::SpamRiskText
+            | ErrorCode::UrlOwnershipUnverified
+            | ErrorCode::PrivacyLevelOptionMismatch
+            | ErrorCode::FileFormatCheckFailed
+            | ErrorCode::DurationCheckFailed
+            | ErrorCode::FrameRateCheckFailed
+            | ErrorCode::PictureSizeCheckFailed
+            | ErrorCode::PublishCancelled => Self::Permanent,
+            ErrorCode::Ok | ErrorCode::Other(_) => Self::from_status(status),
+        }
+    }
+
+    /// The oauth `error_code`, and the share `err_code`.
+    pub fn from_legacy_error_code(code: &LegacyErrorCode) -> Self {
+        match code {
+            LegacyErrorCode::AccessTokenInvalid
+            | LegacyErrorCode::RefreshTokenInvalid
+            | LegacyErrorCode::ShareAccessTokenInvalid => Self::Reauthenticate,
+            LegacyErrorCode::Ok | LegacyErrorCode::InvalidParams | LegacyErrorCode::Unknown(_) => {
+                Self::Permanent
+            }
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Retryable { .. })
+    }
+}
+
+//
+pub fn classify(err: &Error) -> Classification {
+    match &err.source {
+        ErrorSource::OauthEndpoint(_) | ErrorSource::V2Endpoint(_) => Classification::Permanent,
+        ErrorSource::OauthResponse { status, body } => match body {
+            Ok(body) => Classification::from_legacy_error_code(&body.data.error_code),
+            Err(_) => Classification::from_status(*status),
+        },
+        ErrorSource::V2Response { status, body } => match body {
+            Ok(body) => Classification::from_v2_error_code(&body.error.code, *status),
+            Err(_) => Classification::from_status(*status),
+        },
+        #[cfg(feature = "with_media_transfer")]
+        ErrorSource::Upload(err) => {
+            use crate::media_transfer::UploadError;
+
+            match err {
+                UploadError::RespondFailed(_, _) => Classification::Retryable { after: None },
+                // An expired `upload_url` is permanent too, `publish::upload` re-initializes.
+                UploadError::ResponseMismatch {
+                    status: StatusCode::TOO_MANY_REQUESTS,
+                    ..
+                } => Classification::rate_limited(),
+                UploadError::ResponseMismatch { status, .. } if status.is_server_error() => {
+                    Classification::Retryable { after: None }
+                }
+                _ => Classification::Permanent,
+            }
+        }
+        #[cfg(feature = "with_media_transfer")]
+        ErrorSource::ShareVideoUpload(err) => {
+            use crate::endpoints::share::video_upload::VideoUploadError;
+
+            match err {
+                VideoUploadError::RespondFailed(_) => Classification::Retryable { after: None },
+                _ => Classification::Permanent,
+            }
+        }
+        #[cfg(feature = "with_media_transfer")]
+        ErrorSource::ShareResponse(body) => {
+            Classification::from_legacy_error_code(&body.data.err_code)
+        }
+        ErrorSource::Respond(_) => Classification::Retryable { after: None },
+    }
+}
+
+impl Error {
+    pub fn classify(&self) -> Classification {
+        classify(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{endpoints::v2::EndpointRet, objects::v2::ResponseErrorBody};
+
+    #[test]
+    fn test_from_v2_error_code() {
+        for (code, classification) in [
+            ("access_token_invalid", Classification::Reauthenticate),
+            ("scope_not_authorized", Classification::Reauthenticate),
+            ("scope_permission_missed", Classification::Permanent),
+            (
+                "rate_limit_exceeded",
+                Classification::Retryable {
+                    after: Some(Duration::seconds(60)),
+                },
+            ),
+            ("internal_error", Classification::Retryable { after: None }),
+            ("invalid_params", Classification::Permanent),
+            (
+                "spam_risk_too_many_posts",
+                Classification::Retryable {
+                    after: Some(Duration::hours(24)),
+                },
+            ),
+            (
+                "reached_active_user_cap",
+                Classification::Retryable {
+                    after: Some(Duration::hours(24)),
+                },
+            ),
+            (
+                "video_pull_failed",
+                Classification::Retryable { after: None },
+            ),
+            ("file_format_check_failed", Classification::Permanent),
+            (
+                "spam_risk_too_many_pending_share",
+                Classification::UserActionRequired,
+            ),
+            (
+                "spam_risk_user_banned_from_posting",
+                Classification::UserActionRequired,
+            ),
+        ] {
+            assert_eq!(
+                Classification::from_v2_error_code(&code.parse().unwrap(), StatusCode::BAD_REQUEST),
+                classification,
+                "{code}"
+            );
+        }
+
+        // Unknown code, falls back to the status.
+        let code = "some_new_code".parse().unwrap();
+        assert_eq!(
+            Classification::from_v2_error_code(&code, StatusCode::SERVICE_UNAVAILABLE),
+            Classification::Retryable { after: None }
+        );
+        assert_eq!(
+            Classification::from_v2_error_code(&code, StatusCode::BAD_REQUEST),
+            Classification::Permanent
+        );
+    }
+
+    #[test]
+    fn test_classify() {
+        let body = serde_json::from_str::<ResponseErrorBody>(include_str!(
+            "../tests/response_body_files/v2/user_info__err__access_token_invalid.json"
+        ))
+        .unwrap();
+        let err = EndpointRet::<()>::Other((StatusCode::UNAUTHORIZED, Ok(body)))
+            .into_result()
+            .unwrap_err();
+        assert_eq!(err.classify(), Classification::Reauthenticate);
+
+        let err = crate::endpoints::oauth::EndpointRet::<()>::Other((
+            StatusCode::OK,
+            Ok(serde_json::from_str(include_str!(
+                "../tests/response_body_files/oauth/refresh_token__err.json"
+            ))
+            .unwrap()),
+        ))
+        .into_result()
+        .unwrap_err();
+        assert_eq!(classify(&err), Classification::Permanent);
+
+        let err = Error::from(ErrorSource::Respond("connection reset".into()));
+        assert!(err.classify().is_retryable());
+    }
+}
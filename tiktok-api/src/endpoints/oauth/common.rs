@@ -2,7 +2,7 @@ use http_api_client_endpoint::{
     http::{Error as HttpError, StatusCode},
     Body, Response,
 };
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::Error as SerdeJsonError;
 use url::ParseError as UrlParseError;
 
@@ -20,6 +20,22 @@ where
     Other((StatusCode, Result<ResponseErrorBody, Body>)),
 }
 
+impl<T> EndpointRet<T>
+where
+    T: core::fmt::Debug + Clone,
+{
+    pub fn into_result(self) -> Result<T, crate::Error> {
+        match self {
+            Self::Ok(x) => Ok(x),
+            Self::Other((status, body)) => Err(crate::error::ErrorSource::OauthResponse {
+                status,
+                body: body.map(Box::new),
+            }
+            .into()),
+        }
+    }
+}
+
 //
 //
 //
@@ -49,9 +65,16 @@ where
         StatusCode::OK => {
             use crate::objects::oauth::Message;
 
+            #[derive(Deserialize)]
+            struct MessageOnly {
+                message: Message,
+            }
+
             #[allow(clippy::single_match)]
-            match serde_json::from_slice::<Message>(response.body()) {
-                Ok(Message::Success) => {
+            match serde_json::from_slice::<MessageOnly>(response.body()) {
+                Ok(MessageOnly {
+                    message: Message::Success,
+                }) => {
                     let ok_json = serde_json::from_slice::<T>(response.body())
                         .map_err(EndpointError::DeResponseBodyFailed)?;
 
@@ -86,7 +109,25 @@ mod tests {
         match endpoint_parse_response::<()>(resp) {
             Ok(EndpointRet::Other((status_code, Ok(err_body)))) => {
                 assert_eq!(status_code, StatusCode::OK);
-                assert_eq!(err_body.data.error_code, 10010);
+                assert_eq!(
+                    err_body.data.error_code,
+                    crate::objects::oauth::LegacyErrorCode::RefreshTokenInvalid
+                );
+            }
+            x => panic!("{x:?}"),
+        }
+
+        let resp_body = include_str!("../../../tests/response_body_files/oauth/refresh_token.json");
+        let resp = Response::builder()
+            .status(StatusCode::OK)
+            .body(resp_body.as_bytes().to_vec())?;
+
+        match endpoint_parse_response::<
+            crate::endpoints::oauth::refresh_token::RefreshTokenResponseBody,
+        >(resp)
+        {
+            Ok(EndpointRet::Ok(ok_json)) => {
+                assert_eq!(ok_json.data.open_id, "_000fwZ23Mw4RY9cB4lDQyKCgQg4Ft6SyTuE");
             }
             x => panic!("{x:?}"),
         }
@@ -5,3 +5,5 @@ pub use common::EndpointRet;
 //
 pub mod refresh_token;
 pub use refresh_token::RefreshTokenEndpoint;
+pub mod revoke;
+pub use revoke::RevokeEndpoint;
@@ -0,0 +1,129 @@
+use http_api_client_endpoint::{
+    http::{
+        header::{ACCEPT, USER_AGENT},
+        Method,
+    },
+    Body, Endpoint, Request, Response,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::common::{endpoint_parse_response, EndpointError, EndpointRet};
+use crate::objects::oauth::{LegacyErrorCode, Message};
+
+//
+pub const URL: &str = "https://open-api.tiktok.com/oauth/revoke/";
+
+//
+/// Revoking a token that is already revoked or expired fails with
+/// `LegacyErrorCode::AccessTokenInvalid`.
+#[derive(Debug, Clone)]
+pub struct RevokeEndpoint {
+    pub open_id: String,
+    pub access_token: String,
+}
+impl RevokeEndpoint {
+    pub fn new(open_id: impl AsRef<str>, access_token: impl AsRef<str>) -> Self {
+        Self {
+            open_id: open_id.as_ref().into(),
+            access_token: access_token.as_ref().into(),
+        }
+    }
+}
+
+impl Endpoint for RevokeEndpoint {
+    type RenderRequestError = EndpointError;
+
+    type ParseResponseOutput = EndpointRet<RevokeResponseBody>;
+    type ParseResponseError = EndpointError;
+
+    fn render_request(&self) -> Result<Request<Body>, Self::RenderRequestError> {
+        let mut url = Url::parse(URL).map_err(EndpointError::MakeRequestUrlFailed)?;
+        url.query_pairs_mut()
+            .append_pair("open_id", &self.open_id)
+            .append_pair("access_token", &self.access_token);
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(url.as_str())
+            .header(USER_AGENT, "tiktok-api")
+            .header(ACCEPT, "application/json")
+            .body(vec![])
+            .map_err(EndpointError::MakeRequestFailed)?;
+
+        Ok(request)
+    }
+
+    fn parse_response(
+        &self,
+        response: Response<Body>,
+    ) -> Result<Self::ParseResponseOutput, Self::ParseResponseError> {
+        endpoint_parse_response(response)
+    }
+}
+
+//
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RevokeResponseBody {
+    pub data: RevokeResponseBodyData,
+    pub message: Message,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RevokeResponseBodyData {
+    pub description: Option<String>,
+    pub error_code: LegacyErrorCode,
+    pub log_id: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use http_api_client_endpoint::http::StatusCode;
+
+    #[test]
+    fn test_render_request() {
+        let req = RevokeEndpoint::new("OPEN_ID", "TOKEN")
+            .render_request()
+            .unwrap();
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(
+            req.uri(),
+            "https://open-api.tiktok.com/oauth/revoke/?open_id=OPEN_ID&access_token=TOKEN"
+        );
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let endpoint = RevokeEndpoint::new("OPEN_ID", "TOKEN");
+
+        match endpoint.parse_response(Response::new(
+            include_str!("../../../tests/response_body_files/oauth/revoke.json")
+                .as_bytes()
+                .to_vec(),
+        )) {
+            Ok(EndpointRet::Ok(ok_json)) => {
+                assert_eq!(ok_json.data.error_code, LegacyErrorCode::Ok);
+                assert_eq!(ok_json.message, Message::Success);
+            }
+            x => panic!("{x:?}"),
+        }
+
+        match endpoint.parse_response(Response::new(
+            include_str!(
+                "../../../tests/response_body_files/oauth/revoke__err__with_refresh_token.json"
+            )
+            .as_bytes()
+            .to_vec(),
+        )) {
+            Ok(EndpointRet::Other((StatusCode::OK, Ok(err_json)))) => {
+                assert_eq!(
+                    err_json.data.error_code,
+                    LegacyErrorCode::AccessTokenInvalid
+                );
+            }
+            x => panic!("{x:?}"),
+        }
+    }
+}
@@ -1,3 +1,3 @@
 //
-#[cfg(feature = "with_video_upload")]
+#[cfg(feature = "with_media_transfer")]
 pub mod video_upload;
@@ -1,26 +1,35 @@
 use chrono::Utc;
-use reqwest::{
-    multipart::{Form, Part},
-    Body, Client, Error as ReqwestError,
+use http_api_client_endpoint::http::{
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    HeaderMap, HeaderValue, Method,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Error as SerdeJsonError;
+use tokio_util::sync::CancellationToken;
 use url::{ParseError as UrlParseError, Url};
 
+use crate::{
+    objects::oauth::LegacyErrorCode,
+    transport::{
+        multipart::MultipartFile, Transport, TransportBody, TransportError, TransportRequest,
+    },
+};
+
 //
 pub const URL: &str = "https://open-api.tiktok.com/share/video/upload/";
 
 //
-pub async fn video_upload<T>(
-    client: &Client,
+pub async fn video_upload<C, T>(
+    client: &C,
     open_id: impl AsRef<str>,
     access_token: impl AsRef<str>,
-    stream: T,
-    stream_length: Option<u64>,
+    body: T,
     file_name: Option<String>,
+    cancellation_token: Option<&CancellationToken>,
 ) -> Result<VideoUploadResponseBody, VideoUploadError>
 where
-    T: Into<Body>,
+    C: Transport + ?Sized,
+    T: Into<TransportBody>,
 {
     let open_id = open_id.as_ref();
     let access_token = access_token.as_ref();
@@ -34,71 +43,75 @@ where
         .append_pair("access_token", access_token);
 
     //
-    let part = if let Some(stream_length) = stream_length {
-        Part::stream_with_length(stream, stream_length)
-    } else {
-        Part::stream(stream)
-    };
+    let file_name = file_name.unwrap_or_else(|| format!("{}.mp4", Utc::now().timestamp_millis()));
 
-    let part = if let Some(file_name) = file_name {
-        part.file_name(file_name)
-    } else {
-        part.file_name(format!("{}.mp4", Utc::now().timestamp_millis()))
-    };
+    let multipart = MultipartFile::new("video", &file_name, body.into());
 
-    let form = Form::new().part("video", part).percent_encode_noop();
-
-    //
-    let resp = client
-        .post(req_url)
-        .multipart(form)
-        .send()
-        .await
-        .map_err(VideoUploadError::RespondFailed)?;
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_str(&multipart.content_type()).expect("boundary is ascii"),
+    );
+    if let Some(length) = multipart.body.length() {
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(length));
+    }
 
     //
-    let resp_body = resp
-        .bytes()
-        .await
-        .map_err(VideoUploadError::ReadResponseBodyFailed)?;
-    let resp_body = resp_body.as_ref();
+    let respond = client.send(TransportRequest {
+        method: Method::POST,
+        url: req_url,
+        headers,
+        body: multipart.body,
+    });
+    let resp = match cancellation_token {
+        Some(cancellation_token) => cancellation_token
+            .run_until_cancelled(respond)
+            .await
+            .ok_or(VideoUploadError::Cancelled)?,
+        None => respond.await,
+    }
+    .map_err(VideoUploadError::RespondFailed)?;
 
-    serde_json::from_slice::<VideoUploadResponseBody>(resp_body)
+    serde_json::from_slice::<VideoUploadResponseBody>(&resp.body)
         .map_err(VideoUploadError::DeResponseBodyFailed)
 }
 
 #[cfg(feature = "with_tokio")]
-pub async fn video_upload_from_reader_stream<S>(
-    client: &Client,
+pub async fn video_upload_from_reader_stream<C, S>(
+    client: &C,
     open_id: impl AsRef<str>,
     access_token: impl AsRef<str>,
     stream: S,
     stream_length: Option<u64>,
     file_name: Option<String>,
+    cancellation_token: Option<&CancellationToken>,
 ) -> Result<VideoUploadResponseBody, VideoUploadError>
 where
+    C: Transport + ?Sized,
     S: tokio::io::AsyncRead + Send + Sync + 'static,
 {
-    use tokio_util::io::ReaderStream;
-
     video_upload(
         client,
         open_id,
         access_token,
-        Body::wrap_stream(ReaderStream::new(stream)),
-        stream_length,
+        TransportBody::reader(stream, stream_length),
         file_name,
+        cancellation_token,
     )
     .await
 }
 
 #[cfg(feature = "with_tokio_fs")]
-pub async fn video_upload_from_file(
-    client: &Client,
+pub async fn video_upload_from_file<C>(
+    client: &C,
     open_id: impl AsRef<str>,
     access_token: impl AsRef<str>,
     file_path: &std::path::PathBuf,
-) -> Result<VideoUploadResponseBody, VideoUploadError> {
+    cancellation_token: Option<&CancellationToken>,
+) -> Result<VideoUploadResponseBody, VideoUploadError>
+where
+    C: Transport + ?Sized,
+{
     use tokio::fs::File;
 
     let crate::tokio_fs_util::Info {
@@ -119,6 +132,7 @@ pub async fn video_upload_from_file(
         file,
         Some(file_size),
         file_name,
+        cancellation_token,
     )
     .await
 }
@@ -132,10 +146,20 @@ pub struct VideoUploadResponseBody {
     pub extra: VideoUploadResponseBodyExtra,
 }
 
+impl VideoUploadResponseBody {
+    /// The request succeeded when `err_code` is `Ok`.
+    pub fn into_result(self) -> Result<Self, crate::Error> {
+        match self.data.err_code {
+            LegacyErrorCode::Ok => Ok(self),
+            _ => Err(crate::error::ErrorSource::ShareResponse(Box::new(self)).into()),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct VideoUploadResponseBodyData {
-    pub err_code: i64,
-    pub error_code: i64,
+    pub err_code: LegacyErrorCode,
+    pub error_code: LegacyErrorCode,
     pub share_id: Option<String>,
     pub error_msg: Option<String>,
 }
@@ -152,13 +176,13 @@ pub struct VideoUploadResponseBodyExtra {
 #[derive(Debug)]
 pub enum VideoUploadError {
     MakeRequestUrlFailed(UrlParseError),
-    RespondFailed(ReqwestError),
-    ReadResponseBodyFailed(ReqwestError),
+    RespondFailed(TransportError),
     DeResponseBodyFailed(SerdeJsonError),
     #[cfg(feature = "with_tokio_fs")]
     GetFileInfoFailed(std::io::Error),
     #[cfg(feature = "with_tokio_fs")]
     OpenFileFailed(std::io::Error),
+    Cancelled,
 }
 impl core::fmt::Display for VideoUploadError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -171,13 +195,17 @@ impl std::error::Error for VideoUploadError {}
 mod tests {
     use super::*;
 
+    use http_api_client_endpoint::http::StatusCode;
+
+    use crate::transport::{mock::MockTransport, TransportResponse};
+
     #[test]
     fn test_de_response_body() {
         match serde_json::from_str::<VideoUploadResponseBody>(include_str!(
             "../../../tests/response_body_files/share/video_upload.json"
         )) {
             Ok(ok_json) => {
-                assert_eq!(ok_json.data.err_code, 0);
+                assert_eq!(ok_json.data.err_code, LegacyErrorCode::Ok);
                 assert_eq!(ok_json.extra.error_detail, "");
             }
             x => panic!("{x:?}"),
@@ -187,13 +215,84 @@ mod tests {
             "../../../tests/response_body_files/share/video_upload__err.json"
         )) {
             Ok(ok_json) => {
-                assert_eq!(ok_json.data.err_code, 20000);
+                assert_eq!(
+                    ok_json.data.err_code,
+                    LegacyErrorCode::ShareAccessTokenInvalid
+                );
                 assert_eq!(
                     ok_json.extra.error_detail,
                     "access_token not found in the request query param"
                 );
+
+                let err = ok_json.into_result().unwrap_err();
+                assert_eq!(err.kind, crate::ErrorKind::Auth);
+                assert_eq!(err.classify(), crate::Classification::Reauthenticate);
+                assert_eq!(err.log_id(), Some("20230227020936C1D05270AC18A066168B"));
+            }
+            x => panic!("{x:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_video_upload() {
+        let transport = MockTransport::new(|_| TransportResponse {
+            status: StatusCode::OK,
+            body: include_bytes!("../../../tests/response_body_files/share/video_upload.json")
+                .to_vec(),
+        });
+
+        match video_upload(
+            &transport,
+            "OPEN_ID",
+            "TOKEN",
+            b"DATA".to_vec(),
+            Some("x.mp4".into()),
+            None,
+        )
+        .await
+        {
+            Ok(ok_json) => {
+                assert_eq!(ok_json.data.err_code, LegacyErrorCode::Ok);
             }
             x => panic!("{x:?}"),
         }
+
+        let request = transport.requests().remove(0);
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(
+            request.url.as_str(),
+            "https://open-api.tiktok.com/share/video/upload/?open_id=OPEN_ID&access_token=TOKEN"
+        );
+        assert!(request.headers[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("multipart/form-data; boundary="));
+        assert_eq!(
+            request.headers[CONTENT_LENGTH],
+            request.body.len().to_string()
+        );
+        assert!(String::from_utf8(request.body).unwrap().contains(
+            "Content-Disposition: form-data; name=\"video\"; filename=\"x.mp4\"\r\n\r\nDATA\r\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_video_upload_cancelled() {
+        let cancellation_token = CancellationToken::new();
+        cancellation_token.cancel();
+
+        match video_upload(
+            &MockTransport::new(|_| unreachable!()),
+            "OPEN_ID",
+            "TOKEN",
+            vec![0_u8; 16],
+            None,
+            Some(&cancellation_token),
+        )
+        .await
+        {
+            Err(VideoUploadError::Cancelled) => {}
+            x => panic!("{x:?}"),
+        }
     }
 }
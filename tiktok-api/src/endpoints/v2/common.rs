@@ -2,11 +2,11 @@ use http_api_client_endpoint::{
     http::{Error as HttpError, StatusCode},
     Body, Response,
 };
-use serde::de::DeserializeOwned;
-use serde_json::Error as SerdeJsonError;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{Error as SerdeJsonError, Map, Value};
 use url::ParseError as UrlParseError;
 
-use crate::objects::v2::ResponseErrorBody;
+use crate::objects::v2::{Error, ErrorCode, ResponseErrorBody};
 
 //
 //
@@ -20,6 +20,22 @@ where
     Other((StatusCode, Result<ResponseErrorBody, Result<String, Body>>)),
 }
 
+impl<T> EndpointRet<T>
+where
+    T: core::fmt::Debug + Clone,
+{
+    pub fn into_result(self) -> Result<T, crate::Error> {
+        match self {
+            Self::Ok(x) => Ok(x),
+            Self::Other((status, body)) => Err(crate::error::ErrorSource::V2Response {
+                status,
+                body: body.map(Box::new),
+            }
+            .into()),
+        }
+    }
+}
+
 //
 //
 //
@@ -31,6 +47,8 @@ pub enum EndpointError {
     DeResponseBodyFailed(SerdeJsonError),
     #[cfg(feature = "with_tokio_fs")]
     GetFileInfoFailed(std::io::Error),
+    #[cfg(feature = "with_tokio_fs")]
+    SniffFailed(crate::media_type::SniffError),
 }
 impl core::fmt::Display for EndpointError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -49,6 +67,42 @@ where
     let status = response.status();
     match status {
         StatusCode::OK => {
+            // Like the oauth `message: error`, a 200 may still carry a non-ok code.
+            // Only the code is required here, so a partial `error` is not taken for a success.
+            #[derive(Deserialize)]
+            struct ErrorProbe {
+                code: ErrorCode,
+                #[serde(default)]
+                message: String,
+                #[serde(default)]
+                log_id: String,
+            }
+            #[derive(Deserialize)]
+            struct ErrorOnly {
+                #[serde(default)]
+                data: Option<Map<String, Value>>,
+                error: Option<ErrorProbe>,
+            }
+            if let Ok(ErrorOnly {
+                data,
+                error: Some(error),
+            }) = serde_json::from_slice::<ErrorOnly>(response.body())
+            {
+                if error.code != ErrorCode::Ok {
+                    return Ok(EndpointRet::Other((
+                        status,
+                        Ok(ResponseErrorBody {
+                            data: data.unwrap_or_default(),
+                            error: Error {
+                                code: error.code,
+                                message: error.message,
+                                log_id: error.log_id,
+                            },
+                        }),
+                    )));
+                }
+            }
+
             let ok_json = serde_json::from_slice::<T>(response.body())
                 .map_err(EndpointError::DeResponseBodyFailed)?;
 
@@ -64,3 +118,75 @@ where
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize, Debug, Clone)]
+    struct Body {
+        data: serde_json::Map<String, serde_json::Value>,
+    }
+
+    fn parse(status: StatusCode, body: &str) -> EndpointRet<Body> {
+        let response = Response::builder()
+            .status(status)
+            .body(body.as_bytes().to_vec())
+            .unwrap();
+        endpoint_parse_response::<Body>(response).unwrap()
+    }
+
+    #[test]
+    fn test_endpoint_parse_response() {
+        match parse(
+            StatusCode::OK,
+            include_str!("../../../tests/response_body_files/v2/user_info.json"),
+        ) {
+            EndpointRet::Ok(ok_json) => assert!(ok_json.data.contains_key("user")),
+            x => panic!("{x:?}"),
+        }
+
+        // Without `error`.
+        match parse(StatusCode::OK, r#"{"data":{}}"#) {
+            EndpointRet::Ok(ok_json) => assert!(ok_json.data.is_empty()),
+            x => panic!("{x:?}"),
+        }
+
+        match parse(
+            StatusCode::OK,
+            r#"{"data":{"x":1},"error":{"code":"spam_risk_too_many_posts","message":"","log_id":"L1"}}"#,
+        ) {
+            EndpointRet::Other((StatusCode::OK, Ok(err_json))) => {
+                assert_eq!(err_json.error.code, ErrorCode::SpamRiskTooManyPosts);
+                assert_eq!(err_json.error.log_id, "L1");
+                assert_eq!(err_json.data["x"], 1);
+            }
+            x => panic!("{x:?}"),
+        }
+
+        // Without `log_id`, nor `message`.
+        match parse(
+            StatusCode::OK,
+            r#"{"data":{"x":1},"error":{"code":"rate_limit_exceeded"}}"#,
+        ) {
+            EndpointRet::Other((StatusCode::OK, Ok(err_json))) => {
+                assert_eq!(err_json.error.code, ErrorCode::RateLimitExceeded);
+                assert_eq!(err_json.error.log_id, "");
+                assert_eq!(err_json.data["x"], 1);
+            }
+            x => panic!("{x:?}"),
+        }
+
+        match parse(
+            StatusCode::UNAUTHORIZED,
+            include_str!(
+                "../../../tests/response_body_files/v2/user_info__err__access_token_invalid.json"
+            ),
+        ) {
+            EndpointRet::Other((StatusCode::UNAUTHORIZED, Ok(err_json))) => {
+                assert_eq!(err_json.error.code, ErrorCode::AccessTokenInvalid);
+            }
+            x => panic!("{x:?}"),
+        }
+    }
+}
@@ -6,8 +6,24 @@ pub use common::EndpointRet;
 pub mod user_info;
 pub use user_info::UserInfoEndpoint;
 
+pub mod publish_cancel;
+pub use publish_cancel::PublishCancelEndpoint;
+
+pub mod publish_status_fetch;
+pub use publish_status_fetch::PublishStatusFetchEndpoint;
+
 //
-#[cfg(feature = "with_video_upload")]
+#[cfg(feature = "with_media_transfer")]
 pub mod video_upload_init;
-#[cfg(feature = "with_video_upload")]
+#[cfg(feature = "with_media_transfer")]
 pub use video_upload_init::VideoUploadInitEndpoint;
+
+#[cfg(feature = "with_media_transfer")]
+pub mod post_video_init;
+#[cfg(feature = "with_media_transfer")]
+pub use post_video_init::PostVideoInitEndpoint;
+
+#[cfg(feature = "with_media_transfer")]
+pub mod photo_post_init;
+#[cfg(feature = "with_media_transfer")]
+pub use photo_post_init::PhotoPostInitEndpoint;
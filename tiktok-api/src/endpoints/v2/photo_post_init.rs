@@ -0,0 +1,256 @@
+use http_api_client_endpoint::{
+    http::{
+        header::{ACCEPT, AUTHORIZATION, USER_AGENT},
+        Method,
+    },
+    Body, Endpoint, Request, Response,
+};
+use serde::{Deserialize, Serialize};
+use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
+use url::Url;
+
+use super::{
+    common::{endpoint_parse_response, EndpointError, EndpointRet},
+    post_video_init::PrivacyLevel,
+};
+use crate::verified_sources::{UnverifiedUrl, VerifiedSources};
+
+//
+pub const URL: &str = "https://open.tiktokapis.com/v2/post/publish/content/init/";
+pub const MEDIA_TYPE: &str = "PHOTO";
+
+//
+#[derive(Debug, Clone)]
+pub struct PhotoPostInitEndpoint {
+    pub access_token: String,
+    pub post_info: PhotoPostInfo,
+    pub source_info: PhotoPostSourceInfo,
+    pub post_mode: PostMode,
+}
+impl PhotoPostInitEndpoint {
+    /// Photos are only pulled from URLs, every one is checked against `verified_sources`
+    /// before any request is sent.
+    pub fn new(
+        access_token: impl AsRef<str>,
+        post_info: PhotoPostInfo,
+        source_info: PhotoPostSourceInfo,
+        post_mode: PostMode,
+        verified_sources: &VerifiedSources,
+    ) -> Result<Self, UnverifiedUrl> {
+        source_info.verify(verified_sources)?;
+
+        Ok(Self {
+            access_token: access_token.as_ref().into(),
+            post_info,
+            source_info,
+            post_mode,
+        })
+    }
+}
+
+impl Endpoint for PhotoPostInitEndpoint {
+    type RenderRequestError = EndpointError;
+
+    type ParseResponseOutput = EndpointRet<PhotoPostInitResponseBody>;
+    type ParseResponseError = EndpointError;
+
+    fn render_request(&self) -> Result<Request<Body>, Self::RenderRequestError> {
+        let request_body = PhotoPostInitRequestBody {
+            post_info: self.post_info.to_owned(),
+            source_info: self.source_info.to_owned(),
+            post_mode: self.post_mode.to_owned(),
+            media_type: MEDIA_TYPE.into(),
+        };
+        let request_body =
+            serde_json::to_vec(&request_body).map_err(EndpointError::SerRequestBodyFailed)?;
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(URL)
+            .header(AUTHORIZATION, format!("Bearer {}", &self.access_token))
+            .header(USER_AGENT, "tiktok-api")
+            .header(ACCEPT, "application/json; charset=UTF-8")
+            .body(request_body)
+            .map_err(EndpointError::MakeRequestFailed)?;
+
+        Ok(request)
+    }
+
+    fn parse_response(
+        &self,
+        response: Response<Body>,
+    ) -> Result<Self::ParseResponseOutput, Self::ParseResponseError> {
+        endpoint_parse_response(response)
+    }
+}
+
+//
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhotoPostInitRequestBody {
+    pub post_info: PhotoPostInfo,
+    pub source_info: PhotoPostSourceInfo,
+    pub post_mode: PostMode,
+    pub media_type: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct PhotoPostInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Required by `PostMode::DirectPost`, one of the creator's `privacy_level_options`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privacy_level: Option<PrivacyLevel>,
+    #[serde(default)]
+    pub disable_comment: bool,
+    #[serde(default)]
+    pub auto_add_music: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "source")]
+pub enum PhotoPostSourceInfo {
+    #[serde(rename = "PULL_FROM_URL")]
+    PullFromUrl {
+        photo_images: Vec<Url>,
+        /// Index into `photo_images`.
+        photo_cover_index: usize,
+    },
+}
+impl PhotoPostSourceInfo {
+    /// Rejects the first URL that TikTok would fail with `url_ownership_unverified`.
+    pub fn verify(&self, verified_sources: &VerifiedSources) -> Result<(), UnverifiedUrl> {
+        match self {
+            Self::PullFromUrl { photo_images, .. } => photo_images
+                .iter()
+                .try_for_each(|url| verified_sources.verify(url)),
+        }
+    }
+}
+
+#[derive(Deserialize_enum_str, Serialize_enum_str, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PostMode {
+    DirectPost,
+    /// To the creator's inbox, they finish the post in the app.
+    MediaUpload,
+    #[serde(other)]
+    Other(Box<str>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhotoPostInitResponseBody {
+    pub data: PhotoPostInitResponseBodyData,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhotoPostInitResponseBodyData {
+    pub publish_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_info(urls: &[&str]) -> PhotoPostSourceInfo {
+        PhotoPostSourceInfo::PullFromUrl {
+            photo_images: urls.iter().map(|x| x.parse().unwrap()).collect(),
+            photo_cover_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_render_request() {
+        let req = PhotoPostInitEndpoint::new(
+            "TOKEN",
+            PhotoPostInfo {
+                title: Some("funny cat".into()),
+                privacy_level: Some(PrivacyLevel::SelfOnly),
+                ..Default::default()
+            },
+            source_info(&[
+                "https://example.verified.domain.com/1.jpg",
+                "https://example.verified.domain.com/2.jpg",
+            ]),
+            PostMode::DirectPost,
+            &VerifiedSources::new().with_domain("verified.domain.com"),
+        )
+        .unwrap()
+        .render_request()
+        .unwrap();
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.uri(), URL);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(req.body()).unwrap(),
+            serde_json::json!({
+                "post_info": {
+                    "title": "funny cat",
+                    "privacy_level": "SELF_ONLY",
+                    "disable_comment": false,
+                    "auto_add_music": false
+                },
+                "source_info": {
+                    "source": "PULL_FROM_URL",
+                    "photo_images": [
+                        "https://example.verified.domain.com/1.jpg",
+                        "https://example.verified.domain.com/2.jpg"
+                    ],
+                    "photo_cover_index": 0
+                },
+                "post_mode": "DIRECT_POST",
+                "media_type": "PHOTO"
+            })
+        );
+    }
+
+    #[test]
+    fn test_new_verified_sources() {
+        let verified_sources = VerifiedSources::new().with_url_prefix(
+            "https://example.verified.domain.com/photos/"
+                .parse()
+                .unwrap(),
+        );
+
+        match PhotoPostInitEndpoint::new(
+            "TOKEN",
+            PhotoPostInfo::default(),
+            source_info(&[
+                "https://example.verified.domain.com/photos/1.jpg",
+                "https://example.verified.domain.com/other/2.jpg",
+            ]),
+            PostMode::MediaUpload,
+            &verified_sources,
+        ) {
+            Err(UnverifiedUrl::Unverified { url, closest }) => {
+                assert_eq!(url.path(), "/other/2.jpg");
+                assert_eq!(
+                    closest.as_deref(),
+                    Some("https://example.verified.domain.com/photos/")
+                );
+            }
+            x => panic!("{x:?}"),
+        }
+
+        match PhotoPostInitEndpoint::new(
+            "TOKEN",
+            PhotoPostInfo::default(),
+            source_info(&["http://example.verified.domain.com/photos/1.jpg"]),
+            PostMode::MediaUpload,
+            &verified_sources,
+        ) {
+            Err(UnverifiedUrl::HttpsRequired { .. }) => {}
+            x => panic!("{x:?}"),
+        }
+    }
+
+    #[test]
+    fn test_de_response_body() {
+        match serde_json::from_str::<PhotoPostInitResponseBody>(include_str!(
+            "../../../tests/response_body_files/v2/photo_post_init.json"
+        )) {
+            Ok(ok_json) => assert_eq!(ok_json.data.publish_id, "p_pub_url~v2.123456789"),
+            x => panic!("{x:?}"),
+        }
+    }
+}
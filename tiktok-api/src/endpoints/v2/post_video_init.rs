@@ -0,0 +1,215 @@
+use http_api_client_endpoint::{
+    http::{
+        header::{ACCEPT, AUTHORIZATION, USER_AGENT},
+        Method,
+    },
+    Body, Endpoint, Request, Response,
+};
+use serde::{Deserialize, Serialize};
+use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
+
+use super::{
+    common::{endpoint_parse_response, EndpointError, EndpointRet},
+    video_upload_init::{VideoUploadInitRequestBodySourceInfo, VideoUploadInitResponseBody},
+};
+use crate::verified_sources::{UnverifiedUrl, VerifiedSources};
+
+//
+/// Direct post, TikTok publishes the video once the upload is processed.
+pub const URL: &str = "https://open.tiktokapis.com/v2/post/publish/video/init/";
+
+//
+#[derive(Debug, Clone)]
+pub struct PostVideoInitEndpoint {
+    pub access_token: String,
+    pub post_info: PostInfo,
+    pub source_info: VideoUploadInitRequestBodySourceInfo,
+}
+impl PostVideoInitEndpoint {
+    /// Rejects a `PULL_FROM_URL` source that TikTok would fail with `url_ownership_unverified`,
+    /// before any request is sent.
+    pub fn new(
+        access_token: impl AsRef<str>,
+        post_info: PostInfo,
+        source_info: VideoUploadInitRequestBodySourceInfo,
+        verified_sources: &VerifiedSources,
+    ) -> Result<Self, UnverifiedUrl> {
+        source_info.verify(verified_sources)?;
+
+        Ok(Self {
+            access_token: access_token.as_ref().into(),
+            post_info,
+            source_info,
+        })
+    }
+
+    /// A `FILE_UPLOAD` source, which needs no verified source.
+    pub fn file_upload(
+        access_token: impl AsRef<str>,
+        post_info: PostInfo,
+        video_size: usize,
+        chunk_size: Option<usize>,
+    ) -> Self {
+        Self {
+            access_token: access_token.as_ref().into(),
+            post_info,
+            source_info: VideoUploadInitRequestBodySourceInfo::file_upload(video_size, chunk_size),
+        }
+    }
+}
+
+impl Endpoint for PostVideoInitEndpoint {
+    type RenderRequestError = EndpointError;
+
+    type ParseResponseOutput = EndpointRet<PostVideoInitResponseBody>;
+    type ParseResponseError = EndpointError;
+
+    fn render_request(&self) -> Result<Request<Body>, Self::RenderRequestError> {
+        let request_body = PostVideoInitRequestBody {
+            post_info: self.post_info.to_owned(),
+            source_info: self.source_info.to_owned(),
+        };
+        let request_body =
+            serde_json::to_vec(&request_body).map_err(EndpointError::SerRequestBodyFailed)?;
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(URL)
+            .header(AUTHORIZATION, format!("Bearer {}", &self.access_token))
+            .header(USER_AGENT, "tiktok-api")
+            .header(ACCEPT, "application/json; charset=UTF-8")
+            .body(request_body)
+            .map_err(EndpointError::MakeRequestFailed)?;
+
+        Ok(request)
+    }
+
+    fn parse_response(
+        &self,
+        response: Response<Body>,
+    ) -> Result<Self::ParseResponseOutput, Self::ParseResponseError> {
+        endpoint_parse_response(response)
+    }
+}
+
+//
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PostVideoInitRequestBody {
+    pub post_info: PostInfo,
+    pub source_info: VideoUploadInitRequestBodySourceInfo,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PostInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Must be one of the creator's `privacy_level_options`.
+    pub privacy_level: PrivacyLevel,
+    #[serde(default)]
+    pub disable_duet: bool,
+    #[serde(default)]
+    pub disable_comment: bool,
+    #[serde(default)]
+    pub disable_stitch: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_cover_timestamp_ms: Option<u64>,
+}
+impl PostInfo {
+    pub fn new(privacy_level: PrivacyLevel) -> Self {
+        Self {
+            title: None,
+            privacy_level,
+            disable_duet: false,
+            disable_comment: false,
+            disable_stitch: false,
+            video_cover_timestamp_ms: None,
+        }
+    }
+
+    pub fn with_title(mut self, title: impl AsRef<str>) -> Self {
+        self.title = Some(title.as_ref().into());
+        self
+    }
+}
+
+#[derive(Deserialize_enum_str, Serialize_enum_str, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PrivacyLevel {
+    PublicToEveryone,
+    MutualFollowFriends,
+    FollowerOfCreator,
+    SelfOnly,
+    #[serde(other)]
+    Other(Box<str>),
+}
+
+/// Same shape as the inbox init.
+pub type PostVideoInitResponseBody = VideoUploadInitResponseBody;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_request() {
+        let req = PostVideoInitEndpoint::file_upload(
+            "TOKEN",
+            PostInfo::new(PrivacyLevel::SelfOnly).with_title("this will be a funny #cat video"),
+            50000123,
+            Some(10000000),
+        )
+        .render_request()
+        .unwrap();
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.uri(), URL);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(req.body()).unwrap(),
+            serde_json::json!({
+                "post_info": {
+                    "title": "this will be a funny #cat video",
+                    "privacy_level": "SELF_ONLY",
+                    "disable_duet": false,
+                    "disable_comment": false,
+                    "disable_stitch": false
+                },
+                "source_info": {
+                    "source": "FILE_UPLOAD",
+                    "video_size": 50000123,
+                    "chunk_size": 10000000,
+                    "total_chunk_count": 5
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_new_verified_sources() {
+        let verified_sources = VerifiedSources::new().with_domain("verified.domain.com");
+
+        assert!(PostVideoInitEndpoint::new(
+            "TOKEN",
+            PostInfo::new(PrivacyLevel::SelfOnly),
+            VideoUploadInitRequestBodySourceInfo::PullFromUrl {
+                video_url: "https://example.verified.domain.com/example_video.mp4"
+                    .parse()
+                    .unwrap(),
+            },
+            &verified_sources,
+        )
+        .is_ok());
+
+        match PostVideoInitEndpoint::new(
+            "TOKEN",
+            PostInfo::new(PrivacyLevel::SelfOnly),
+            VideoUploadInitRequestBodySourceInfo::PullFromUrl {
+                video_url: "http://example.verified.domain.com/example_video.mp4"
+                    .parse()
+                    .unwrap(),
+            },
+            &verified_sources,
+        ) {
+            Err(UnverifiedUrl::HttpsRequired { .. }) => {}
+            x => panic!("{x:?}"),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use http_api_client_endpoint::{
+    http::{
+        header::{ACCEPT, AUTHORIZATION, USER_AGENT},
+        Method,
+    },
+    Body, Endpoint, Request, Response,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::common::{endpoint_parse_response, EndpointError, EndpointRet};
+
+//
+pub const URL: &str = "https://open.tiktokapis.com/v2/post/publish/cancel/";
+
+//
+#[derive(Debug, Clone)]
+pub struct PublishCancelEndpoint {
+    pub access_token: String,
+    pub publish_id: String,
+}
+impl PublishCancelEndpoint {
+    pub fn new(access_token: impl AsRef<str>, publish_id: impl AsRef<str>) -> Self {
+        Self {
+            access_token: access_token.as_ref().into(),
+            publish_id: publish_id.as_ref().into(),
+        }
+    }
+}
+
+impl Endpoint for PublishCancelEndpoint {
+    type RenderRequestError = EndpointError;
+
+    type ParseResponseOutput = EndpointRet<PublishCancelResponseBody>;
+    type ParseResponseError = EndpointError;
+
+    fn render_request(&self) -> Result<Request<Body>, Self::RenderRequestError> {
+        let request_body = PublishCancelRequestBody {
+            publish_id: self.publish_id.to_owned(),
+        };
+        let request_body =
+            serde_json::to_vec(&request_body).map_err(EndpointError::SerRequestBodyFailed)?;
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(URL)
+            .header(AUTHORIZATION, format!("Bearer {}", &self.access_token))
+            .header(USER_AGENT, "tiktok-api")
+            .header(ACCEPT, "application/json; charset=UTF-8")
+            .body(request_body)
+            .map_err(EndpointError::MakeRequestFailed)?;
+
+        Ok(request)
+    }
+
+    fn parse_response(
+        &self,
+        response: Response<Body>,
+    ) -> Result<Self::ParseResponseOutput, Self::ParseResponseError> {
+        endpoint_parse_response(response)
+    }
+}
+
+//
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishCancelRequestBody {
+    pub publish_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishCancelResponseBody {
+    #[serde(default)]
+    pub data: Map<String, Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_request() {
+        let req = PublishCancelEndpoint::new("TOKEN", "v_inbox_file~v2.123456789")
+            .render_request()
+            .unwrap();
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.uri(), URL);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(req.body()).unwrap(),
+            serde_json::json!({
+                "publish_id": "v_inbox_file~v2.123456789"
+            })
+        );
+    }
+
+    #[test]
+    fn test_de_response_body() {
+        match serde_json::from_str::<PublishCancelResponseBody>(include_str!(
+            "../../../tests/response_body_files/v2/publish_cancel.json"
+        )) {
+            Ok(ok_json) => {
+                assert!(ok_json.data.is_empty());
+            }
+            x => panic!("{x:?}"),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use http_api_client_endpoint::{
+    http::{
+        header::{ACCEPT, AUTHORIZATION, USER_AGENT},
+        Method,
+    },
+    Body, Endpoint, Request, Response,
+};
+use serde::{Deserialize, Serialize};
+use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
+
+use super::common::{endpoint_parse_response, EndpointError, EndpointRet};
+
+//
+pub const URL: &str = "https://open.tiktokapis.com/v2/post/publish/status/fetch/";
+
+//
+#[derive(Debug, Clone)]
+pub struct PublishStatusFetchEndpoint {
+    pub access_token: String,
+    pub publish_id: String,
+}
+impl PublishStatusFetchEndpoint {
+    pub fn new(access_token: impl AsRef<str>, publish_id: impl AsRef<str>) -> Self {
+        Self {
+            access_token: access_token.as_ref().into(),
+            publish_id: publish_id.as_ref().into(),
+        }
+    }
+}
+
+impl Endpoint for PublishStatusFetchEndpoint {
+    type RenderRequestError = EndpointError;
+
+    type ParseResponseOutput = EndpointRet<PublishStatusFetchResponseBody>;
+    type ParseResponseError = EndpointError;
+
+    fn render_request(&self) -> Result<Request<Body>, Self::RenderRequestError> {
+        let request_body = PublishStatusFetchRequestBody {
+            publish_id: self.publish_id.to_owned(),
+        };
+        let request_body =
+            serde_json::to_vec(&request_body).map_err(EndpointError::SerRequestBodyFailed)?;
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(URL)
+            .header(AUTHORIZATION, format!("Bearer {}", &self.access_token))
+            .header(USER_AGENT, "tiktok-api")
+            .header(ACCEPT, "application/json; charset=UTF-8")
+            .body(request_body)
+            .map_err(EndpointError::MakeRequestFailed)?;
+
+        Ok(request)
+    }
+
+    fn parse_response(
+        &self,
+        response: Response<Body>,
+    ) -> Result<Self::ParseResponseOutput, Self::ParseResponseError> {
+        endpoint_parse_response(response)
+    }
+}
+
+//
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishStatusFetchRequestBody {
+    pub publish_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishStatusFetchResponseBody {
+    pub data: PublishStatusFetchResponseBodyData,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishStatusFetchResponseBodyData {
+    pub status: PublishStatus,
+    pub fail_reason: Option<String>,
+    /// Spelled as TikTok sends it.
+    #[serde(default)]
+    pub publicaly_available_post_id: Vec<u64>,
+    pub uploaded_bytes: Option<u64>,
+    pub downloaded_bytes: Option<u64>,
+}
+
+#[derive(Deserialize_enum_str, Serialize_enum_str, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PublishStatus {
+    ProcessingUpload,
+    ProcessingDownload,
+    SendToUserInbox,
+    PublishComplete,
+    Failed,
+    #[serde(other)]
+    Other(Box<str>),
+}
+
+impl PublishStatus {
+    /// `SEND_TO_USER_INBOX` is final for inbox uploads, the user finishes the post in the app.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Self::SendToUserInbox | Self::PublishComplete | Self::Failed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_request() {
+        let req = PublishStatusFetchEndpoint::new("TOKEN", "v_pub_file~v2.123456789")
+            .render_request()
+            .unwrap();
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.uri(), URL);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(req.body()).unwrap(),
+            serde_json::json!({
+                "publish_id": "v_pub_file~v2.123456789"
+            })
+        );
+    }
+
+    #[test]
+    fn test_de_response_body() {
+        match serde_json::from_str::<PublishStatusFetchResponseBody>(include_str!(
+            "../../../tests/response_body_files/v2/publish_status_fetch.json"
+        )) {
+            Ok(ok_json) => {
+                assert_eq!(ok_json.data.status, PublishStatus::PublishComplete);
+                assert_eq!(
+                    ok_json.data.publicaly_available_post_id,
+                    vec![7182846487765412654]
+                );
+                assert_eq!(ok_json.data.uploaded_bytes, Some(10000));
+            }
+            x => panic!("{x:?}"),
+        }
+
+        match serde_json::from_str::<PublishStatusFetchResponseBody>(include_str!(
+            "../../../tests/response_body_files/v2/publish_status_fetch__failed.json"
+        )) {
+            Ok(ok_json) => {
+                assert_eq!(ok_json.data.status, PublishStatus::Failed);
+                assert!(ok_json.data.status.is_terminal());
+                assert_eq!(
+                    ok_json.data.fail_reason.as_deref(),
+                    Some("file_format_check_failed")
+                );
+            }
+            x => panic!("{x:?}"),
+        }
+    }
+}
@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use super::common::{endpoint_parse_response, EndpointError, EndpointRet};
-use crate::objects::v2::{Error, User};
+use crate::objects::v2::User;
 
 //
 pub const URL: &str = "https://open.tiktokapis.com/v2/user/info/";
@@ -69,7 +69,6 @@ impl Endpoint for UserInfoEndpoint {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UserInfoResponseBody {
     pub data: UserInfoResponseBodyData,
-    pub error: Error,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -81,8 +80,6 @@ pub struct UserInfoResponseBodyData {
 mod tests {
     use super::*;
 
-    use crate::objects::v2::ErrorCode;
-
     #[test]
     fn test_render_request() {
         let req = UserInfoEndpoint::new("TOKEN").render_request().unwrap();
@@ -109,7 +106,6 @@ mod tests {
                     ok_json.data.user.open_id,
                     Some("_000fwZ23Mw4RY9cB4lDQyKCgQg4Ft6SyTuE".into())
                 );
-                assert_eq!(ok_json.error.code, ErrorCode::Ok);
             }
             x => panic!("{x:?}"),
         }
@@ -123,7 +119,6 @@ mod tests {
                     ok_json.data.user.open_id,
                     Some("723f24d7-e717-40f8-a2b6-cb8464cd23b4".into())
                 );
-                assert_eq!(ok_json.error.code, ErrorCode::Ok);
             }
             x => panic!("{x:?}"),
         }
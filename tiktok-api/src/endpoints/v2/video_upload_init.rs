@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use http_api_client_endpoint::{
     http::{
         header::{ACCEPT, AUTHORIZATION, USER_AGENT},
@@ -9,25 +10,52 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use super::common::{endpoint_parse_response, EndpointError, EndpointRet};
-use crate::objects::v2::Error;
+use crate::verified_sources::{UnverifiedUrl, VerifiedSources};
 
 //
 pub const URL: &str = "https://open.tiktokapis.com/v2/post/publish/inbox/video/init/";
 
+/// `upload_url` is valid for about an hour after `/inbox/video/init/`.
+pub const UPLOAD_URL_LIFETIME_SECS: i64 = 60 * 60;
+
 //
 #[derive(Debug, Clone)]
 pub struct VideoUploadInitEndpoint {
     pub access_token: String,
     pub source_info: VideoUploadInitRequestBodySourceInfo,
+    /// Set by `with_file`, pass it to `UploadOptions::with_expected_fingerprint`.
+    #[cfg(feature = "with_tokio_fs")]
+    pub file_fingerprint: Option<crate::media_transfer::FileFingerprint>,
 }
 impl VideoUploadInitEndpoint {
+    /// Rejects a `PULL_FROM_URL` source that TikTok would fail with `url_ownership_unverified`,
+    /// before any request is sent.
     pub fn new(
         access_token: impl AsRef<str>,
         source_info: VideoUploadInitRequestBodySourceInfo,
+        verified_sources: &VerifiedSources,
+    ) -> Result<Self, UnverifiedUrl> {
+        source_info.verify(verified_sources)?;
+
+        Ok(Self {
+            access_token: access_token.as_ref().into(),
+            source_info,
+            #[cfg(feature = "with_tokio_fs")]
+            file_fingerprint: None,
+        })
+    }
+
+    /// A `FILE_UPLOAD` source, which needs no verified source.
+    pub fn file_upload(
+        access_token: impl AsRef<str>,
+        video_size: usize,
+        chunk_size: Option<usize>,
     ) -> Self {
         Self {
             access_token: access_token.as_ref().into(),
-            source_info,
+            source_info: VideoUploadInitRequestBodySourceInfo::file_upload(video_size, chunk_size),
+            #[cfg(feature = "with_tokio_fs")]
+            file_fingerprint: None,
         }
     }
 
@@ -37,31 +65,37 @@ impl VideoUploadInitEndpoint {
         file_path: &std::path::PathBuf,
         chunk_size: Option<usize>,
     ) -> Result<Self, EndpointError> {
-        use crate::media_transfer::{get_chunk_size_and_total_chunk_count, CHUNK_SIZE_MAX};
-
-        let crate::tokio_fs_util::Info {
-            file_size,
-            file_name: _,
-        } = crate::tokio_fs_util::info(file_path)
+        let file_fingerprint = crate::media_transfer::FileFingerprint::from_file(file_path)
             .await
             .map_err(EndpointError::GetFileInfoFailed)?;
 
-        let video_size = file_size as usize;
-        let (chunk_size, total_chunk_count) =
-            get_chunk_size_and_total_chunk_count(video_size, chunk_size.unwrap_or(CHUNK_SIZE_MAX));
-
-        //
-        let source_info = VideoUploadInitRequestBodySourceInfo::FileUpload {
-            video_size,
-            chunk_size,
-            total_chunk_count,
-        };
+        // Rejects unsupported containers before the init burns a publish slot.
+        crate::media_type::sniff_file(file_path)
+            .await
+            .map_err(EndpointError::SniffFailed)?;
 
         Ok(Self {
             access_token: access_token.as_ref().into(),
-            source_info,
+            source_info: VideoUploadInitRequestBodySourceInfo::file_upload(
+                file_fingerprint.size as usize,
+                chunk_size,
+            ),
+            file_fingerprint: Some(file_fingerprint),
         })
     }
+
+    #[cfg(feature = "with_tokio_fs")]
+    pub fn with_spooled(
+        access_token: impl AsRef<str>,
+        spooled_video: &crate::media_transfer::SpooledVideo,
+        chunk_size: Option<usize>,
+    ) -> Self {
+        Self {
+            access_token: access_token.as_ref().into(),
+            source_info: spooled_video.source_info(chunk_size),
+            file_fingerprint: None,
+        }
+    }
 }
 
 impl Endpoint for VideoUploadInitEndpoint {
@@ -115,37 +149,65 @@ pub enum VideoUploadInitRequestBodySourceInfo {
     #[serde(rename = "PULL_FROM_URL")]
     PullFromUrl { video_url: Url },
 }
+impl VideoUploadInitRequestBodySourceInfo {
+    pub fn file_upload(video_size: usize, chunk_size: Option<usize>) -> Self {
+        use crate::media_transfer::{get_chunk_size_and_total_chunk_count, CHUNK_SIZE_MAX};
+
+        let (chunk_size, total_chunk_count) =
+            get_chunk_size_and_total_chunk_count(video_size, chunk_size.unwrap_or(CHUNK_SIZE_MAX));
+
+        Self::FileUpload {
+            video_size,
+            chunk_size,
+            total_chunk_count,
+        }
+    }
+
+    /// Rejects a `PULL_FROM_URL` source that TikTok would fail with `url_ownership_unverified`.
+    pub fn verify(&self, verified_sources: &VerifiedSources) -> Result<(), UnverifiedUrl> {
+        match self {
+            Self::FileUpload { .. } => Ok(()),
+            Self::PullFromUrl { video_url } => verified_sources.verify(video_url),
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VideoUploadInitResponseBody {
     pub data: VideoUploadInitResponseBodyData,
-    pub error: Error,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VideoUploadInitResponseBodyData {
     pub publish_id: String,
     pub upload_url: Option<Url>,
+    /// Not sent by TikTok, estimated as `UPLOAD_URL_LIFETIME_SECS` after the response is decoded.
+    #[serde(default = "upload_url_expires_at_default")]
+    pub upload_url_expires_at: DateTime<Utc>,
+}
+impl VideoUploadInitResponseBodyData {
+    pub fn upload_url_remaining(&self) -> chrono::Duration {
+        self.upload_url_expires_at - Utc::now()
+    }
+
+    pub fn is_upload_url_expired(&self) -> bool {
+        self.upload_url_remaining() <= chrono::Duration::zero()
+    }
+}
+
+fn upload_url_expires_at_default() -> DateTime<Utc> {
+    Utc::now() + chrono::Duration::seconds(UPLOAD_URL_LIFETIME_SECS)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::objects::v2::ErrorCode;
-
     #[test]
     fn test_render_request() {
-        let req = VideoUploadInitEndpoint::new(
-            "TOKEN",
-            VideoUploadInitRequestBodySourceInfo::FileUpload {
-                video_size: 30567100,
-                chunk_size: 30567100,
-                total_chunk_count: 1,
-            },
-        )
-        .render_request()
-        .unwrap();
+        let req = VideoUploadInitEndpoint::file_upload("TOKEN", 30567100, Some(30567100))
+            .render_request()
+            .unwrap();
         assert_eq!(req.method(), Method::POST);
         assert_eq!(req.uri(), URL);
         assert_eq!(
@@ -167,7 +229,9 @@ mod tests {
                     .parse()
                     .unwrap(),
             },
+            &VerifiedSources::new().with_domain("verified.domain.com"),
         )
+        .unwrap()
         .render_request()
         .unwrap();
         assert_eq!(req.method(), Method::POST);
@@ -183,6 +247,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_new_verified_sources() {
+        let verified_sources = VerifiedSources::new()
+            .with_url_prefix("https://example.verified.domain.com/".parse().unwrap());
+
+        assert!(VideoUploadInitEndpoint::new(
+            "TOKEN",
+            VideoUploadInitRequestBodySourceInfo::PullFromUrl {
+                video_url: "https://example.verified.domain.com/example_video.mp4"
+                    .parse()
+                    .unwrap(),
+            },
+            &verified_sources,
+        )
+        .is_ok());
+        assert!(VideoUploadInitEndpoint::new(
+            "TOKEN",
+            VideoUploadInitRequestBodySourceInfo::file_upload(1024, None),
+            &verified_sources,
+        )
+        .is_ok());
+
+        match VideoUploadInitEndpoint::new(
+            "TOKEN",
+            VideoUploadInitRequestBodySourceInfo::PullFromUrl {
+                video_url: "https://example.unverified.domain.com/example_video.mp4"
+                    .parse()
+                    .unwrap(),
+            },
+            &verified_sources,
+        ) {
+            Err(UnverifiedUrl::Unverified { closest, .. }) => {
+                assert_eq!(
+                    closest.as_deref(),
+                    Some("https://example.verified.domain.com/")
+                );
+            }
+            x => panic!("{x:?}"),
+        }
+    }
+
     #[test]
     fn test_de_response_body() {
         match serde_json::from_str::<VideoUploadInitResponseBody>(include_str!(
@@ -194,7 +299,11 @@ mod tests {
                     ok_json.data.upload_url,
                     Some("https://open-upload.tiktokapis.com/video/?upload_id=67890&upload_token=Xza123".parse().unwrap())
                 );
-                assert_eq!(ok_json.error.code, ErrorCode::Ok);
+                assert!(!ok_json.data.is_upload_url_expired());
+                assert!(
+                    ok_json.data.upload_url_remaining()
+                        > chrono::Duration::seconds(UPLOAD_URL_LIFETIME_SECS - 60)
+                );
             }
             x => panic!("{x:?}"),
         }
@@ -206,9 +315,38 @@ mod tests {
             Ok(ok_json) => {
                 assert_eq!(ok_json.data.publish_id, "v_inbox_file~v2.123456789");
                 assert!(ok_json.data.upload_url.is_none());
-                assert_eq!(ok_json.error.code, ErrorCode::Ok);
             }
             x => panic!("{x:?}"),
         }
     }
+
+    #[cfg(feature = "with_tokio_fs")]
+    #[tokio::test]
+    async fn test_with_file() -> Result<(), Box<dyn std::error::Error>> {
+        let file_path = std::env::temp_dir().join(format!(
+            "tiktok-api-video-upload-init-with-file-{}.mp4",
+            std::process::id()
+        ));
+        let mut data = b"\0\0\0\x18ftypisom\0\0\x02\0isomiso2".to_vec();
+        data.resize(1024, 0);
+        std::fs::write(&file_path, &data)?;
+
+        let ret = VideoUploadInitEndpoint::with_file("TOKEN", &file_path, None).await;
+        std::fs::remove_file(&file_path)?;
+        let endpoint = ret?;
+        assert_eq!(endpoint.file_fingerprint.map(|x| x.size), Some(1024));
+        match endpoint.source_info {
+            VideoUploadInitRequestBodySourceInfo::FileUpload { video_size, .. } => {
+                assert_eq!(video_size, 1024)
+            }
+            x => panic!("{x:?}"),
+        }
+
+        match VideoUploadInitEndpoint::with_file("TOKEN", &std::env::temp_dir(), None).await {
+            Err(EndpointError::GetFileInfoFailed(_)) => {}
+            x => panic!("{x:?}"),
+        }
+
+        Ok(())
+    }
 }
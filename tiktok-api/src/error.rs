@@ -0,0 +1,338 @@
+use http_api_client_endpoint::{http::StatusCode, Body};
+
+use crate::{
+    endpoints::{oauth::common::EndpointError as OauthEndpointError, v2::common::EndpointError},
+    objects::{
+        oauth::{LegacyErrorCode, ResponseErrorBody as OauthResponseErrorBody},
+        v2::{ErrorCode, ResponseErrorBody},
+    },
+};
+
+//
+//
+//
+/// One error for the whole crate, match on `kind`. The module's own error is kept in `source`.
+#[derive(Debug)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub source: ErrorSource,
+}
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.source {
+            ErrorSource::OauthEndpoint(err) => Some(err),
+            ErrorSource::V2Endpoint(err) => Some(err),
+            #[cfg(feature = "with_media_transfer")]
+            ErrorSource::Upload(err) => Some(err),
+            #[cfg(feature = "with_media_transfer")]
+            ErrorSource::ShareVideoUpload(err) => Some(err),
+            ErrorSource::Respond(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    /// TikTok's request id, ask for it when reporting to TikTok.
+    pub fn log_id(&self) -> Option<&str> {
+        match &self.source {
+            ErrorSource::V2Response { body: Ok(body), .. } => Some(&body.error.log_id),
+            #[cfg(feature = "with_media_transfer")]
+            ErrorSource::ShareResponse(body) => Some(&body.extra.logid),
+            _ => None,
+        }
+    }
+}
+
+impl From<ErrorSource> for Error {
+    fn from(source: ErrorSource) -> Self {
+        Self {
+            kind: source.kind(),
+            source,
+        }
+    }
+}
+
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The access token or refresh token is invalid, expired or revoked.
+    Auth,
+    /// The user did not grant a scope the call needs.
+    Scope,
+    RateLimit,
+    /// Rejected as sent, including a video TikTok does not accept, or a user it does not let post.
+    InvalidParams,
+    /// Reading the video or sending its bytes failed.
+    Upload,
+    /// No response, e.g. connection or TLS failures.
+    Transport,
+    /// The response could not be decoded.
+    Decode,
+    /// TikTok failed, or answered with an error this crate does not know.
+    Server,
+}
+
+impl ErrorKind {
+    /// For error bodies without a known code.
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => Self::Auth,
+            StatusCode::FORBIDDEN => Self::Scope,
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimit,
+            x if x.is_client_error() => Self::InvalidParams,
+            _ => Self::Server,
+        }
+    }
+
+    pub fn from_v2_error_code(code: &ErrorCode, status: StatusCode) -> Self {
+        match code {
+            ErrorCode::AccessTokenInvalid
+            | ErrorCode::TokenNotAuthorizedForSpecifiedDeviceid
+            | ErrorCode::AuthRemoved => Self::Auth,
+            ErrorCode::ScopeNotAuthorized | ErrorCode::ScopePermissionMissed => Self::Scope,
+            ErrorCode::RateLimitExceeded
+            | ErrorCode::SpamRiskTooManyPendingShare
+            | ErrorCode::SpamRiskTooManyPosts
+            | ErrorCode::ReachedActiveUserCap => Self::RateLimit,
+            ErrorCode::InvalidParams
+            | ErrorCode::InvalidFileUpload
+            | ErrorCode::SpamRisk
+            | ErrorCode::SpamRiskText
+            | ErrorCode::SpamRiskUserBannedFromPosting
+            | ErrorCode::UnauditedClientCanOnlyPostToPrivateAccounts
+            | ErrorCode::UrlOwnershipUnverified
+            | ErrorCode::PrivacyLevelOptionMismatch
+            | ErrorCode::FileFormatCheckFailed
+            | ErrorCode::DurationCheckFailed
+            | ErrorCode::FrameRateCheckFailed
+            | ErrorCode::PictureSizeCheckFailed => Self::InvalidParams,
+            ErrorCode::VideoPullFailed
+            | ErrorCode::PhotoPullFailed
+            | ErrorCode::PublishCancelled => Self::Upload,
+            ErrorCode::InternalError => Self::Server,
+            ErrorCode::Ok | ErrorCode::Other(_) => Self::from_status(status),
+        }
+    }
+
+    /// The oauth `error_code`, and the share `err_code`.
+    pub fn from_legacy_error_code(code: &LegacyErrorCode) -> Self {
+        match code {
+            LegacyErrorCode::InvalidParams => Self::InvalidParams,
+            LegacyErrorCode::AccessTokenInvalid
+            | LegacyErrorCode::RefreshTokenInvalid
+            | LegacyErrorCode::ShareAccessTokenInvalid => Self::Auth,
+            LegacyErrorCode::Ok | LegacyErrorCode::Unknown(_) => Self::Server,
+        }
+    }
+}
+
+//
+#[derive(Debug)]
+pub enum ErrorSource {
+    OauthEndpoint(OauthEndpointError),
+    /// `endpoints::oauth::EndpointRet::Other`
+    OauthResponse {
+        status: StatusCode,
+        body: Result<Box<OauthResponseErrorBody>, Body>,
+    },
+    V2Endpoint(EndpointError),
+    /// `endpoints::v2::EndpointRet::Other`
+    V2Response {
+        status: StatusCode,
+        body: Result<Box<ResponseErrorBody>, Result<String, Body>>,
+    },
+    #[cfg(feature = "with_media_transfer")]
+    Upload(crate::media_transfer::UploadError),
+    #[cfg(feature = "with_media_transfer")]
+    ShareVideoUpload(crate::endpoints::share::video_upload::VideoUploadError),
+    /// A share response with a non-zero `err_code`.
+    #[cfg(feature = "with_media_transfer")]
+    ShareResponse(Box<crate::endpoints::share::video_upload::VideoUploadResponseBody>),
+    /// The http client's own error.
+    Respond(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl ErrorSource {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::OauthEndpoint(err) => match err {
+                OauthEndpointError::MakeRequestUrlFailed(_)
+                | OauthEndpointError::MakeRequestFailed(_) => ErrorKind::InvalidParams,
+                OauthEndpointError::DeResponseBodyFailed(_) => ErrorKind::Decode,
+            },
+            Self::OauthResponse { status, body } => match body {
+                Ok(body) => ErrorKind::from_legacy_error_code(&body.data.error_code),
+                Err(_) => ErrorKind::from_status(*status),
+            },
+            Self::V2Endpoint(err) => match err {
+                EndpointError::MakeRequestUrlFailed(_)
+                | EndpointError::SerRequestBodyFailed(_)
+                | EndpointError::MakeRequestFailed(_) => ErrorKind::InvalidParams,
+                EndpointError::DeResponseBodyFailed(_) => ErrorKind::Decode,
+                #[cfg(feature = "with_tokio_fs")]
+                EndpointError::GetFileInfoFailed(_) => ErrorKind::Upload,
+                #[cfg(feature = "with_tokio_fs")]
+                EndpointError::SniffFailed(_) => ErrorKind::InvalidParams,
+            },
+            Self::V2Response { status, body } => match body {
+                Ok(body) => ErrorKind::from_v2_error_code(&body.error.code, *status),
+                Err(_) => ErrorKind::from_status(*status),
+            },
+            #[cfg(feature = "with_media_transfer")]
+            Self::Upload(err) => {
+                use crate::media_transfer::UploadError;
+
+                match err {
+                    UploadError::ContentTypeInvalid
+                    | UploadError::VideoEmpty
+                    | UploadError::ByteRangeInvalid(_)
+                    | UploadError::ChunkSizeTooSmaillOrFileTooLarge => ErrorKind::InvalidParams,
+                    UploadError::RespondFailed(_, _) => ErrorKind::Transport,
+                    UploadError::ResponseMismatch {
+                        status: StatusCode::TOO_MANY_REQUESTS,
+                        ..
+                    } => ErrorKind::RateLimit,
+                    _ => ErrorKind::Upload,
+                }
+            }
+            #[cfg(feature = "with_media_transfer")]
+            Self::ShareVideoUpload(err) => {
+                use crate::endpoints::share::video_upload::VideoUploadError;
+
+                match err {
+                    VideoUploadError::MakeRequestUrlFailed(_) => ErrorKind::InvalidParams,
+                    VideoUploadError::RespondFailed(_) => ErrorKind::Transport,
+                    VideoUploadError::DeResponseBodyFailed(_) => ErrorKind::Decode,
+                    _ => ErrorKind::Upload,
+                }
+            }
+            #[cfg(feature = "with_media_transfer")]
+            Self::ShareResponse(body) => ErrorKind::from_legacy_error_code(&body.data.err_code),
+            Self::Respond(_) => ErrorKind::Transport,
+        }
+    }
+}
+
+//
+//
+//
+impl From<OauthEndpointError> for Error {
+    fn from(err: OauthEndpointError) -> Self {
+        ErrorSource::OauthEndpoint(err).into()
+    }
+}
+
+impl From<EndpointError> for Error {
+    fn from(err: EndpointError) -> Self {
+        ErrorSource::V2Endpoint(err).into()
+    }
+}
+
+#[cfg(feature = "with_media_transfer")]
+impl From<crate::media_transfer::UploadError> for Error {
+    fn from(err: crate::media_transfer::UploadError) -> Self {
+        ErrorSource::Upload(err).into()
+    }
+}
+
+#[cfg(feature = "with_media_transfer")]
+impl From<crate::endpoints::share::video_upload::VideoUploadError> for Error {
+    fn from(err: crate::endpoints::share::video_upload::VideoUploadError) -> Self {
+        ErrorSource::ShareVideoUpload(err).into()
+    }
+}
+
+#[cfg(feature = "http-api-client")]
+impl<RE, EPE> From<http_api_client::ClientRespondEndpointError<RE, EPE, EPE>> for Error
+where
+    RE: std::error::Error + Send + Sync + 'static,
+    EPE: std::error::Error + Send + Sync + 'static,
+    Error: From<EPE>,
+{
+    fn from(err: http_api_client::ClientRespondEndpointError<RE, EPE, EPE>) -> Self {
+        use http_api_client::ClientRespondEndpointError;
+
+        match err {
+            ClientRespondEndpointError::RespondFailed(err) => {
+                ErrorSource::Respond(Box::new(err)).into()
+            }
+            ClientRespondEndpointError::EndpointRenderRequestFailed(err)
+            | ClientRespondEndpointError::EndpointParseResponseFailed(err) => err.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::endpoints::{oauth, v2};
+
+    #[test]
+    fn test_from_endpoint_ret() {
+        let ret = serde_json::from_str::<ResponseErrorBody>(include_str!(
+            "../tests/response_body_files/v2/user_info__err__access_token_invalid.json"
+        ))
+        .unwrap();
+        let err = v2::EndpointRet::<()>::Other((StatusCode::UNAUTHORIZED, Ok(ret)))
+            .into_result()
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Auth);
+        assert!(err.log_id().is_some());
+
+        let ret = serde_json::from_str::<ResponseErrorBody>(include_str!(
+            "../tests/response_body_files/v2/video_upload_init__err__spam_risk_too_many_pending_share.json"
+        ))
+        .unwrap();
+        let err = v2::EndpointRet::<()>::Other((StatusCode::FORBIDDEN, Ok(ret)))
+            .into_result()
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::RateLimit);
+        // Not the token, the user can not post at all.
+        assert_eq!(
+            ErrorKind::from_v2_error_code(
+                &"spam_risk_user_banned_from_posting".parse().unwrap(),
+                StatusCode::FORBIDDEN
+            ),
+            ErrorKind::InvalidParams
+        );
+
+        let err = v2::EndpointRet::<()>::Other((
+            StatusCode::BAD_GATEWAY,
+            Err(Ok("<html></html>".into())),
+        ))
+        .into_result()
+        .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Server);
+
+        let ret = serde_json::from_str::<OauthResponseErrorBody>(include_str!(
+            "../tests/response_body_files/oauth/refresh_token__err_with_expired_refresh_token.json"
+        ))
+        .unwrap();
+        let err = oauth::EndpointRet::<()>::Other((StatusCode::OK, Ok(ret)))
+            .into_result()
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Auth);
+
+        assert!(matches!(v2::EndpointRet::Ok(1).into_result(), Ok(1)));
+    }
+
+    #[test]
+    fn test_question_mark() {
+        fn de() -> Result<u8, Error> {
+            let x = serde_json::from_slice(b"{")
+                .map_err(v2::common::EndpointError::DeResponseBodyFailed)?;
+            Ok(x)
+        }
+
+        let err = de().unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Decode);
+        assert!(std::error::Error::source(&err).is_some());
+    }
+}
@@ -0,0 +1,16 @@
+//
+#[cfg(any(feature = "with_media_transfer", feature = "with_webhook_receiver"))]
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{x:02x}")).collect()
+}
+
+#[cfg(feature = "with_webhook_signature")]
+pub(crate) fn decode(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -1,9 +1,31 @@
 //
+pub mod classify;
 pub mod endpoints;
+pub mod error;
+pub mod media_type;
 pub mod objects;
+pub mod verified_sources;
+pub mod webhooks;
 
-#[cfg(feature = "with_video_upload")]
+pub use classify::{classify, Classification};
+pub use error::{Error, ErrorKind};
+
+#[cfg(any(feature = "with_media_transfer", feature = "with_webhook_signature"))]
+mod hex;
+#[cfg(feature = "with_media_inspect")]
+pub mod media_inspect;
+#[cfg(feature = "with_media_transfer")]
 pub mod media_transfer;
+#[cfg(feature = "with_media_transfer")]
+pub mod publish;
+#[cfg(feature = "with_scheduler")]
+pub mod scheduler;
+#[cfg(feature = "http-api-client")]
+pub mod token_store;
+#[cfg(feature = "with_media_transfer")]
+pub mod transport;
+#[cfg(feature = "with_upload_queue")]
+pub mod upload_queue;
 
 #[cfg(feature = "with_tokio_fs")]
 pub mod tokio_fs_util;
@@ -0,0 +1,155 @@
+use core::time::Duration;
+
+use super::{MediaInfo, VideoCodec};
+
+// https://developers.tiktok.com/doc/content-posting-api-media-transfer-guide/#video_restrictions
+pub const PICTURE_SIZE_MIN: u32 = 360;
+pub const PICTURE_SIZE_MAX: u32 = 4096;
+pub const FRAME_RATE_MIN: f64 = 23.0;
+pub const FRAME_RATE_MAX: f64 = 60.0;
+
+//
+#[derive(Debug, Clone)]
+pub struct MediaLimits {
+    /// Usually `max_video_post_duration_sec` from the creator info, None skips the check.
+    pub max_duration: Option<Duration>,
+    pub picture_size_min: u32,
+    pub picture_size_max: u32,
+    pub frame_rate_min: f64,
+    pub frame_rate_max: f64,
+    pub codecs: Vec<VideoCodec>,
+}
+impl Default for MediaLimits {
+    fn default() -> Self {
+        Self {
+            max_duration: None,
+            picture_size_min: PICTURE_SIZE_MIN,
+            picture_size_max: PICTURE_SIZE_MAX,
+            frame_rate_min: FRAME_RATE_MIN,
+            frame_rate_max: FRAME_RATE_MAX,
+            codecs: vec![
+                VideoCodec::H264,
+                VideoCodec::H265,
+                VideoCodec::Vp8,
+                VideoCodec::Vp9,
+            ],
+        }
+    }
+}
+
+impl MediaLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_video_post_duration_sec(mut self, max_video_post_duration_sec: u64) -> Self {
+        self.max_duration = Some(Duration::from_secs(max_video_post_duration_sec));
+        self
+    }
+
+    pub fn with_codecs(mut self, codecs: Vec<VideoCodec>) -> Self {
+        self.codecs = codecs;
+        self
+    }
+
+    /// Returns every violated limit, not only the first.
+    pub fn validate(&self, info: &MediaInfo) -> Result<(), Vec<LimitViolation>> {
+        let mut violations = vec![];
+
+        if let Some(max_duration) = self.max_duration {
+            if info.duration > max_duration {
+                violations.push(LimitViolation::DurationTooLong {
+                    duration: info.duration,
+                    max_duration,
+                });
+            }
+        }
+
+        let picture_size_range = self.picture_size_min..=self.picture_size_max;
+        if !picture_size_range.contains(&info.width) || !picture_size_range.contains(&info.height) {
+            violations.push(LimitViolation::PictureSizeOutOfRange {
+                width: info.width,
+                height: info.height,
+            });
+        }
+
+        if let Some(fps) = info.fps {
+            if !(self.frame_rate_min..=self.frame_rate_max).contains(&fps) {
+                violations.push(LimitViolation::FrameRateOutOfRange { fps });
+            }
+        }
+
+        if !self.codecs.contains(&info.codec) {
+            violations.push(LimitViolation::CodecUnsupported(info.codec.to_owned()));
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+//
+#[derive(Debug, Clone, PartialEq)]
+pub enum LimitViolation {
+    DurationTooLong {
+        duration: Duration,
+        max_duration: Duration,
+    },
+    PictureSizeOutOfRange {
+        width: u32,
+        height: u32,
+    },
+    FrameRateOutOfRange {
+        fps: f64,
+    },
+    CodecUnsupported(VideoCodec),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let info = MediaInfo {
+            duration: Duration::from_secs(90),
+            width: 1080,
+            height: 1920,
+            fps: Some(30.0),
+            codec: VideoCodec::H264,
+        };
+
+        assert!(MediaLimits::default().validate(&info).is_ok());
+        assert_eq!(
+            MediaLimits::default()
+                .with_max_video_post_duration_sec(60)
+                .validate(&info)
+                .unwrap_err(),
+            vec![LimitViolation::DurationTooLong {
+                duration: Duration::from_secs(90),
+                max_duration: Duration::from_secs(60),
+            }]
+        );
+
+        let info = MediaInfo {
+            width: 320,
+            fps: Some(120.0),
+            codec: VideoCodec::Av1,
+            ..info
+        };
+        assert_eq!(
+            MediaLimits::default().validate(&info).unwrap_err(),
+            vec![
+                LimitViolation::PictureSizeOutOfRange {
+                    width: 320,
+                    height: 1920
+                },
+                LimitViolation::FrameRateOutOfRange { fps: 120.0 },
+                LimitViolation::CodecUnsupported(VideoCodec::Av1),
+            ]
+        );
+    }
+}
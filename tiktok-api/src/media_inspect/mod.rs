@@ -0,0 +1,454 @@
+use core::time::Duration;
+use std::io::{Read, Seek, SeekFrom};
+
+pub mod limits;
+
+pub use limits::{LimitViolation, MediaLimits};
+
+//
+/// `moov` is read into memory, anything larger is treated as broken.
+pub const MOOV_SIZE_MAX: u64 = 64 * 1024 * 1024;
+
+//
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaInfo {
+    pub duration: Duration,
+    pub width: u32,
+    pub height: u32,
+    /// None when the video track has no samples.
+    pub fps: Option<f64>,
+    pub codec: VideoCodec,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VideoCodec {
+    H264,
+    H265,
+    Vp8,
+    Vp9,
+    Av1,
+    /// The `stsd` sample entry type, e.g. `mp4v`.
+    Other(String),
+}
+impl VideoCodec {
+    fn from_fourcc(fourcc: &[u8]) -> Self {
+        match fourcc {
+            b"avc1" | b"avc3" => Self::H264,
+            b"hvc1" | b"hev1" => Self::H265,
+            b"vp08" => Self::Vp8,
+            b"vp09" => Self::Vp9,
+            b"av01" => Self::Av1,
+            x => Self::Other(String::from_utf8_lossy(x).into()),
+        }
+    }
+}
+
+//
+/// Reads the top level boxes of an ISO-BMFF (MP4/MOV) file, seeking over `mdat`.
+pub fn inspect<R>(reader: &mut R) -> Result<MediaInfo, InspectError>
+where
+    R: Read + Seek,
+{
+    let file_end = reader
+        .seek(SeekFrom::End(0))
+        .map_err(InspectError::ReadFailed)?;
+    let mut position = reader
+        .seek(SeekFrom::Start(0))
+        .map_err(InspectError::ReadFailed)?;
+
+    while file_end.saturating_sub(position) >= 8 {
+        let mut header = [0_u8; 16];
+        reader
+            .read_exact(&mut header[..8])
+            .map_err(InspectError::ReadFailed)?;
+
+        let kind = [header[4], header[5], header[6], header[7]];
+        let (header_len, size) =
+            match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+                0 => (8, file_end - position),
+                1 => {
+                    reader
+                        .read_exact(&mut header[8..16])
+                        .map_err(InspectError::ReadFailed)?;
+                    (
+                        16,
+                        u64::from_be_bytes(header[8..16].try_into().expect("8 bytes")),
+                    )
+                }
+                x => (8, x as u64),
+            };
+        let box_end = position
+            .checked_add(size)
+            .filter(|x| size >= header_len && *x <= file_end)
+            .ok_or(InspectError::BoxInvalid(kind))?;
+
+        if &kind == b"moov" {
+            let body_len = size - header_len;
+            if body_len > MOOV_SIZE_MAX {
+                return Err(InspectError::BoxInvalid(kind));
+            }
+            let mut body = vec![0; body_len as usize];
+            reader
+                .read_exact(&mut body)
+                .map_err(InspectError::ReadFailed)?;
+            return parse_moov(&body);
+        }
+
+        position = reader
+            .seek(SeekFrom::Start(box_end))
+            .map_err(InspectError::ReadFailed)?;
+    }
+
+    Err(InspectError::MoovMissing)
+}
+
+pub fn inspect_file(file_path: &std::path::Path) -> Result<MediaInfo, InspectError> {
+    let file = std::fs::File::open(file_path).map_err(InspectError::ReadFailed)?;
+    inspect(&mut std::io::BufReader::new(file))
+}
+
+//
+fn parse_moov(moov: &[u8]) -> Result<MediaInfo, InspectError> {
+    let mvhd = find_box(moov, b"mvhd")?.ok_or(InspectError::BoxMissing(*b"mvhd"))?;
+    let (timescale, duration) = match mvhd.first() {
+        Some(1) => (read_u32(mvhd, 20, b"mvhd")?, read_u64(mvhd, 24, b"mvhd")?),
+        _ => (
+            read_u32(mvhd, 12, b"mvhd")?,
+            read_u32(mvhd, 16, b"mvhd")? as u64,
+        ),
+    };
+    if timescale == 0 {
+        return Err(InspectError::BoxInvalid(*b"mvhd"));
+    }
+    let duration = Duration::try_from_secs_f64(duration as f64 / timescale as f64)
+        .map_err(|_| InspectError::BoxInvalid(*b"mvhd"))?;
+
+    for (kind, trak) in boxes(moov)? {
+        if &kind != b"trak" {
+            continue;
+        }
+        let mdia = find_box(trak, b"mdia")?.ok_or(InspectError::BoxMissing(*b"mdia"))?;
+        let hdlr = find_box(mdia, b"hdlr")?.ok_or(InspectError::BoxMissing(*b"hdlr"))?;
+        if hdlr.get(8..12) != Some(b"vide") {
+            continue;
+        }
+
+        return parse_video_trak(trak, mdia, duration);
+    }
+
+    Err(InspectError::VideoTrackMissing)
+}
+
+fn parse_video_trak(
+    trak: &[u8],
+    mdia: &[u8],
+    duration: Duration,
+) -> Result<MediaInfo, InspectError> {
+    let tkhd = find_box(trak, b"tkhd")?.ok_or(InspectError::BoxMissing(*b"tkhd"))?;
+    // Width and height are 16.16 fixed point, after the version dependent times and the matrix.
+    let size_offset = match tkhd.first() {
+        Some(1) => 88,
+        _ => 76,
+    };
+    let width = read_u32(tkhd, size_offset, b"tkhd")? >> 16;
+    let height = read_u32(tkhd, size_offset + 4, b"tkhd")? >> 16;
+
+    let mdhd = find_box(mdia, b"mdhd")?.ok_or(InspectError::BoxMissing(*b"mdhd"))?;
+    let media_timescale = match mdhd.first() {
+        Some(1) => read_u32(mdhd, 20, b"mdhd")?,
+        _ => read_u32(mdhd, 12, b"mdhd")?,
+    };
+
+    let stbl = find_box(mdia, b"minf")?
+        .map(|minf| find_box(minf, b"stbl"))
+        .transpose()?
+        .flatten()
+        .ok_or(InspectError::BoxMissing(*b"stbl"))?;
+
+    let stsd = find_box(stbl, b"stsd")?.ok_or(InspectError::BoxMissing(*b"stsd"))?;
+    // version/flags(4) + entry_count(4) + first sample entry size(4) + type(4)
+    let codec =
+        VideoCodec::from_fourcc(stsd.get(12..16).ok_or(InspectError::BoxInvalid(*b"stsd"))?);
+
+    let fps = match find_box(stbl, b"stts")? {
+        Some(stts) => {
+            let entry_count = read_u32(stts, 4, b"stts")? as usize;
+            let (mut sample_count, mut sample_duration) = (0_u64, 0_u64);
+            for i in 0..entry_count {
+                let count = read_u32(stts, 8 + i * 8, b"stts")? as u64;
+                let delta = read_u32(stts, 12 + i * 8, b"stts")? as u64;
+                sample_count = sample_count
+                    .checked_add(count)
+                    .ok_or(InspectError::BoxInvalid(*b"stts"))?;
+                sample_duration = count
+                    .checked_mul(delta)
+                    .and_then(|x| sample_duration.checked_add(x))
+                    .ok_or(InspectError::BoxInvalid(*b"stts"))?;
+            }
+            if sample_count == 0 || sample_duration == 0 {
+                None
+            } else {
+                Some(sample_count as f64 * media_timescale as f64 / sample_duration as f64)
+            }
+        }
+        None => None,
+    };
+
+    Ok(MediaInfo {
+        duration,
+        width,
+        height,
+        fps,
+        codec,
+    })
+}
+
+type BoxKind = [u8; 4];
+
+fn boxes(data: &[u8]) -> Result<Vec<(BoxKind, &[u8])>, InspectError> {
+    let mut ret = vec![];
+    let mut rest = data;
+    while rest.len() >= 8 {
+        let kind = [rest[4], rest[5], rest[6], rest[7]];
+        let (header_len, size) = match u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) {
+            0 => (8, rest.len()),
+            1 => {
+                let size = rest
+                    .get(8..16)
+                    .map(|x| u64::from_be_bytes(x.try_into().expect("8 bytes")))
+                    .ok_or(InspectError::BoxInvalid(kind))?;
+                (
+                    16,
+                    usize::try_from(size).map_err(|_| InspectError::BoxInvalid(kind))?,
+                )
+            }
+            x => (8, x as usize),
+        };
+        if size < header_len || size > rest.len() {
+            return Err(InspectError::BoxInvalid(kind));
+        }
+
+        ret.push((kind, &rest[header_len..size]));
+        rest = &rest[size..];
+    }
+    Ok(ret)
+}
+
+fn find_box<'a>(data: &'a [u8], kind: &[u8; 4]) -> Result<Option<&'a [u8]>, InspectError> {
+    Ok(boxes(data)?
+        .into_iter()
+        .find(|(x, _)| x == kind)
+        .map(|(_, body)| body))
+}
+
+fn read_u32(data: &[u8], offset: usize, kind: &[u8; 4]) -> Result<u32, InspectError> {
+    data.get(offset..offset + 4)
+        .map(|x| u32::from_be_bytes(x.try_into().expect("4 bytes")))
+        .ok_or(InspectError::BoxInvalid(*kind))
+}
+
+fn read_u64(data: &[u8], offset: usize, kind: &[u8; 4]) -> Result<u64, InspectError> {
+    data.get(offset..offset + 8)
+        .map(|x| u64::from_be_bytes(x.try_into().expect("8 bytes")))
+        .ok_or(InspectError::BoxInvalid(*kind))
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub enum InspectError {
+    ReadFailed(std::io::Error),
+    MoovMissing,
+    BoxMissing([u8; 4]),
+    BoxInvalid([u8; 4]),
+    VideoTrackMissing,
+}
+impl core::fmt::Display for InspectError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for InspectError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    fn bx(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut buf = ((8 + body.len()) as u32).to_be_bytes().to_vec();
+        buf.extend_from_slice(kind);
+        buf.extend_from_slice(body);
+        buf
+    }
+
+    fn trak(
+        handler: &[u8; 4],
+        codec: &[u8; 4],
+        width: u32,
+        height: u32,
+        timescale: u32,
+    ) -> Vec<u8> {
+        let mut tkhd = vec![0; 84];
+        tkhd[76..80].copy_from_slice(&(width << 16).to_be_bytes());
+        tkhd[80..84].copy_from_slice(&(height << 16).to_be_bytes());
+
+        let mut mdhd = vec![0; 24];
+        mdhd[12..16].copy_from_slice(&timescale.to_be_bytes());
+
+        let mut hdlr = vec![0; 24];
+        hdlr[8..12].copy_from_slice(handler);
+
+        let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stsd.extend_from_slice(&bx(codec, &[0; 78]));
+
+        // 300 samples of 512 then 1 sample of 1024, at 15360 timescale.
+        let mut stts = vec![0, 0, 0, 0, 0, 0, 0, 2];
+        for (count, delta) in [(300_u32, 512_u32), (1, 1024)] {
+            stts.extend_from_slice(&count.to_be_bytes());
+            stts.extend_from_slice(&delta.to_be_bytes());
+        }
+
+        let stbl = [bx(b"stsd", &stsd), bx(b"stts", &stts)].concat();
+        let minf = bx(b"stbl", &stbl);
+        let mdia = [bx(b"mdhd", &mdhd), bx(b"hdlr", &hdlr), bx(b"minf", &minf)].concat();
+        bx(b"trak", &[bx(b"tkhd", &tkhd), bx(b"mdia", &mdia)].concat())
+    }
+
+    fn mvhd(version: u8, timescale: u32, duration: u64) -> Vec<u8> {
+        let mut mvhd = vec![0; 112];
+        mvhd[0] = version;
+        match version {
+            1 => {
+                mvhd[20..24].copy_from_slice(&timescale.to_be_bytes());
+                mvhd[24..32].copy_from_slice(&duration.to_be_bytes());
+            }
+            _ => {
+                mvhd[12..16].copy_from_slice(&timescale.to_be_bytes());
+                mvhd[16..20].copy_from_slice(&(duration as u32).to_be_bytes());
+            }
+        }
+        bx(b"mvhd", &mvhd)
+    }
+
+    /// A 10s 1080x1920 H.264 file with an audio track, `moov` after `mdat` like most encoders.
+    fn fixture() -> Vec<u8> {
+        let mut mvhd = vec![0; 100];
+        mvhd[12..16].copy_from_slice(&1000_u32.to_be_bytes());
+        mvhd[16..20].copy_from_slice(&10_000_u32.to_be_bytes());
+
+        let moov = [
+            bx(b"mvhd", &mvhd),
+            trak(b"soun", b"mp4a", 0, 0, 44100),
+            trak(b"vide", b"avc1", 1080, 1920, 15360),
+        ]
+        .concat();
+
+        [
+            bx(b"ftyp", b"isom\0\0\x02\0isomiso2avc1mp41"),
+            bx(b"mdat", &[0; 1024]),
+            bx(b"moov", &moov),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_inspect() -> Result<(), Box<dyn std::error::Error>> {
+        let info = inspect(&mut Cursor::new(fixture()))?;
+        assert_eq!(info.duration, Duration::from_secs(10));
+        assert_eq!((info.width, info.height), (1080, 1920));
+        assert_eq!(info.codec, VideoCodec::H264);
+        assert!((info.fps.unwrap() - 301.0 * 15360.0 / (300.0 * 512.0 + 1024.0)).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_inspect_file() -> Result<(), Box<dyn std::error::Error>> {
+        let path = std::env::temp_dir().join(format!(
+            "tiktok-api-media-inspect-{}.mp4",
+            std::process::id()
+        ));
+        std::fs::write(&path, fixture())?;
+        let ret = inspect_file(&path);
+        std::fs::remove_file(&path)?;
+        assert_eq!(ret?.codec, VideoCodec::H264);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_inspect_failed() {
+        let data = [bx(b"ftyp", b"isom\0\0\x02\0isom"), bx(b"mdat", &[0; 16])].concat();
+        match inspect(&mut Cursor::new(data)) {
+            Err(InspectError::MoovMissing) => {}
+            x => panic!("{x:?}"),
+        }
+
+        let mut mvhd = vec![0; 100];
+        mvhd[12..16].copy_from_slice(&1000_u32.to_be_bytes());
+        let moov = [bx(b"mvhd", &mvhd), trak(b"soun", b"mp4a", 0, 0, 44100)].concat();
+        match inspect(&mut Cursor::new(bx(b"moov", &moov))) {
+            Err(InspectError::VideoTrackMissing) => {}
+            x => panic!("{x:?}"),
+        }
+
+        let mut data = fixture();
+        data.truncate(data.len() - 10);
+        match inspect(&mut Cursor::new(data)) {
+            Err(InspectError::BoxInvalid(x)) => assert_eq!(&x, b"moov"),
+            x => panic!("{x:?}"),
+        }
+    }
+
+    #[test]
+    fn test_inspect_malformed() {
+        // 64-bit size near u64::MAX.
+        let mut data = bx(b"ftyp", b"isom\0\0\x02\0isom");
+        data.extend_from_slice(&1_u32.to_be_bytes());
+        data.extend_from_slice(b"mdat");
+        data.extend_from_slice(&(u64::MAX - 4).to_be_bytes());
+        data.extend_from_slice(&[0; 16]);
+        match inspect(&mut Cursor::new(data)) {
+            Err(InspectError::BoxInvalid(x)) => assert_eq!(&x, b"mdat"),
+            x => panic!("{x:?}"),
+        }
+
+        // Duration too large for `Duration`.
+        let moov = [
+            mvhd(1, 1, u64::MAX),
+            trak(b"vide", b"avc1", 1080, 1920, 15360),
+        ]
+        .concat();
+        match inspect(&mut Cursor::new(bx(b"moov", &moov))) {
+            Err(InspectError::BoxInvalid(x)) => assert_eq!(&x, b"mvhd"),
+            x => panic!("{x:?}"),
+        }
+
+        // `stts` sums overflowing u64.
+        let mut stts = vec![0, 0, 0, 0, 0, 0, 0, 2];
+        for _ in 0..2 {
+            stts.extend_from_slice(&u32::MAX.to_be_bytes());
+            stts.extend_from_slice(&u32::MAX.to_be_bytes());
+        }
+        let mut mdhd = vec![0; 24];
+        mdhd[12..16].copy_from_slice(&15360_u32.to_be_bytes());
+        let mut hdlr = vec![0; 24];
+        hdlr[8..12].copy_from_slice(b"vide");
+        let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stsd.extend_from_slice(&bx(b"avc1", &[0; 78]));
+        let minf = bx(b"stbl", &[bx(b"stsd", &stsd), bx(b"stts", &stts)].concat());
+        let mdia = [bx(b"mdhd", &mdhd), bx(b"hdlr", &hdlr), bx(b"minf", &minf)].concat();
+        let trak = bx(
+            b"trak",
+            &[bx(b"tkhd", &[0; 84]), bx(b"mdia", &mdia)].concat(),
+        );
+        let moov = [mvhd(0, 1000, 10_000), trak].concat();
+        match inspect(&mut Cursor::new(bx(b"moov", &moov))) {
+            Err(InspectError::BoxInvalid(x)) => assert_eq!(&x, b"stts"),
+            x => panic!("{x:?}"),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use sha2::{Digest as _, Sha256};
+
+//
+pub type Sha256Digest = [u8; 32];
+
+pub fn to_hex(digest: &Sha256Digest) -> String {
+    crate::hex::encode(digest)
+}
+
+pub(crate) fn finalize(file: Sha256) -> Sha256Digest {
+    file.finalize().into()
+}
+
+//
+/// Hashes one chunk attempt, continuing from the whole file hash of the chunks before it.
+/// Retries start over from the same state, so only the accepted attempt ends up in the file hash.
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkHasher {
+    chunk: Sha256,
+    file: Sha256,
+    len: usize,
+}
+
+impl ChunkHasher {
+    pub(crate) fn new(file: &Sha256) -> Self {
+        Self {
+            chunk: Sha256::new(),
+            file: file.to_owned(),
+            len: 0,
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        self.chunk.update(data);
+        self.file.update(data);
+        self.len += data.len();
+    }
+
+    #[cfg(any(test, feature = "with_tokio_fs"))]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the chunk digest and the whole file state to continue from.
+    pub(crate) fn finish(self) -> (Sha256Digest, Sha256) {
+        (self.chunk.finalize().into(), self.file)
+    }
+}
+
+//
+//
+//
+#[cfg(feature = "with_tokio_fs")]
+pub(crate) use hashing_reader::HashingReader;
+
+#[cfg(feature = "with_tokio_fs")]
+mod hashing_reader {
+    use core::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use std::sync::{Arc, Mutex};
+
+    use tokio::io::{AsyncRead, ReadBuf};
+
+    use super::ChunkHasher;
+
+    /// The transport owns the body, so the hasher is shared to read it back after the send.
+    pub(crate) struct HashingReader<R> {
+        pub(crate) inner: R,
+        pub(crate) hasher: Arc<Mutex<ChunkHasher>>,
+    }
+
+    impl<R> AsyncRead for HashingReader<R>
+    where
+        R: AsyncRead + Unpin,
+    {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            let this = self.get_mut();
+
+            let filled_before = buf.filled().len();
+            let ret = Pin::new(&mut this.inner).poll_read(cx, buf);
+            if let Poll::Ready(Ok(())) = &ret {
+                this.hasher
+                    .lock()
+                    .expect("ChunkHasher poisoned")
+                    .update(&buf.filled()[filled_before..]);
+            }
+            ret
+        }
+    }
+}
+
+//
+//
+//
+/// Size and modification time, taken when the file is sized for `/inbox/video/init/`
+/// and compared again before each chunk is read.
+#[cfg(feature = "with_tokio_fs")]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileFingerprint {
+    pub size: u64,
+    pub modified: Option<std::time::SystemTime>,
+}
+
+#[cfg(feature = "with_tokio_fs")]
+impl FileFingerprint {
+    pub async fn from_file(file_path: &std::path::PathBuf) -> Result<Self, std::io::Error> {
+        let metadata = tokio::fs::metadata(file_path).await?;
+        if !metadata.is_file() {
+            return Err(std::io::Error::other("is_file required"));
+        }
+
+        Ok(Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_hasher() {
+        let file = Sha256::new();
+
+        let mut hasher = ChunkHasher::new(&file);
+        hasher.update(b"hello ");
+        let (chunk_1, file) = hasher.finish();
+
+        // A failed attempt does not leak into the file hash.
+        let mut failed = ChunkHasher::new(&file);
+        failed.update(b"garbage");
+
+        let mut hasher = ChunkHasher::new(&file);
+        hasher.update(b"world");
+        assert_eq!(hasher.len(), 5);
+        let (chunk_2, file) = hasher.finish();
+
+        assert_eq!(chunk_1, <[u8; 32]>::from(Sha256::digest(b"hello ")));
+        assert_eq!(chunk_2, <[u8; 32]>::from(Sha256::digest(b"world")));
+        assert_eq!(
+            to_hex(&file.finalize().into()),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+    }
+}
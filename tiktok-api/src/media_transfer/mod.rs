@@ -0,0 +1,894 @@
+use bytes::Bytes;
+use http_api_client_endpoint::http::{
+    header::{CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE},
+    HeaderMap, HeaderValue, Method, StatusCode,
+};
+use url::Url;
+
+use crate::{
+    objects::v2::ResponseErrorBody,
+    transport::{Transport, TransportBody, TransportError, TransportRequest},
+};
+
+pub mod integrity;
+use integrity::ChunkHasher;
+#[cfg(feature = "with_tokio_fs")]
+pub use integrity::FileFingerprint;
+pub use integrity::Sha256Digest;
+
+pub mod options;
+pub use options::{OnChunk, UploadOptions};
+
+pub mod report;
+pub use report::{ChunkReport, UploadReport, UploadStatus};
+
+pub mod source;
+pub use source::UploadSource;
+
+#[cfg(feature = "with_tokio")]
+pub mod rate_limit;
+#[cfg(feature = "with_tokio")]
+pub use rate_limit::RateLimiter;
+
+#[cfg(feature = "with_pull_server")]
+pub mod pull_server;
+#[cfg(feature = "with_pull_server")]
+pub use pull_server::{pull_from_file, PullServer, PullServerConfig};
+
+#[cfg(feature = "with_tokio_fs")]
+pub mod spool;
+#[cfg(feature = "with_tokio_fs")]
+pub use spool::{spool, SpoolError, SpooledVideo};
+
+//
+pub const CHUNK_SIZE_MIN: usize = 1024 * 1024 * 5;
+pub const CHUNK_SIZE_MAX: usize = 1024 * 1024 * 64;
+pub const CHUNK_COUNT_MIN: usize = 1;
+pub const CHUNK_COUNT_MAX: usize = 1000;
+
+//
+fn get_chunk_size(chunk_size: usize) -> usize {
+    chunk_size.clamp(CHUNK_SIZE_MIN, CHUNK_SIZE_MAX)
+}
+
+pub fn get_chunk_size_and_total_chunk_count(
+    video_size: usize,
+    chunk_size: usize,
+) -> (usize, usize) {
+    let chunk_size = get_chunk_size(chunk_size);
+
+    if video_size <= chunk_size {
+        (video_size, 1)
+    } else {
+        (
+            chunk_size,
+            (video_size as f64 / chunk_size as f64).floor() as usize,
+        )
+    }
+}
+
+pub fn get_chunk_ranges(
+    video_size: usize,
+    chunk_size: usize,
+) -> Result<Vec<core::ops::Range<usize>>, UploadError> {
+    if video_size == 0 {
+        return Err(UploadError::VideoEmpty);
+    }
+
+    let (chunk_size, total_chunk_count) =
+        get_chunk_size_and_total_chunk_count(video_size, chunk_size);
+
+    if total_chunk_count > CHUNK_COUNT_MAX {
+        return Err(UploadError::ChunkSizeTooSmaillOrFileTooLarge);
+    }
+
+    let ranges = (0..total_chunk_count)
+        .map(|chunk_index| {
+            let start = chunk_index * chunk_size;
+            // The last chunk takes the remaining bytes, so it may be up to (2 * chunk_size - 1).
+            let end = if chunk_index + 1 == total_chunk_count {
+                video_size
+            } else {
+                start + chunk_size
+            };
+            start..end
+        })
+        .collect();
+
+    Ok(ranges)
+}
+
+//
+//
+//
+pub async fn upload_part<C, T>(
+    client: &C,
+    upload_url: Url,
+    content_type: &str,
+    byte_range: core::ops::Range<usize>,
+    video_size: usize,
+    body: T,
+) -> Result<StatusCode, UploadError>
+where
+    C: Transport + ?Sized,
+    T: Into<TransportBody>,
+{
+    if byte_range.is_empty() || byte_range.end > video_size {
+        return Err(UploadError::ByteRangeInvalid(byte_range));
+    }
+
+    let content_length = byte_range.end - byte_range.start;
+    let content_range = format!(
+        "bytes {}-{}/{}",
+        byte_range.start,
+        byte_range.end - 1,
+        video_size,
+    );
+
+    //
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_str(content_type).map_err(|_| UploadError::ContentTypeInvalid)?,
+    );
+    headers.insert(CONTENT_LENGTH, HeaderValue::from(content_length));
+    headers.insert(
+        CONTENT_RANGE,
+        HeaderValue::from_str(&content_range).expect("content_range is ascii"),
+    );
+
+    let response = client
+        .send(TransportRequest {
+            method: Method::PUT,
+            url: upload_url,
+            headers,
+            body: body.into(),
+        })
+        .await
+        .map_err(|err| UploadError::RespondFailed(byte_range.to_owned(), err))?;
+
+    //
+    match response.status {
+        StatusCode::PARTIAL_CONTENT | StatusCode::CREATED => Ok(response.status),
+        status => Err(UploadError::ResponseMismatch {
+            byte_range,
+            status,
+            error_body: serde_json::from_slice::<ResponseErrorBody>(&response.body)
+                .ok()
+                .map(Box::new),
+            body: response.body,
+        }),
+    }
+}
+
+#[cfg(feature = "with_tokio")]
+pub async fn upload_part_from_reader_stream<C, S>(
+    client: &C,
+    upload_url: Url,
+    content_type: &str,
+    byte_range: core::ops::Range<usize>,
+    video_size: usize,
+    stream: S,
+) -> Result<StatusCode, UploadError>
+where
+    C: Transport + ?Sized,
+    S: tokio::io::AsyncRead + Send + Sync + 'static,
+{
+    let length = byte_range.len() as u64;
+
+    upload_part(
+        client,
+        upload_url,
+        content_type,
+        byte_range,
+        video_size,
+        TransportBody::reader(stream, Some(length)),
+    )
+    .await
+}
+
+#[cfg(feature = "with_tokio_fs")]
+pub async fn upload_part_from_file<C>(
+    client: &C,
+    upload_url: Url,
+    content_type: &str,
+    file_path: &std::path::PathBuf,
+    file_index: core::ops::Range<usize>,
+    file_size: usize,
+) -> Result<StatusCode, UploadError>
+where
+    C: Transport + ?Sized,
+{
+    upload_part_from_file_with_hasher(
+        client,
+        upload_url,
+        content_type,
+        file_path,
+        file_index,
+        file_size,
+        None,
+        ChunkHasher::new(&Default::default()),
+    )
+    .await
+    .map(|(status_code, _)| status_code)
+}
+
+#[cfg(feature = "with_tokio_fs")]
+#[allow(clippy::too_many_arguments)]
+async fn upload_part_from_file_with_hasher<C>(
+    client: &C,
+    upload_url: Url,
+    content_type: &str,
+    file_path: &std::path::PathBuf,
+    file_index: core::ops::Range<usize>,
+    file_size: usize,
+    rate_limiter: Option<&RateLimiter>,
+    hasher: ChunkHasher,
+) -> Result<(StatusCode, ChunkHasher), UploadError>
+where
+    C: Transport + ?Sized,
+{
+    use std::sync::{Arc, Mutex};
+
+    use tokio::{
+        fs::File,
+        io::{AsyncReadExt as _, AsyncSeekExt as _, SeekFrom},
+    };
+
+    let file_index_start = file_index.start;
+    let file_index_end = file_index.end;
+
+    let file_take_size = file_index_end - file_index_start;
+
+    let mut file = File::open(&file_path)
+        .await
+        .map_err(UploadError::OpenFileFailed)?;
+    file.seek(SeekFrom::Start(file_index_start as u64))
+        .await
+        .map_err(UploadError::OpenFileFailed)?;
+    let hasher = Arc::new(Mutex::new(hasher));
+    let file = integrity::HashingReader {
+        inner: file.take(file_take_size as u64),
+        hasher: hasher.to_owned(),
+    };
+
+    let status_code = match rate_limiter {
+        Some(rate_limiter) => {
+            upload_part_from_reader_stream(
+                client,
+                upload_url,
+                content_type,
+                file_index,
+                file_size,
+                rate_limiter.throttle(file),
+            )
+            .await?
+        }
+        None => {
+            upload_part_from_reader_stream(
+                client,
+                upload_url,
+                content_type,
+                file_index,
+                file_size,
+                file,
+            )
+            .await?
+        }
+    };
+
+    let hasher = hasher.lock().expect("ChunkHasher poisoned").to_owned();
+    if hasher.len() != file_take_size {
+        return Err(UploadError::ReadFailed(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "file shorter than the chunk",
+        )));
+    }
+
+    Ok((status_code, hasher))
+}
+
+#[cfg(feature = "with_tokio_fs")]
+pub async fn upload_from_file<C>(
+    client: &C,
+    upload_url: Url,
+    content_type: &str,
+    file_path: &std::path::PathBuf,
+    options: &UploadOptions,
+) -> Result<UploadReport, UploadError>
+where
+    C: Transport + ?Sized,
+{
+    let expected_fingerprint = match &options.expected_fingerprint {
+        Some(x) => x.to_owned(),
+        None => FileFingerprint::from_file(file_path)
+            .await
+            .map_err(UploadError::GetFileInfoFailed)?,
+    };
+
+    let video_size = expected_fingerprint.size as usize;
+
+    upload_chunks(video_size, options, |chunk_range, hasher| {
+        let upload_url = upload_url.to_owned();
+        let expected_fingerprint = &expected_fingerprint;
+        async move {
+            // A file changed since sizing would otherwise surface as a confusing Content-Range 4xx.
+            let fingerprint = FileFingerprint::from_file(file_path)
+                .await
+                .map_err(UploadError::GetFileInfoFailed)?;
+            if &fingerprint != expected_fingerprint {
+                return Err(UploadError::FileModified {
+                    expected: expected_fingerprint.to_owned(),
+                    actual: fingerprint,
+                });
+            }
+
+            upload_part_from_file_with_hasher(
+                client,
+                upload_url,
+                content_type,
+                file_path,
+                chunk_range,
+                video_size,
+                options.rate_limiter.as_ref(),
+                hasher,
+            )
+            .await
+        }
+    })
+    .await
+}
+
+pub async fn upload_from_bytes<C>(
+    client: &C,
+    upload_url: Url,
+    content_type: &str,
+    bytes: impl Into<Bytes>,
+    options: &UploadOptions,
+) -> Result<UploadReport, UploadError>
+where
+    C: Transport + ?Sized,
+{
+    let bytes: Bytes = bytes.into();
+    if bytes.is_empty() {
+        return Err(UploadError::VideoEmpty);
+    }
+
+    upload_chunks(bytes.len(), options, |chunk_range, mut hasher| {
+        let chunk = bytes.slice(chunk_range.to_owned());
+        hasher.update(&chunk);
+
+        let upload_url = upload_url.to_owned();
+        let video_size = bytes.len();
+        async move {
+            upload_part(
+                client,
+                upload_url,
+                content_type,
+                chunk_range,
+                video_size,
+                chunk_body(chunk, options),
+            )
+            .await
+            .map(|status_code| (status_code, hasher))
+        }
+    })
+    .await
+}
+
+/// Reads each chunk synchronously before sending it, so it does not need a tokio runtime
+/// for the reading side. Use `upload_from_file` instead for files on a tokio runtime.
+pub async fn upload_from_reader_seek<C, R>(
+    client: &C,
+    upload_url: Url,
+    content_type: &str,
+    reader: &mut R,
+    options: &UploadOptions,
+) -> Result<UploadReport, UploadError>
+where
+    C: Transport + ?Sized,
+    R: std::io::Read + std::io::Seek,
+{
+    use std::io::SeekFrom;
+
+    let video_size = reader
+        .seek(SeekFrom::End(0))
+        .map_err(UploadError::ReadFailed)? as usize;
+
+    upload_chunks(video_size, options, |chunk_range, mut hasher| {
+        let mut buf = vec![0; chunk_range.end - chunk_range.start];
+        let read_ret = reader
+            .seek(SeekFrom::Start(chunk_range.start as u64))
+            .and_then(|_| reader.read_exact(&mut buf))
+            .map_err(UploadError::ReadFailed);
+        hasher.update(&buf);
+
+        let upload_url = upload_url.to_owned();
+        async move {
+            read_ret?;
+            upload_part(
+                client,
+                upload_url,
+                content_type,
+                chunk_range,
+                video_size,
+                chunk_body(buf.into(), options),
+            )
+            .await
+            .map(|status_code| (status_code, hasher))
+        }
+    })
+    .await
+}
+
+#[cfg(feature = "with_tokio")]
+fn chunk_body(chunk: Bytes, options: &UploadOptions) -> TransportBody {
+    match &options.rate_limiter {
+        Some(rate_limiter) => {
+            let length = chunk.len() as u64;
+            TransportBody::reader(
+                rate_limiter.throttle(std::io::Cursor::new(chunk)),
+                Some(length),
+            )
+        }
+        None => TransportBody::Bytes(chunk),
+    }
+}
+
+#[cfg(not(feature = "with_tokio"))]
+fn chunk_body(chunk: Bytes, _options: &UploadOptions) -> TransportBody {
+    TransportBody::Bytes(chunk)
+}
+
+async fn upload_chunks<F, Fut>(
+    video_size: usize,
+    options: &UploadOptions,
+    mut upload_chunk: F,
+) -> Result<UploadReport, UploadError>
+where
+    F: FnMut(core::ops::Range<usize>, ChunkHasher) -> Fut,
+    Fut: core::future::Future<Output = Result<(StatusCode, ChunkHasher), UploadError>>,
+{
+    let chunk_ranges = get_chunk_ranges(video_size, options.chunk_size.unwrap_or(CHUNK_SIZE_MAX))?;
+    let max_attempts = core::cmp::max(options.max_attempts, 1);
+
+    let mut report = UploadReport {
+        total_bytes: video_size,
+        chunks: vec![],
+        status: UploadStatus::Partial,
+        sha256: None,
+    };
+    let mut file_hasher = Default::default();
+    for (chunk_index, chunk_range) in chunk_ranges
+        .into_iter()
+        .enumerate()
+        .skip(options.start_chunk)
+    {
+        if options.is_cancelled() {
+            return Ok(report);
+        }
+
+        let started_at = std::time::Instant::now();
+        let mut attempts = 0;
+        let ret = loop {
+            attempts += 1;
+            match upload_chunk(chunk_range.to_owned(), ChunkHasher::new(&file_hasher)).await {
+                Err(err) if attempts < max_attempts && err.is_retryable() => continue,
+                ret => break ret,
+            }
+        };
+
+        let ret = ret.map(|(status_code, hasher)| {
+            let (chunk_sha256, file_hasher_next) = hasher.finish();
+            file_hasher = file_hasher_next;
+            (status_code, chunk_sha256)
+        });
+
+        report.chunks.push(ChunkReport {
+            byte_range: chunk_range,
+            attempts,
+            elapsed: started_at.elapsed(),
+            status_code: match &ret {
+                Ok((status_code, _)) => Some(*status_code),
+                Err(UploadError::ResponseMismatch { status, .. }) => Some(*status),
+                Err(_) => None,
+            },
+            sha256: ret.as_ref().ok().map(|(_, chunk_sha256)| *chunk_sha256),
+        });
+
+        if let (Ok(_), Some(on_chunk)) = (&ret, &options.on_chunk) {
+            (on_chunk.0)(chunk_index, &report.chunks[report.chunks.len() - 1]);
+        }
+
+        match ret {
+            Ok((StatusCode::CREATED, _)) => {
+                report.status = UploadStatus::Complete;
+                if options.start_chunk == 0 {
+                    report.sha256 = Some(integrity::finalize(core::mem::take(&mut file_hasher)));
+                }
+                break;
+            }
+            Ok(_) => {}
+            Err(error) => {
+                report.status = UploadStatus::Failed {
+                    chunk: chunk_index,
+                    error,
+                };
+                break;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub enum UploadError {
+    ContentTypeInvalid,
+    VideoEmpty,
+    ByteRangeInvalid(core::ops::Range<usize>),
+    RespondFailed(core::ops::Range<usize>, TransportError),
+    ResponseMismatch {
+        byte_range: core::ops::Range<usize>,
+        status: StatusCode,
+        body: Vec<u8>,
+        /// `body` decoded as a v2 error, when possible.
+        error_body: Option<Box<ResponseErrorBody>>,
+    },
+    ReadFailed(std::io::Error),
+    #[cfg(feature = "with_tokio_fs")]
+    GetFileInfoFailed(std::io::Error),
+    #[cfg(feature = "with_tokio_fs")]
+    OpenFileFailed(std::io::Error),
+    #[cfg(feature = "with_tokio_fs")]
+    FileModified {
+        expected: FileFingerprint,
+        actual: FileFingerprint,
+    },
+    ChunkSizeTooSmaillOrFileTooLarge,
+}
+impl core::fmt::Display for UploadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for UploadError {}
+
+impl UploadError {
+    pub fn byte_range(&self) -> Option<&core::ops::Range<usize>> {
+        match self {
+            Self::RespondFailed(byte_range, _) | Self::ResponseMismatch { byte_range, .. } => {
+                Some(byte_range)
+            }
+            _ => None,
+        }
+    }
+
+    /// An expired `upload_url` is answered with a 4xx such as 403, 404 or 410, so this alone
+    /// can not tell it apart from other rejections, see `crate::publish::upload`.
+    pub fn is_upload_url_expired_response(&self) -> bool {
+        matches!(
+            self,
+            Self::ResponseMismatch {
+                status: StatusCode::FORBIDDEN | StatusCode::NOT_FOUND | StatusCode::GONE,
+                ..
+            }
+        )
+    }
+
+    fn is_retryable(&self) -> bool {
+        match self {
+            Self::RespondFailed(_, _) => true,
+            Self::ResponseMismatch { status, .. } => status.is_server_error(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sha2::{Digest as _, Sha256};
+
+    use crate::transport::mock::MockTransport;
+
+    #[test]
+    fn test_get_chunk_ranges() {
+        assert_eq!(get_chunk_ranges(100, 0).unwrap(), vec![0..100]);
+
+        let mb = 1024 * 1024;
+        assert_eq!(
+            get_chunk_ranges(mb * 12 + 1, mb * 5).unwrap(),
+            vec![0..mb * 5, mb * 5..mb * 12 + 1]
+        );
+
+        match get_chunk_ranges(CHUNK_SIZE_MIN * (CHUNK_COUNT_MAX + 1), 0) {
+            Err(UploadError::ChunkSizeTooSmaillOrFileTooLarge) => {}
+            x => panic!("{x:?}"),
+        }
+
+        match get_chunk_ranges(0, 0) {
+            Err(UploadError::VideoEmpty) => {}
+            x => panic!("{x:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_empty() {
+        let transport = MockTransport::upload_server();
+        let upload_url: Url = "https://open-upload.tiktokapis.com/video/?upload_id=67890"
+            .parse()
+            .unwrap();
+
+        match upload_from_bytes(
+            &transport,
+            upload_url.to_owned(),
+            "video/mp4",
+            Bytes::new(),
+            &UploadOptions::default(),
+        )
+        .await
+        {
+            Err(UploadError::VideoEmpty) => {}
+            x => panic!("{x:?}"),
+        }
+
+        match upload_part(&transport, upload_url, "video/mp4", 0..0, 0, Bytes::new()).await {
+            Err(UploadError::ByteRangeInvalid(byte_range)) => assert_eq!(byte_range, 0..0),
+            x => panic!("{x:?}"),
+        }
+
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_upload_chunks() {
+        let mb = 1024 * 1024;
+        let options = UploadOptions::new().with_chunk_size(mb * 5);
+
+        let report = upload_chunks(mb * 11, &options, |chunk_range, hasher| {
+            core::future::ready(Ok((
+                if chunk_range.end == mb * 11 {
+                    StatusCode::CREATED
+                } else {
+                    StatusCode::PARTIAL_CONTENT
+                },
+                hasher,
+            )))
+        })
+        .await
+        .unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.total_bytes, mb * 11);
+        assert_eq!(report.completed_ranges(), vec![0..mb * 5, mb * 5..mb * 11]);
+        assert_eq!(report.uploaded_bytes(), mb * 11);
+
+        //
+        let mut n = 0;
+        let report = upload_chunks(
+            mb * 11,
+            &options.to_owned().with_max_attempts(2),
+            |byte_range, hasher| {
+                n += 1;
+                core::future::ready(if n == 1 {
+                    Err(UploadError::ResponseMismatch {
+                        byte_range,
+                        status: StatusCode::SERVICE_UNAVAILABLE,
+                        body: vec![],
+                        error_body: None,
+                    })
+                } else if byte_range.start == 0 {
+                    Ok((StatusCode::PARTIAL_CONTENT, hasher))
+                } else {
+                    Err(UploadError::ResponseMismatch {
+                        byte_range,
+                        status: StatusCode::BAD_REQUEST,
+                        body: vec![],
+                        error_body: None,
+                    })
+                })
+            },
+        )
+        .await
+        .unwrap();
+        assert!(!report.is_complete());
+        assert_eq!(report.chunks[0].attempts, 2);
+        assert_eq!(report.chunks[1].attempts, 1);
+        assert_eq!(report.chunks[1].status_code, Some(StatusCode::BAD_REQUEST));
+        match &report.status {
+            UploadStatus::Failed { chunk: 1, error } => {
+                assert_eq!(error.byte_range(), Some(&(mb * 5..mb * 11)));
+            }
+            x => panic!("{x:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_chunks_cancelled() {
+        let cancellation_token = tokio_util::sync::CancellationToken::new();
+        let options = UploadOptions::new()
+            .with_chunk_size(CHUNK_SIZE_MIN)
+            .with_cancellation_token(cancellation_token.to_owned());
+
+        let report = upload_chunks(CHUNK_SIZE_MIN * 3, &options, |_, hasher| {
+            cancellation_token.cancel();
+            core::future::ready(Ok((StatusCode::PARTIAL_CONTENT, hasher)))
+        })
+        .await
+        .unwrap();
+        match report.status {
+            UploadStatus::Partial => {}
+            x => panic!("{x:?}"),
+        }
+        assert_eq!(report.completed_ranges(), vec![0..CHUNK_SIZE_MIN]);
+    }
+
+    #[tokio::test]
+    async fn test_upload_chunks_resumed() {
+        let checkpoints = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let options = UploadOptions::new()
+            .with_chunk_size(CHUNK_SIZE_MIN)
+            .with_start_chunk(1)
+            .with_on_chunk({
+                let checkpoints = checkpoints.to_owned();
+                move |chunk_index, _| checkpoints.lock().unwrap().push(chunk_index)
+            });
+
+        let report = upload_chunks(CHUNK_SIZE_MIN * 3, &options, |chunk_range, hasher| {
+            core::future::ready(Ok((
+                if chunk_range.end == CHUNK_SIZE_MIN * 3 {
+                    StatusCode::CREATED
+                } else {
+                    StatusCode::PARTIAL_CONTENT
+                },
+                hasher,
+            )))
+        })
+        .await
+        .unwrap();
+        assert!(report.is_complete());
+        assert_eq!(
+            report.completed_ranges(),
+            vec![
+                CHUNK_SIZE_MIN..CHUNK_SIZE_MIN * 2,
+                CHUNK_SIZE_MIN * 2..CHUNK_SIZE_MIN * 3
+            ]
+        );
+        assert_eq!(report.sha256, None);
+        assert_eq!(*checkpoints.lock().unwrap(), vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_upload_from_bytes() {
+        let transport = MockTransport::upload_server();
+        let data = (0..CHUNK_SIZE_MIN * 2 + 1)
+            .map(|x| x as u8)
+            .collect::<Vec<_>>();
+
+        let report = upload_from_bytes(
+            &transport,
+            "https://open-upload.tiktokapis.com/video/?upload_id=67890"
+                .parse()
+                .unwrap(),
+            "video/mp4",
+            data.to_owned(),
+            &UploadOptions::new().with_chunk_size(CHUNK_SIZE_MIN),
+        )
+        .await
+        .unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.sha256, Some(Sha256::digest(&data).into()));
+        assert_eq!(
+            report.chunks[1].sha256,
+            Some(Sha256::digest(&data[CHUNK_SIZE_MIN..]).into())
+        );
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, Method::PUT);
+        assert_eq!(requests[0].headers[CONTENT_TYPE], "video/mp4");
+        assert_eq!(
+            requests[0].headers[CONTENT_LENGTH],
+            CHUNK_SIZE_MIN.to_string()
+        );
+        assert_eq!(
+            requests[0].headers[CONTENT_RANGE],
+            format!("bytes 0-{}/{}", CHUNK_SIZE_MIN - 1, data.len())
+        );
+        assert_eq!(
+            requests[1].headers[CONTENT_RANGE],
+            format!("bytes {}-{}/{}", CHUNK_SIZE_MIN, data.len() - 1, data.len())
+        );
+        assert_eq!(
+            requests
+                .into_iter()
+                .flat_map(|x| x.body)
+                .collect::<Vec<_>>(),
+            data
+        );
+    }
+
+    #[cfg(feature = "with_tokio_fs")]
+    #[tokio::test]
+    async fn test_upload_from_file() -> Result<(), Box<dyn std::error::Error>> {
+        let data = (0..CHUNK_SIZE_MIN + 100)
+            .map(|x| (x % 251) as u8)
+            .collect::<Vec<_>>();
+        let file_path = std::env::temp_dir().join(format!(
+            "tiktok-api-upload-from-file-{}",
+            std::process::id()
+        ));
+        std::fs::write(&file_path, &data)?;
+        let upload_url: Url =
+            "https://open-upload.tiktokapis.com/video/?upload_id=67890".parse()?;
+
+        let fingerprint = FileFingerprint::from_file(&file_path).await?;
+        let report = upload_from_file(
+            &MockTransport::upload_server(),
+            upload_url.to_owned(),
+            "video/mp4",
+            &file_path,
+            &UploadOptions::new().with_expected_fingerprint(fingerprint.to_owned()),
+        )
+        .await?;
+        assert!(report.is_complete());
+        assert_eq!(report.sha256, Some(Sha256::digest(&data).into()));
+        assert_eq!(report.chunks[0].sha256, Some(Sha256::digest(&data).into()));
+
+        // Sized before the file was appended to.
+        std::fs::write(&file_path, [&data[..], b"appended"].concat())?;
+        let report = upload_from_file(
+            &MockTransport::upload_server(),
+            upload_url,
+            "video/mp4",
+            &file_path,
+            &UploadOptions::new().with_expected_fingerprint(fingerprint),
+        )
+        .await;
+        std::fs::remove_file(&file_path)?;
+        match report?.status {
+            UploadStatus::Failed {
+                chunk: 0,
+                error: UploadError::FileModified { expected, actual },
+            } => {
+                assert_eq!(expected.size, data.len() as u64);
+                assert_eq!(actual.size, data.len() as u64 + 8);
+            }
+            x => panic!("{x:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upload_from_reader_seek_read_failed() {
+        struct Unseekable;
+        impl std::io::Read for Unseekable {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Ok(0)
+            }
+        }
+        impl std::io::Seek for Unseekable {
+            fn seek(&mut self, _pos: std::io::SeekFrom) -> std::io::Result<u64> {
+                Err(std::io::Error::other("unseekable"))
+            }
+        }
+
+        match upload_from_reader_seek(
+            &MockTransport::upload_server(),
+            "https://open-upload.tiktokapis.com/video/".parse().unwrap(),
+            "video/mp4",
+            &mut Unseekable,
+            &UploadOptions::default(),
+        )
+        .await
+        {
+            Err(UploadError::ReadFailed(_)) => {}
+            x => panic!("{x:?}"),
+        }
+    }
+}
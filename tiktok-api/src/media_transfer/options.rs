@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use tokio_util::sync::CancellationToken;
+
+use super::ChunkReport;
+
+//
+pub const MAX_ATTEMPTS_DEFAULT: usize = 1;
+
+//
+#[allow(clippy::type_complexity)]
+#[derive(Clone)]
+pub struct OnChunk(pub Arc<dyn Fn(usize, &ChunkReport) + Send + Sync>);
+impl core::fmt::Debug for OnChunk {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("OnChunk").finish()
+    }
+}
+
+//
+#[derive(Debug, Clone)]
+pub struct UploadOptions {
+    pub chunk_size: Option<usize>,
+    /// Per chunk, only transport errors and 5xx responses are retried.
+    pub max_attempts: usize,
+    /// Checked before each chunk, the in-flight chunk is always allowed to finish.
+    pub cancellation_token: Option<CancellationToken>,
+    /// Used by `crate::publish::upload`, calls the publish cancel endpoint after a cancellation.
+    pub cancel_publish_on_cancellation: bool,
+    /// Used by `crate::publish::upload`, how many times to re-run `/inbox/video/init/`
+    /// and restart after the `upload_url` expires.
+    pub max_reinitializations: usize,
+    /// Chunks before it were accepted by an earlier run on the same `upload_url`.
+    /// The report then has no `sha256` of the whole source.
+    /// `crate::publish::upload` starts from 0 again after re-initializing, so `on_chunk`
+    /// indexes then refer to the new `PublishUpload::publish_id`.
+    pub start_chunk: usize,
+    /// Called after each accepted chunk with its index, e.g. to persist a checkpoint.
+    pub on_chunk: Option<OnChunk>,
+    #[cfg(feature = "with_tokio")]
+    pub rate_limiter: Option<super::RateLimiter>,
+    /// Used by `upload_from_file`, usually taken by `VideoUploadInitEndpoint::with_file`.
+    /// When None, the fingerprint is taken when the upload starts.
+    #[cfg(feature = "with_tokio_fs")]
+    pub expected_fingerprint: Option<super::FileFingerprint>,
+}
+impl Default for UploadOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: None,
+            max_attempts: MAX_ATTEMPTS_DEFAULT,
+            cancellation_token: None,
+            cancel_publish_on_cancellation: false,
+            max_reinitializations: 0,
+            start_chunk: 0,
+            on_chunk: None,
+            #[cfg(feature = "with_tokio")]
+            rate_limiter: None,
+            #[cfg(feature = "with_tokio_fs")]
+            expected_fingerprint: None,
+        }
+    }
+}
+
+impl UploadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn with_cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = Some(cancellation_token);
+        self
+    }
+
+    pub fn with_cancel_publish_on_cancellation(mut self, value: bool) -> Self {
+        self.cancel_publish_on_cancellation = value;
+        self
+    }
+
+    pub fn with_max_reinitializations(mut self, max_reinitializations: usize) -> Self {
+        self.max_reinitializations = max_reinitializations;
+        self
+    }
+
+    pub fn with_start_chunk(mut self, start_chunk: usize) -> Self {
+        self.start_chunk = start_chunk;
+        self
+    }
+
+    pub fn with_on_chunk(
+        mut self,
+        on_chunk: impl Fn(usize, &ChunkReport) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_chunk = Some(OnChunk(Arc::new(on_chunk)));
+        self
+    }
+
+    #[cfg(feature = "with_tokio")]
+    pub fn with_rate_limiter(mut self, rate_limiter: super::RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    #[cfg(feature = "with_tokio_fs")]
+    pub fn with_expected_fingerprint(mut self, fingerprint: super::FileFingerprint) -> Self {
+        self.expected_fingerprint = Some(fingerprint);
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_token
+            .as_ref()
+            .map(|x| x.is_cancelled())
+            .unwrap_or(false)
+    }
+}
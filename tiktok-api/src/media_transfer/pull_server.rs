@@ -0,0 +1,750 @@
+use core::{
+    convert::Infallible,
+    ops::Range,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::{
+    net::{SocketAddr, TcpListener},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use http_api_client::Client;
+use hyper::{
+    header::{ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, RANGE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use tokio::{
+    fs::File,
+    io::{AsyncRead, AsyncReadExt as _, AsyncSeekExt as _, ReadBuf, SeekFrom},
+    task::JoinHandle,
+    time::Instant,
+};
+use tokio_util::{io::ReaderStream, sync::CancellationToken};
+use url::Url;
+
+use crate::{
+    endpoints::v2::{
+        publish_status_fetch::{PublishStatus, PublishStatusFetchResponseBodyData},
+        video_upload_init::{VideoUploadInitRequestBodySourceInfo, VideoUploadInitResponseBody},
+        EndpointRet, PublishStatusFetchEndpoint, VideoUploadInitEndpoint,
+    },
+    verified_sources::{UnverifiedUrl, VerifiedSources},
+};
+
+//
+/// `upload_url` lifetime, TikTok is not expected to still be pulling after it.
+pub const TIMEOUT_SECS_DEFAULT: u64 = 60 * 60;
+pub const STATUS_POLL_INTERVAL_SECS_DEFAULT: u64 = 5;
+
+//
+//
+//
+#[derive(Debug, Clone)]
+pub struct PullServerConfig {
+    pub bind_addr: SocketAddr,
+    /// A URL prefix verified with TikTok that reaches `bind_addr`, e.g. through a reverse proxy.
+    /// Should end with `/`.
+    pub public_prefix: Url,
+    pub content_type: Option<String>,
+    /// Used by `pull_from_file`, how long to wait for TikTok after the init.
+    pub timeout: Duration,
+    /// Used by `pull_from_file`, how often to fetch the publish status meanwhile.
+    pub status_poll_interval: Duration,
+}
+impl PullServerConfig {
+    pub fn new(bind_addr: SocketAddr, public_prefix: Url) -> Self {
+        Self {
+            bind_addr,
+            public_prefix,
+            content_type: None,
+            timeout: Duration::from_secs(TIMEOUT_SECS_DEFAULT),
+            status_poll_interval: Duration::from_secs(STATUS_POLL_INTERVAL_SECS_DEFAULT),
+        }
+    }
+
+    pub fn with_content_type(mut self, content_type: impl AsRef<str>) -> Self {
+        self.content_type = Some(content_type.as_ref().into());
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_status_poll_interval(mut self, status_poll_interval: Duration) -> Self {
+        self.status_poll_interval = status_poll_interval;
+        self
+    }
+}
+
+//
+//
+//
+/// Serves one local file under a one-time, unguessable URL until every byte has been served
+/// once, or until the shutdown token is cancelled, e.g. after the status endpoint reports done.
+///
+/// TikTok may pull with several range requests, so the URL serves any number of requests
+/// until then. After that it answers `410 Gone`, also on connections that are still open.
+#[derive(Debug)]
+pub struct PullServer {
+    video_url: Url,
+    local_addr: SocketAddr,
+    state: Arc<State>,
+    stop: CancellationToken,
+    handle: JoinHandle<Result<(), hyper::Error>>,
+}
+
+#[derive(Debug)]
+struct State {
+    file_path: PathBuf,
+    file_size: u64,
+    path_suffix: String,
+    content_type: String,
+    progress: Mutex<Progress>,
+    /// Cancelled once the download completed, or was given up on, which revokes the URL.
+    stop: CancellationToken,
+}
+
+#[derive(Debug, Default)]
+struct Progress {
+    served: Vec<Range<u64>>,
+    served_bytes: u64,
+    requests: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullServerReport {
+    pub served_bytes: u64,
+    pub requests: usize,
+    /// Every byte of the file was served at least once.
+    pub complete: bool,
+}
+
+impl PullServer {
+    pub async fn bind(
+        config: &PullServerConfig,
+        file_path: &PathBuf,
+        shutdown: Option<CancellationToken>,
+    ) -> Result<Self, PullServerError> {
+        let crate::tokio_fs_util::Info {
+            file_size,
+            file_name,
+        } = crate::tokio_fs_util::info(file_path)
+            .await
+            .map_err(PullServerError::GetFileInfoFailed)?;
+
+        let mut video_url = config.public_prefix.to_owned();
+        video_url
+            .path_segments_mut()
+            .map_err(|_| PullServerError::PublicPrefixInvalid)?
+            .pop_if_empty()
+            .push(&unguessable_token().map_err(PullServerError::TokenFailed)?)
+            .push(file_name.as_deref().unwrap_or("video"));
+        let path_suffix = {
+            let segments = video_url.path().rsplitn(3, '/').collect::<Vec<_>>();
+            format!("/{}/{}", segments[1], segments[0])
+        };
+
+        let stop = shutdown.map(|x| x.child_token()).unwrap_or_default();
+        let state = Arc::new(State {
+            file_path: file_path.to_owned(),
+            file_size,
+            path_suffix,
+            content_type: config
+                .content_type
+                .to_owned()
+                .unwrap_or_else(|| "video/mp4".into()),
+            progress: Default::default(),
+            stop: stop.to_owned(),
+        });
+
+        let listener = TcpListener::bind(config.bind_addr).map_err(PullServerError::BindFailed)?;
+        listener
+            .set_nonblocking(true)
+            .map_err(PullServerError::BindFailed)?;
+        let server = Server::from_tcp(listener).map_err(PullServerError::ServeFailed)?;
+
+        let make_service = {
+            let state = state.to_owned();
+            make_service_fn(move |_| {
+                let state = state.to_owned();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| {
+                        respond(state.to_owned(), request)
+                    }))
+                }
+            })
+        };
+        let server = server.serve(make_service);
+        let local_addr = server.local_addr();
+        let handle = tokio::spawn(server.with_graceful_shutdown({
+            let stop = stop.to_owned();
+            async move { stop.cancelled().await }
+        }));
+
+        Ok(Self {
+            video_url,
+            local_addr,
+            state,
+            stop,
+            handle,
+        })
+    }
+
+    pub fn video_url(&self) -> &Url {
+        &self.video_url
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn source_info(&self) -> VideoUploadInitRequestBodySourceInfo {
+        VideoUploadInitRequestBodySourceInfo::PullFromUrl {
+            video_url: self.video_url.to_owned(),
+        }
+    }
+
+    pub fn report(&self) -> PullServerReport {
+        self.state.report()
+    }
+
+    /// Stops without waiting for the download to complete, the URL is no longer served.
+    pub fn stop(&self) {
+        self.stop.cancel();
+    }
+
+    /// Waits for the server to shut down, in-flight responses are allowed to finish.
+    pub async fn wait(self) -> Result<PullServerReport, PullServerError> {
+        self.handle
+            .await
+            .map_err(|err| PullServerError::ServeJoinFailed(err.to_string()))?
+            .map_err(PullServerError::ServeFailed)?;
+
+        Ok(self.state.report())
+    }
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub struct PullFromFile {
+    pub init_ret: EndpointRet<VideoUploadInitResponseBody>,
+    pub report: PullServerReport,
+    /// The first publish status past `PROCESSING_DOWNLOAD`, None when every byte was served
+    /// first, or the init was rejected.
+    pub status: Option<PublishStatusFetchResponseBodyData>,
+}
+
+/// Serves `file_path`, submits `/inbox/video/init/` with its URL, and waits for TikTok to
+/// download every byte or for the publish status to leave `PROCESSING_DOWNLOAD`, e.g. with
+/// `video_pull_failed`, at most `PullServerConfig::timeout`. The URL is revoked and the server
+/// stopped then, or right away when the init is rejected or the URL is not under
+/// `verified_sources`.
+pub async fn pull_from_file<C>(
+    client: &C,
+    access_token: impl AsRef<str>,
+    config: &PullServerConfig,
+    verified_sources: &VerifiedSources,
+    file_path: &PathBuf,
+    shutdown: Option<CancellationToken>,
+) -> Result<PullFromFile, PullServerError>
+where
+    C: Client + Send + Sync,
+{
+    let access_token = access_token.as_ref();
+    let server = PullServer::bind(config, file_path, shutdown).await?;
+
+    let video_upload_init =
+        match VideoUploadInitEndpoint::new(access_token, server.source_info(), verified_sources) {
+            Ok(x) => x,
+            Err(err) => {
+                server.stop();
+                let _ = server.wait().await;
+                return Err(PullServerError::UrlUnverified(err));
+            }
+        };
+    let init_ret = match client.respond_endpoint(&video_upload_init).await {
+        Ok(x) => x,
+        Err(err) => {
+            server.stop();
+            let _ = server.wait().await;
+            return Err(PullServerError::RespondInitFailed(Box::new(err)));
+        }
+    };
+    let publish_status_fetch = match &init_ret {
+        EndpointRet::Ok(ok_json) => {
+            PublishStatusFetchEndpoint::new(access_token, &ok_json.data.publish_id)
+        }
+        EndpointRet::Other(_) => {
+            server.stop();
+            let report = server.wait().await?;
+            return Ok(PullFromFile {
+                init_ret,
+                report,
+                status: None,
+            });
+        }
+    };
+
+    let deadline = Instant::now() + config.timeout;
+    let status = loop {
+        let wait = core::cmp::min(
+            config.status_poll_interval,
+            deadline.saturating_duration_since(Instant::now()),
+        );
+        // Cancelled once every byte was served, or by the shutdown token.
+        if tokio::time::timeout(wait, server.stop.cancelled())
+            .await
+            .is_ok()
+        {
+            break None;
+        }
+        if Instant::now() >= deadline {
+            server.stop();
+            return Err(PullServerError::TimedOut(server.wait().await?));
+        }
+
+        // Failed fetches are retried at the next interval, until the deadline.
+        if let Ok(EndpointRet::Ok(ok_json)) = client.respond_endpoint(&publish_status_fetch).await {
+            if ok_json.data.status != PublishStatus::ProcessingDownload {
+                break Some(ok_json.data);
+            }
+        }
+    };
+
+    server.stop();
+    let report = server.wait().await?;
+
+    Ok(PullFromFile {
+        init_ret,
+        report,
+        status,
+    })
+}
+
+//
+fn unguessable_token() -> Result<String, getrandom::Error> {
+    let mut buf = [0_u8; 16];
+    getrandom::fill(&mut buf)?;
+    Ok(crate::hex::encode(&buf))
+}
+
+impl State {
+    fn report(&self) -> PullServerReport {
+        let progress = self.progress.lock().expect("Progress poisoned");
+        PullServerReport {
+            served_bytes: progress.served_bytes,
+            requests: progress.requests,
+            complete: progress.is_complete(self.file_size),
+        }
+    }
+
+    fn record(&self, range: Range<u64>) {
+        let mut progress = self.progress.lock().expect("Progress poisoned");
+        progress.record(range);
+        if progress.is_complete(self.file_size) {
+            self.stop.cancel();
+        }
+    }
+}
+
+impl Progress {
+    fn record(&mut self, range: Range<u64>) {
+        self.served_bytes += range.end - range.start;
+
+        self.served.push(range);
+        self.served.sort_by_key(|x| x.start);
+        let mut merged: Vec<Range<u64>> = vec![];
+        for range in self.served.drain(..) {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        self.served = merged;
+    }
+
+    fn is_complete(&self, file_size: u64) -> bool {
+        match self.served.as_slice() {
+            [range] => range.start == 0 && range.end >= file_size,
+            [] => file_size == 0 && self.requests > 0,
+            _ => false,
+        }
+    }
+}
+
+async fn respond(state: Arc<State>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    Ok(respond_inner(state, request)
+        .await
+        .unwrap_or_else(|status| {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = status;
+            response
+        }))
+}
+
+async fn respond_inner(
+    state: Arc<State>,
+    request: Request<Body>,
+) -> Result<Response<Body>, StatusCode> {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return Err(StatusCode::METHOD_NOT_ALLOWED);
+    }
+    if !request.uri().path().ends_with(&state.path_suffix) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if state.stop.is_cancelled() {
+        return Err(StatusCode::GONE);
+    }
+    state.progress.lock().expect("Progress poisoned").requests += 1;
+
+    let range = match request.headers().get(RANGE) {
+        Some(value) => Some(
+            value
+                .to_str()
+                .ok()
+                .and_then(|x| parse_range(x, state.file_size))
+                .ok_or(StatusCode::RANGE_NOT_SATISFIABLE)?,
+        ),
+        None => None,
+    };
+    let byte_range = range.to_owned().unwrap_or(0..state.file_size);
+
+    let mut response = Response::builder()
+        .header(ACCEPT_RANGES, "bytes")
+        .header(CONTENT_TYPE, state.content_type.as_str())
+        .header(CONTENT_LENGTH, byte_range.end - byte_range.start);
+    if range.is_some() {
+        response = response.status(StatusCode::PARTIAL_CONTENT).header(
+            CONTENT_RANGE,
+            format!(
+                "bytes {}-{}/{}",
+                byte_range.start,
+                byte_range.end - 1,
+                state.file_size
+            ),
+        );
+    }
+
+    let body = if request.method() == Method::HEAD || byte_range.is_empty() {
+        Body::empty()
+    } else {
+        let mut file = File::open(&state.file_path)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        file.seek(SeekFrom::Start(byte_range.start))
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let reader = CountingReader {
+            inner: file.take(byte_range.end - byte_range.start),
+            position: byte_range.start,
+            state: state.to_owned(),
+        };
+        Body::wrap_stream(ReaderStream::new(reader))
+    };
+
+    response
+        .body(body)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Parses a single `bytes=` range, multiple ranges are not supported.
+fn parse_range(value: &str, file_size: u64) -> Option<Range<u64>> {
+    let (start, end) = value.strip_prefix("bytes=")?.trim().split_once('-')?;
+    let range = match (start, end) {
+        ("", suffix) => {
+            let suffix = suffix.parse::<u64>().ok()?;
+            file_size.saturating_sub(suffix)..file_size
+        }
+        (start, "") => start.parse::<u64>().ok()?..file_size,
+        (start, end) => start.parse::<u64>().ok()?..end.parse::<u64>().ok()?.saturating_add(1),
+    };
+    let range = range.start..range.end.min(file_size);
+
+    (range.start < range.end).then_some(range)
+}
+
+struct CountingReader<R> {
+    inner: R,
+    position: u64,
+    state: Arc<State>,
+}
+
+impl<R> AsyncRead for CountingReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+
+        let filled_before = buf.filled().len();
+        let ret = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = &ret {
+            let n = (buf.filled().len() - filled_before) as u64;
+            if n > 0 {
+                this.state.record(this.position..this.position + n);
+                this.position += n;
+            }
+        }
+        ret
+    }
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub enum PullServerError {
+    GetFileInfoFailed(std::io::Error),
+    PublicPrefixInvalid,
+    BindFailed(std::io::Error),
+    ServeFailed(hyper::Error),
+    ServeJoinFailed(String),
+    TokenFailed(getrandom::Error),
+    UrlUnverified(UnverifiedUrl),
+    RespondInitFailed(Box<dyn std::error::Error + Send + Sync>),
+    /// `PullServerConfig::timeout` elapsed, with what was served until then.
+    TimedOut(PullServerReport),
+}
+impl core::fmt::Display for PullServerError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for PullServerError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use http_api_client::async_trait;
+    use tokio::{io::AsyncWriteExt as _, net::TcpStream};
+
+    /// Answers the status fetch with `status_body`.
+    struct MockClient {
+        status_body: &'static str,
+    }
+
+    #[async_trait]
+    impl Client for MockClient {
+        type RespondError = std::io::Error;
+
+        async fn respond(
+            &self,
+            request: http_api_client::Request<http_api_client::Body>,
+        ) -> Result<http_api_client::Response<http_api_client::Body>, Self::RespondError> {
+            let body = match request.uri().to_string().as_str() {
+                crate::endpoints::v2::video_upload_init::URL => {
+                    include_str!("../../tests/response_body_files/v2/video_upload_init.json")
+                }
+                crate::endpoints::v2::publish_status_fetch::URL => self.status_body,
+                x => panic!("{x}"),
+            };
+
+            Ok(http_api_client::Response::new(body.as_bytes().to_vec()))
+        }
+    }
+
+    async fn get(
+        addr: SocketAddr,
+        path: &str,
+        range: Option<&str>,
+    ) -> Result<(u16, Vec<u8>), Box<dyn std::error::Error>> {
+        let mut stream = TcpStream::connect(addr).await?;
+        let range = range.map(|x| format!("Range: {x}\r\n")).unwrap_or_default();
+        stream
+            .write_all(
+                format!(
+                    "GET {path} HTTP/1.1\r\nHost: localhost\r\n{range}Connection: close\r\n\r\n"
+                )
+                .as_bytes(),
+            )
+            .await?;
+
+        let mut buf = vec![];
+        stream.read_to_end(&mut buf).await?;
+        let header_end = buf
+            .windows(4)
+            .position(|x| x == b"\r\n\r\n")
+            .ok_or("header end missing")?;
+        let status = std::str::from_utf8(&buf[9..12])?.parse()?;
+
+        Ok((status, buf[header_end + 4..].to_vec()))
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-9", 100), Some(0..10));
+        assert_eq!(parse_range("bytes=90-", 100), Some(90..100));
+        assert_eq!(parse_range("bytes=-10", 100), Some(90..100));
+        assert_eq!(parse_range("bytes=50-200", 100), Some(50..100));
+        assert_eq!(parse_range("bytes=100-", 100), None);
+        assert_eq!(parse_range("bytes=0-1,5-6", 100), None);
+        assert_eq!(parse_range("items=0-1", 100), None);
+    }
+
+    #[tokio::test]
+    async fn test_pull_server() -> Result<(), Box<dyn std::error::Error>> {
+        let data = (0..1000).map(|x| (x % 251) as u8).collect::<Vec<_>>();
+        let file_path =
+            std::env::temp_dir().join(format!("tiktok-api-pull-server-{}.mp4", std::process::id()));
+        std::fs::write(&file_path, &data)?;
+
+        let config = PullServerConfig::new(
+            "127.0.0.1:0".parse()?,
+            "https://media.example.com/tiktok/".parse()?,
+        );
+        let server = PullServer::bind(&config, &file_path, None).await?;
+
+        let video_url = server.video_url().to_owned();
+        assert!(video_url
+            .as_str()
+            .starts_with("https://media.example.com/tiktok/"));
+        assert!(video_url.as_str().ends_with(&format!(
+            "/tiktok-api-pull-server-{}.mp4",
+            std::process::id()
+        )));
+        let path = video_url.path();
+        let addr = server.local_addr();
+
+        assert_eq!(get(addr, "/tiktok/other/x.mp4", None).await?.0, 404);
+        assert_eq!(get(addr, path, Some("bytes=2000-")).await?.0, 416);
+
+        let (status, body) = get(addr, path, Some("bytes=0-99")).await?;
+        assert_eq!(status, 206);
+        assert_eq!(body, data[..100]);
+        assert!(!server.report().complete);
+
+        let (status, body) = get(addr, path, Some("bytes=100-")).await?;
+        assert_eq!(status, 206);
+        assert_eq!(body, data[100..]);
+
+        // One-time, also for a connection that is still open.
+        let request = Request::get(path).body(Body::empty())?;
+        assert_eq!(
+            respond_inner(server.state.to_owned(), request).await.err(),
+            Some(StatusCode::GONE)
+        );
+
+        let report = server.wait().await?;
+        std::fs::remove_file(&file_path)?;
+        assert_eq!(
+            report,
+            PullServerReport {
+                served_bytes: 1000,
+                requests: 3,
+                complete: true,
+            }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pull_server_shutdown() -> Result<(), Box<dyn std::error::Error>> {
+        let file_path = std::env::temp_dir().join(format!(
+            "tiktok-api-pull-server-shutdown-{}.mp4",
+            std::process::id()
+        ));
+        std::fs::write(&file_path, [0_u8; 10])?;
+
+        let shutdown = CancellationToken::new();
+        let config = PullServerConfig::new(
+            "127.0.0.1:0".parse()?,
+            "https://media.example.com/".parse()?,
+        );
+        let server = PullServer::bind(&config, &file_path, Some(shutdown.to_owned())).await;
+        std::fs::remove_file(&file_path)?;
+        let server = server?;
+
+        shutdown.cancel();
+        let report = server.wait().await?;
+        assert!(!report.complete);
+        assert_eq!(report.requests, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pull_from_file() -> Result<(), Box<dyn std::error::Error>> {
+        let file_path = std::env::temp_dir().join(format!(
+            "tiktok-api-pull-from-file-{}.mp4",
+            std::process::id()
+        ));
+        std::fs::write(&file_path, [0_u8; 10])?;
+        let config = PullServerConfig::new(
+            "127.0.0.1:0".parse()?,
+            "https://media.example.com/".parse()?,
+        )
+        .with_status_poll_interval(Duration::from_millis(10));
+        let verified_sources = VerifiedSources::new().with_domain("example.com");
+
+        // TikTok failed to pull, nothing was served.
+        let ret = pull_from_file(
+            &MockClient {
+                status_body: r#"{"data":{"status":"FAILED","fail_reason":"video_pull_failed"}}"#,
+            },
+            "TOKEN",
+            &config,
+            &verified_sources,
+            &file_path,
+            None,
+        )
+        .await;
+
+        // Never terminal.
+        let ret_timed_out = pull_from_file(
+            &MockClient {
+                status_body: r#"{"data":{"status":"PROCESSING_DOWNLOAD"}}"#,
+            },
+            "TOKEN",
+            &config.to_owned().with_timeout(Duration::from_millis(50)),
+            &verified_sources,
+            &file_path,
+            None,
+        )
+        .await;
+
+        let ret_unverified = pull_from_file(
+            &MockClient {
+                status_body: r#"{"data":{"status":"PROCESSING_DOWNLOAD"}}"#,
+            },
+            "TOKEN",
+            &config,
+            &VerifiedSources::new().with_domain("other.com"),
+            &file_path,
+            None,
+        )
+        .await;
+        std::fs::remove_file(&file_path)?;
+
+        match ret_unverified {
+            Err(PullServerError::UrlUnverified(UnverifiedUrl::Unverified { .. })) => {}
+            x => panic!("{x:?}"),
+        }
+
+        let PullFromFile { report, status, .. } = ret?;
+        assert!(!report.complete);
+        let status = status.ok_or("status missing")?;
+        assert_eq!(status.status, PublishStatus::Failed);
+        assert_eq!(status.fail_reason.as_deref(), Some("video_pull_failed"));
+
+        match ret_timed_out {
+            Err(PullServerError::TimedOut(report)) => assert_eq!(report.requests, 0),
+            x => panic!("{x:?}"),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,274 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::io::{AsyncRead, ReadBuf};
+
+//
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
+
+pub trait Clock: Send + Sync + 'static {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration) -> Sleep;
+
+    /// Wall clock, for deadlines such as a scheduled `publish_at`.
+    fn utc_now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+//
+//
+//
+/// Token bucket shared by every clone, so one limiter can cap several concurrent uploads.
+#[derive(Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+    clock: Arc<dyn Clock>,
+}
+impl core::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("bucket", &self.bucket)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    bytes_per_second: u64,
+    burst: u64,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_second: u64, burst: u64) -> Self {
+        Self::with_clock(bytes_per_second, burst, TokioClock)
+    }
+
+    pub fn with_clock(bytes_per_second: u64, burst: u64, clock: impl Clock) -> Self {
+        assert!(
+            bytes_per_second > 0,
+            "bytes_per_second must be greater than 0"
+        );
+        assert!(burst > 0, "burst must be greater than 0");
+
+        let bucket = Bucket {
+            bytes_per_second,
+            burst,
+            tokens: burst as f64,
+            updated_at: clock.now(),
+        };
+
+        Self {
+            bucket: Arc::new(Mutex::new(bucket)),
+            clock: Arc::new(clock),
+        }
+    }
+
+    pub fn burst(&self) -> u64 {
+        self.bucket.lock().expect("RateLimiter poisoned").burst
+    }
+
+    /// Takes `n` bytes from the bucket and returns how long the caller must wait before sending them.
+    pub fn reserve(&self, n: u64) -> Duration {
+        let now = self.clock.now();
+        self.bucket
+            .lock()
+            .expect("RateLimiter poisoned")
+            .reserve(n, now)
+    }
+
+    pub fn throttle<R>(&self, reader: R) -> Throttled<R>
+    where
+        R: AsyncRead,
+    {
+        Throttled {
+            inner: reader,
+            rate_limiter: self.to_owned(),
+            sleep: None,
+        }
+    }
+}
+
+impl Bucket {
+    fn reserve(&mut self, n: u64, now: Instant) -> Duration {
+        if now > self.updated_at {
+            let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+            self.tokens =
+                (self.tokens + elapsed * self.bytes_per_second as f64).min(self.burst as f64);
+            self.updated_at = now;
+        }
+
+        self.tokens -= n as f64;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.bytes_per_second as f64)
+        }
+    }
+}
+
+//
+//
+//
+pub struct Throttled<R> {
+    inner: R,
+    rate_limiter: RateLimiter,
+    sleep: Option<Sleep>,
+}
+impl<R> core::fmt::Debug for Throttled<R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Throttled")
+            .field("rate_limiter", &self.rate_limiter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R> AsyncRead for Throttled<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+
+        if let Some(sleep) = this.sleep.as_mut() {
+            match sleep.as_mut().poll(cx) {
+                Poll::Ready(_) => this.sleep = None,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        // Never read more than one burst at a time, so a single read cannot overdraw the bucket by much.
+        let max_len = core::cmp::min(buf.remaining() as u64, this.rate_limiter.burst()) as usize;
+        let mut limited_buf = ReadBuf::new(buf.initialize_unfilled_to(max_len));
+        match Pin::new(&mut this.inner).poll_read(cx, &mut limited_buf) {
+            Poll::Ready(Ok(())) => {
+                let n = limited_buf.filled().len();
+                buf.advance(n);
+
+                let wait = this.rate_limiter.reserve(n as u64);
+                if !wait.is_zero() {
+                    this.sleep = Some(this.rate_limiter.clock.sleep(wait));
+                }
+
+                Poll::Ready(Ok(()))
+            }
+            x => x,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::io::AsyncReadExt as _;
+
+    #[derive(Clone)]
+    struct MockClock {
+        now: Arc<Mutex<Instant>>,
+        slept: Arc<Mutex<Vec<Duration>>>,
+    }
+    impl MockClock {
+        fn new() -> Self {
+            Self {
+                now: Arc::new(Mutex::new(Instant::now())),
+                slept: Default::default(),
+            }
+        }
+    }
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            *self.now.lock().unwrap()
+        }
+
+        fn sleep(&self, duration: Duration) -> Sleep {
+            *self.now.lock().unwrap() += duration;
+            self.slept.lock().unwrap().push(duration);
+            Box::pin(core::future::ready(()))
+        }
+    }
+
+    #[test]
+    fn test_reserve() {
+        let clock = MockClock::new();
+        let rate_limiter = RateLimiter::with_clock(1000, 500, clock.clone());
+
+        assert_eq!(rate_limiter.reserve(500), Duration::ZERO);
+        assert_eq!(rate_limiter.reserve(250), Duration::from_millis(250));
+
+        *clock.now.lock().unwrap() += Duration::from_millis(250);
+        assert_eq!(rate_limiter.reserve(0), Duration::ZERO);
+
+        // Idle time never refills the bucket above the burst.
+        *clock.now.lock().unwrap() += Duration::from_secs(10);
+        assert_eq!(rate_limiter.reserve(1000), Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_throttle() -> Result<(), Box<dyn std::error::Error>> {
+        let clock = MockClock::new();
+        let rate_limiter = RateLimiter::with_clock(1000, 1000, clock.clone());
+
+        let data = vec![1_u8; 5000];
+        let mut buf = vec![];
+        rate_limiter
+            .throttle(&data[..])
+            .read_to_end(&mut buf)
+            .await?;
+        assert_eq!(buf, data);
+
+        let slept = clock.slept.lock().unwrap().iter().sum::<Duration>();
+        assert_eq!(slept, Duration::from_secs(4));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_throttle_shared() -> Result<(), Box<dyn std::error::Error>> {
+        let clock = MockClock::new();
+        let rate_limiter = RateLimiter::with_clock(1000, 1000, clock.clone());
+
+        let data = vec![1_u8; 2000];
+        for _ in 0..2 {
+            let mut buf = vec![];
+            rate_limiter
+                .to_owned()
+                .throttle(&data[..])
+                .read_to_end(&mut buf)
+                .await?;
+        }
+
+        let slept = clock.slept.lock().unwrap().iter().sum::<Duration>();
+        assert_eq!(slept, Duration::from_secs(3));
+
+        Ok(())
+    }
+}
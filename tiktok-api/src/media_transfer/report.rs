@@ -0,0 +1,64 @@
+use core::{ops::Range, time::Duration};
+
+use http_api_client_endpoint::http::StatusCode;
+
+use super::{Sha256Digest, UploadError};
+
+//
+#[derive(Debug)]
+pub struct UploadReport {
+    pub total_bytes: usize,
+    pub chunks: Vec<ChunkReport>,
+    pub status: UploadStatus,
+    /// Of the whole source, only set when `status` is `Complete`.
+    pub sha256: Option<Sha256Digest>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChunkReport {
+    pub byte_range: Range<usize>,
+    pub attempts: usize,
+    /// Across all attempts.
+    pub elapsed: Duration,
+    /// `None` when the last attempt did not get a response.
+    pub status_code: Option<StatusCode>,
+    /// Of the bytes sent in the accepted attempt.
+    pub sha256: Option<Sha256Digest>,
+}
+
+#[derive(Debug)]
+pub enum UploadStatus {
+    Complete,
+    /// Stopped before the last chunk without an error, e.g. cancelled.
+    Partial,
+    Failed {
+        chunk: usize,
+        error: UploadError,
+    },
+}
+
+impl UploadReport {
+    pub fn is_complete(&self) -> bool {
+        matches!(self.status, UploadStatus::Complete)
+    }
+
+    pub fn completed_ranges(&self) -> Vec<Range<usize>> {
+        self.chunks
+            .iter()
+            .filter(|x| {
+                matches!(
+                    x.status_code,
+                    Some(StatusCode::PARTIAL_CONTENT | StatusCode::CREATED)
+                )
+            })
+            .map(|x| x.byte_range.to_owned())
+            .collect()
+    }
+
+    pub fn uploaded_bytes(&self) -> usize {
+        self.completed_ranges()
+            .iter()
+            .map(|x| x.end - x.start)
+            .sum()
+    }
+}
@@ -0,0 +1,112 @@
+use bytes::Bytes;
+use url::Url;
+
+use super::{upload_from_bytes, UploadError, UploadOptions, UploadReport};
+use crate::{
+    endpoints::v2::video_upload_init::VideoUploadInitRequestBodySourceInfo,
+    media_type::{sniff, MediaType, SniffError, SNIFF_LEN},
+    transport::Transport,
+};
+
+//
+#[derive(Debug, Clone)]
+pub enum UploadSource {
+    Bytes(Bytes),
+    #[cfg(feature = "with_tokio_fs")]
+    File(std::path::PathBuf),
+    #[cfg(feature = "with_tokio_fs")]
+    Spooled(std::sync::Arc<super::SpooledVideo>),
+}
+
+impl UploadSource {
+    pub async fn video_size(&self) -> Result<usize, UploadError> {
+        match self {
+            Self::Bytes(bytes) => Ok(bytes.len()),
+            #[cfg(feature = "with_tokio_fs")]
+            Self::File(file_path) => crate::tokio_fs_util::info(file_path)
+                .await
+                .map(|x| x.file_size as usize)
+                .map_err(UploadError::GetFileInfoFailed),
+            #[cfg(feature = "with_tokio_fs")]
+            Self::Spooled(spooled_video) => Ok(spooled_video.video_size()),
+        }
+    }
+
+    pub async fn media_type(&self) -> Result<MediaType, SniffError> {
+        match self {
+            Self::Bytes(bytes) => sniff(&bytes[..core::cmp::min(bytes.len(), SNIFF_LEN)]),
+            #[cfg(feature = "with_tokio_fs")]
+            Self::File(file_path) => crate::media_type::sniff_file(file_path).await,
+            #[cfg(feature = "with_tokio_fs")]
+            Self::Spooled(spooled_video) => spooled_video.media_type().await,
+        }
+    }
+
+    pub async fn source_info(
+        &self,
+        chunk_size: Option<usize>,
+    ) -> Result<VideoUploadInitRequestBodySourceInfo, UploadError> {
+        Ok(VideoUploadInitRequestBodySourceInfo::file_upload(
+            self.video_size().await?,
+            chunk_size,
+        ))
+    }
+
+    pub async fn upload<C>(
+        &self,
+        client: &C,
+        upload_url: Url,
+        content_type: &str,
+        options: &UploadOptions,
+    ) -> Result<UploadReport, UploadError>
+    where
+        C: Transport + ?Sized,
+    {
+        match self {
+            Self::Bytes(bytes) => {
+                upload_from_bytes(client, upload_url, content_type, bytes.to_owned(), options).await
+            }
+            #[cfg(feature = "with_tokio_fs")]
+            Self::File(file_path) => {
+                super::upload_from_file(client, upload_url, content_type, file_path, options).await
+            }
+            #[cfg(feature = "with_tokio_fs")]
+            Self::Spooled(spooled_video) => {
+                super::spool::upload_from_spooled(
+                    client,
+                    upload_url,
+                    content_type,
+                    spooled_video,
+                    options,
+                )
+                .await
+            }
+        }
+    }
+}
+
+impl From<Bytes> for UploadSource {
+    fn from(bytes: Bytes) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+impl From<Vec<u8>> for UploadSource {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes.into())
+    }
+}
+
+#[cfg(feature = "with_tokio_fs")]
+impl From<std::path::PathBuf> for UploadSource {
+    fn from(file_path: std::path::PathBuf) -> Self {
+        Self::File(file_path)
+    }
+}
+
+#[cfg(feature = "with_tokio_fs")]
+impl From<super::SpooledVideo> for UploadSource {
+    fn from(spooled_video: super::SpooledVideo) -> Self {
+        Self::Spooled(spooled_video.into())
+    }
+}
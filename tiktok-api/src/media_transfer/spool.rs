@@ -0,0 +1,264 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bytes::Bytes;
+use tokio::{
+    fs::OpenOptions,
+    io::{AsyncRead, AsyncReadExt as _, AsyncWriteExt as _},
+};
+use url::Url;
+
+use super::{
+    upload_from_bytes, upload_from_file, UploadError, UploadOptions, UploadReport, CHUNK_SIZE_MAX,
+};
+use crate::{
+    endpoints::v2::video_upload_init::VideoUploadInitRequestBodySourceInfo,
+    media_type::{sniff, sniff_file, MediaType, SniffError, SNIFF_LEN},
+    transport::Transport,
+};
+
+//
+pub const MEMORY_LIMIT_DEFAULT: usize = CHUNK_SIZE_MAX;
+/// TikTok accepts videos up to 4 GB.
+pub const SIZE_MAX_DEFAULT: u64 = 4 * 1024 * 1024 * 1024;
+
+static TEMP_FILE_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+//
+//
+//
+#[derive(Debug)]
+pub enum SpooledVideo {
+    Memory(Bytes),
+    File(SpooledFile),
+}
+
+#[derive(Debug)]
+pub struct SpooledFile {
+    pub path: PathBuf,
+    pub size: usize,
+}
+impl Drop for SpooledFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl SpooledVideo {
+    pub fn video_size(&self) -> usize {
+        match self {
+            Self::Memory(bytes) => bytes.len(),
+            Self::File(file) => file.size,
+        }
+    }
+
+    pub fn source_info(&self, chunk_size: Option<usize>) -> VideoUploadInitRequestBodySourceInfo {
+        VideoUploadInitRequestBodySourceInfo::file_upload(self.video_size(), chunk_size)
+    }
+
+    pub async fn media_type(&self) -> Result<MediaType, SniffError> {
+        match self {
+            Self::Memory(bytes) => sniff(&bytes[..core::cmp::min(bytes.len(), SNIFF_LEN)]),
+            Self::File(file) => sniff_file(&file.path).await,
+        }
+    }
+}
+
+//
+//
+//
+/// Reads `reader` to the end, keeping at most `memory_limit` bytes in memory and spilling
+/// the rest to a temporary file, so the total size is known before `/inbox/video/init/`.
+///
+/// Fails with `TooLarge` once more than `max_size` bytes were read, e.g. `SIZE_MAX_DEFAULT`.
+pub async fn spool<R>(
+    mut reader: R,
+    memory_limit: usize,
+    max_size: u64,
+    temp_dir: Option<&Path>,
+) -> Result<SpooledVideo, SpoolError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut buf = vec![];
+    (&mut reader)
+        .take(core::cmp::min(memory_limit as u64, max_size) + 1)
+        .read_to_end(&mut buf)
+        .await
+        .map_err(SpoolError::ReadFailed)?;
+
+    if buf.len() as u64 > max_size {
+        return Err(SpoolError::TooLarge { max_size });
+    }
+    if buf.len() <= memory_limit {
+        return Ok(SpooledVideo::Memory(buf.into()));
+    }
+
+    //
+    let temp_dir = temp_dir
+        .map(|x| x.to_owned())
+        .unwrap_or_else(std::env::temp_dir);
+    let path = temp_dir.join(format!(
+        "tiktok-api-spool-{}-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_micros(),
+        TEMP_FILE_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .await
+        .map_err(SpoolError::CreateTempFileFailed)?;
+    // Removes the file on any error below.
+    let mut spooled_file = SpooledFile { path, size: 0 };
+
+    file.write_all(&buf)
+        .await
+        .map_err(SpoolError::WriteTempFileFailed)?;
+    let copied = tokio::io::copy(
+        &mut (&mut reader).take(max_size - buf.len() as u64 + 1),
+        &mut file,
+    )
+    .await
+    .map_err(SpoolError::WriteTempFileFailed)?;
+    if buf.len() as u64 + copied > max_size {
+        return Err(SpoolError::TooLarge { max_size });
+    }
+    file.flush()
+        .await
+        .map_err(SpoolError::WriteTempFileFailed)?;
+
+    spooled_file.size = buf.len() + copied as usize;
+
+    Ok(SpooledVideo::File(spooled_file))
+}
+
+pub async fn upload_from_spooled<C>(
+    client: &C,
+    upload_url: Url,
+    content_type: &str,
+    spooled_video: &SpooledVideo,
+    options: &UploadOptions,
+) -> Result<UploadReport, UploadError>
+where
+    C: Transport + ?Sized,
+{
+    if spooled_video.video_size() == 0 {
+        return Err(UploadError::VideoEmpty);
+    }
+
+    match spooled_video {
+        SpooledVideo::Memory(bytes) => {
+            upload_from_bytes(client, upload_url, content_type, bytes.to_owned(), options).await
+        }
+        SpooledVideo::File(file) => {
+            upload_from_file(client, upload_url, content_type, &file.path, options).await
+        }
+    }
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub enum SpoolError {
+    ReadFailed(std::io::Error),
+    CreateTempFileFailed(std::io::Error),
+    WriteTempFileFailed(std::io::Error),
+    TooLarge { max_size: u64 },
+}
+impl core::fmt::Display for SpoolError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for SpoolError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_spool() -> Result<(), Box<dyn std::error::Error>> {
+        let data = (0..100_u8).collect::<Vec<_>>();
+
+        match spool(&data[..], 100, SIZE_MAX_DEFAULT, None).await? {
+            SpooledVideo::Memory(bytes) => assert_eq!(bytes, data),
+            x => panic!("{x:?}"),
+        }
+
+        let spooled_video = spool(&data[..], 10, 100, None).await?;
+        assert_eq!(spooled_video.video_size(), 100);
+        let path = match &spooled_video {
+            SpooledVideo::File(file) => {
+                assert_eq!(std::fs::read(&file.path)?, data);
+                file.path.to_owned()
+            }
+            x => panic!("{x:?}"),
+        };
+        drop(spooled_video);
+        assert!(!path.exists());
+
+        // Over `max_size`, in memory and spilled.
+        for memory_limit in [100, 10] {
+            match spool(&data[..], memory_limit, 99, None).await {
+                Err(SpoolError::TooLarge { max_size: 99 }) => {}
+                x => panic!("{x:?}"),
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upload_from_spooled_empty() -> Result<(), Box<dyn std::error::Error>> {
+        let transport = crate::transport::mock::MockTransport::upload_server();
+
+        for memory_limit in [10, 0] {
+            let spooled_video = spool(&[][..], memory_limit, SIZE_MAX_DEFAULT, None).await?;
+            assert_eq!(spooled_video.video_size(), 0);
+            match upload_from_spooled(
+                &transport,
+                "https://open-upload.tiktokapis.com/video/?upload_id=67890".parse()?,
+                "video/mp4",
+                &spooled_video,
+                &UploadOptions::default(),
+            )
+            .await
+            {
+                Err(UploadError::VideoEmpty) => {}
+                x => panic!("{x:?}"),
+            }
+        }
+        assert!(transport.requests().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_source_info() -> Result<(), Box<dyn std::error::Error>> {
+        let data = vec![0_u8; 1024];
+
+        match spool(&data[..], 10, SIZE_MAX_DEFAULT, None)
+            .await?
+            .source_info(None)
+        {
+            VideoUploadInitRequestBodySourceInfo::FileUpload {
+                video_size,
+                chunk_size,
+                total_chunk_count,
+            } => {
+                assert_eq!(video_size, 1024);
+                assert_eq!(chunk_size, 1024);
+                assert_eq!(total_chunk_count, 1);
+            }
+            x => panic!("{x:?}"),
+        }
+
+        Ok(())
+    }
+}
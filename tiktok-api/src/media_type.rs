@@ -0,0 +1,260 @@
+// https://developers.tiktok.com/doc/content-posting-api-media-transfer-guide/
+
+//
+pub const SNIFF_LEN: usize = 64;
+
+const ISO_BMFF_MP4_BRANDS: &[&[u8; 4]] = &[
+    b"isom", b"iso2", b"iso3", b"iso4", b"iso5", b"iso6", b"iso7", b"iso8", b"iso9", b"mp41",
+    b"mp42", b"avc1", b"dash", b"M4V ", b"M4VP", b"M4VH", b"mmp4", b"msnv", b"XAVC",
+];
+const QUICK_TIME_BRAND: &[u8; 4] = b"qt  ";
+/// Atoms that start old QuickTime files written without `ftyp`.
+const QUICK_TIME_LEADING_ATOMS: &[&[u8; 4]] = &[b"moov", b"mdat", b"wide", b"free", b"skip"];
+
+const EBML_MAGIC: &[u8; 4] = &[0x1A, 0x45, 0xDF, 0xA3];
+const EBML_DOC_TYPE_ID: &[u8; 2] = &[0x42, 0x82];
+
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    Mp4,
+    QuickTime,
+    WebM,
+}
+
+impl MediaType {
+    pub fn mime(&self) -> &'static str {
+        match self {
+            Self::Mp4 => "video/mp4",
+            Self::QuickTime => "video/quicktime",
+            Self::WebM => "video/webm",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::QuickTime => "mov",
+            Self::WebM => "webm",
+        }
+    }
+}
+
+//
+/// Detects the container from the first bytes of a file, `SNIFF_LEN` bytes are enough.
+pub fn sniff(header: &[u8]) -> Result<MediaType, SniffError> {
+    if header.len() >= 12 && &header[4..8] == b"ftyp" {
+        let box_size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let box_end = core::cmp::min(core::cmp::max(box_size, 12), header.len());
+
+        let major_brand = &header[8..12];
+        // `ftyp` = major_brand(4) + minor_version(4) + compatible_brands(4 * n)
+        let compatible_brands = header.get(16..box_end).unwrap_or_default().chunks_exact(4);
+
+        if major_brand == QUICK_TIME_BRAND {
+            return Ok(MediaType::QuickTime);
+        }
+        if ISO_BMFF_MP4_BRANDS.iter().any(|x| x[..] == *major_brand) {
+            return Ok(MediaType::Mp4);
+        }
+        if major_brand.starts_with(b"3gp") || major_brand.starts_with(b"3g2") {
+            return Err(SniffError::Unsupported(brand_name(major_brand)));
+        }
+        for brand in compatible_brands {
+            if ISO_BMFF_MP4_BRANDS.iter().any(|x| x[..] == *brand) {
+                // e.g. `M4A ` and `heic` also list `isom`, they are audio and images.
+                return match major_brand {
+                    b"M4A " | b"M4B " | b"M4P " | b"heic" | b"heix" | b"mif1" | b"msf1"
+                    | b"avif" | b"avis" => Err(SniffError::Unsupported(brand_name(major_brand))),
+                    _ => Ok(MediaType::Mp4),
+                };
+            }
+            if brand == QUICK_TIME_BRAND {
+                return Ok(MediaType::QuickTime);
+            }
+        }
+        return Err(SniffError::Unsupported(brand_name(major_brand)));
+    }
+
+    if header.len() >= 8
+        && QUICK_TIME_LEADING_ATOMS
+            .iter()
+            .any(|x| x[..] == header[4..8])
+    {
+        return Ok(MediaType::QuickTime);
+    }
+
+    if header.starts_with(EBML_MAGIC) {
+        return match ebml_doc_type(header) {
+            Some(b"webm") => Ok(MediaType::WebM),
+            Some(doc_type) => Err(SniffError::Unsupported(
+                String::from_utf8_lossy(doc_type).into(),
+            )),
+            None => Err(SniffError::Unrecognized),
+        };
+    }
+
+    if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"AVI " {
+        return Err(SniffError::Unsupported("avi".into()));
+    }
+
+    Err(SniffError::Unrecognized)
+}
+
+#[cfg(feature = "with_tokio_fs")]
+pub async fn sniff_file(file_path: &std::path::PathBuf) -> Result<MediaType, SniffError> {
+    use tokio::{fs::File, io::AsyncReadExt as _};
+
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    File::open(file_path)
+        .await
+        .map_err(SniffError::ReadFailed)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut header)
+        .await
+        .map_err(SniffError::ReadFailed)?;
+
+    sniff(&header)
+}
+
+fn brand_name(brand: &[u8]) -> String {
+    String::from_utf8_lossy(brand).trim_end().into()
+}
+
+fn ebml_doc_type(header: &[u8]) -> Option<&[u8]> {
+    let position = header
+        .windows(EBML_DOC_TYPE_ID.len())
+        .position(|x| x == EBML_DOC_TYPE_ID)?;
+    let rest = &header[position + EBML_DOC_TYPE_ID.len()..];
+
+    // EBML variable size integer, the count of leading zero bits gives the extra bytes.
+    let first = *rest.first()?;
+    let extra = first.leading_zeros() as usize;
+    if extra > 7 {
+        return None;
+    }
+    let mut size = (first & (0xFF >> (extra + 1))) as usize;
+    for x in rest.get(1..=extra)? {
+        size = (size << 8) | *x as usize;
+    }
+
+    rest.get(1 + extra..1 + extra + size)
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub enum SniffError {
+    /// A recognized container TikTok does not accept, e.g. `3gp4`, `matroska`, `avi`.
+    Unsupported(String),
+    Unrecognized,
+    #[cfg(feature = "with_tokio_fs")]
+    ReadFailed(std::io::Error),
+}
+impl core::fmt::Display for SniffError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for SniffError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ftyp(major_brand: &[u8; 4], compatible_brands: &[&[u8; 4]]) -> Vec<u8> {
+        let size = 16 + 4 * compatible_brands.len();
+        let mut buf = vec![];
+        buf.extend_from_slice(&(size as u32).to_be_bytes());
+        buf.extend_from_slice(b"ftyp");
+        buf.extend_from_slice(major_brand);
+        buf.extend_from_slice(&[0, 0, 2, 0]);
+        for brand in compatible_brands {
+            buf.extend_from_slice(*brand);
+        }
+        buf.extend_from_slice(&[0, 0, 0, 8]);
+        buf.extend_from_slice(b"free");
+        buf
+    }
+
+    #[test]
+    fn test_sniff_iso_bmff() {
+        assert_eq!(
+            sniff(&ftyp(b"isom", &[b"isom", b"iso2", b"avc1", b"mp41"])).unwrap(),
+            MediaType::Mp4
+        );
+        assert_eq!(
+            sniff(&ftyp(b"mp42", &[b"mp42", b"isom"])).unwrap(),
+            MediaType::Mp4
+        );
+        assert_eq!(
+            sniff(&ftyp(b"qt  ", &[b"qt  "])).unwrap(),
+            MediaType::QuickTime
+        );
+        assert_eq!(
+            sniff(&ftyp(b"XYZW", &[b"XYZW", b"isom"])).unwrap(),
+            MediaType::Mp4
+        );
+
+        match sniff(&ftyp(b"3gp4", &[b"isom", b"3gp4"])) {
+            Err(SniffError::Unsupported(x)) => assert_eq!(x, "3gp4"),
+            x => panic!("{x:?}"),
+        }
+        match sniff(&ftyp(b"M4A ", &[b"M4A ", b"mp42", b"isom"])) {
+            Err(SniffError::Unsupported(x)) => assert_eq!(x, "M4A"),
+            x => panic!("{x:?}"),
+        }
+        match sniff(&ftyp(b"heic", &[b"mif1", b"heic"])) {
+            Err(SniffError::Unsupported(x)) => assert_eq!(x, "heic"),
+            x => panic!("{x:?}"),
+        }
+    }
+
+    #[test]
+    fn test_sniff_quick_time_without_ftyp() {
+        let mut header = vec![0, 0, 0, 8];
+        header.extend_from_slice(b"wide");
+        header.extend_from_slice(&[0, 0, 0, 16]);
+        header.extend_from_slice(b"mdat");
+        assert_eq!(sniff(&header).unwrap(), MediaType::QuickTime);
+    }
+
+    #[test]
+    fn test_sniff_ebml() {
+        let mut header = EBML_MAGIC.to_vec();
+        header.extend_from_slice(&[0x9F, 0x42, 0x86, 0x81, 0x01, 0x42, 0xF7, 0x81, 0x01]);
+        header.extend_from_slice(&[0x42, 0x82, 0x84]);
+        header.extend_from_slice(b"webm");
+        header.extend_from_slice(&[0x42, 0x87, 0x81, 0x04]);
+        assert_eq!(sniff(&header).unwrap(), MediaType::WebM);
+
+        let mut header = EBML_MAGIC.to_vec();
+        header.extend_from_slice(&[0xA3, 0x42, 0x82, 0x88]);
+        header.extend_from_slice(b"matroska");
+        match sniff(&header) {
+            Err(SniffError::Unsupported(x)) => assert_eq!(x, "matroska"),
+            x => panic!("{x:?}"),
+        }
+    }
+
+    #[test]
+    fn test_sniff_other() {
+        let mut header = b"RIFF".to_vec();
+        header.extend_from_slice(&[0, 0, 0, 0]);
+        header.extend_from_slice(b"AVI LIST");
+        match sniff(&header) {
+            Err(SniffError::Unsupported(x)) => assert_eq!(x, "avi"),
+            x => panic!("{x:?}"),
+        }
+
+        match sniff(b"\x89PNG\r\n\x1a\n") {
+            Err(SniffError::Unrecognized) => {}
+            x => panic!("{x:?}"),
+        }
+        match sniff(b"") {
+            Err(SniffError::Unrecognized) => {}
+            x => panic!("{x:?}"),
+        }
+    }
+}
@@ -6,5 +6,68 @@ pub struct Error {
     pub captcha: Option<String>,
     pub desc_url: Option<String>,
     pub description: Option<String>,
-    pub error_code: isize,
+    pub error_code: LegacyErrorCode,
+}
+
+/// The integer codes of the oauth endpoints and of `/share/video/upload/`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(from = "isize", into = "isize")]
+pub enum LegacyErrorCode {
+    Ok,
+    /// 10002
+    InvalidParams,
+    /// 10008, AccessToken invalid or expired.
+    AccessTokenInvalid,
+    /// 10010, RefreshToken invalid or expired.
+    RefreshTokenInvalid,
+    /// 20000, share access token invalid or not found in the request.
+    ShareAccessTokenInvalid,
+    Unknown(isize),
+}
+
+impl From<isize> for LegacyErrorCode {
+    fn from(x: isize) -> Self {
+        match x {
+            0 => Self::Ok,
+            10002 => Self::InvalidParams,
+            10008 => Self::AccessTokenInvalid,
+            10010 => Self::RefreshTokenInvalid,
+            20000 => Self::ShareAccessTokenInvalid,
+            x => Self::Unknown(x),
+        }
+    }
+}
+
+impl From<LegacyErrorCode> for isize {
+    fn from(x: LegacyErrorCode) -> Self {
+        match x {
+            LegacyErrorCode::Ok => 0,
+            LegacyErrorCode::InvalidParams => 10002,
+            LegacyErrorCode::AccessTokenInvalid => 10008,
+            LegacyErrorCode::RefreshTokenInvalid => 10010,
+            LegacyErrorCode::ShareAccessTokenInvalid => 20000,
+            LegacyErrorCode::Unknown(x) => x,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_error_code() {
+        for (n, code) in [
+            (0, LegacyErrorCode::Ok),
+            (10008, LegacyErrorCode::AccessTokenInvalid),
+            (20000, LegacyErrorCode::ShareAccessTokenInvalid),
+            (10999, LegacyErrorCode::Unknown(10999)),
+        ] {
+            assert_eq!(
+                serde_json::from_str::<LegacyErrorCode>(&n.to_string()).unwrap(),
+                code
+            );
+            assert_eq!(serde_json::to_string(&code).unwrap(), n.to_string());
+        }
+    }
 }
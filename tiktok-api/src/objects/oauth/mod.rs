@@ -3,7 +3,7 @@ pub mod access_token;
 pub use access_token::AccessToken;
 
 pub mod error;
-pub use error::Error;
+pub use error::{Error, LegacyErrorCode};
 
 pub mod message;
 pub use message::Message;
@@ -13,13 +13,15 @@ pub struct ResponseErrorBody {
 mod tests {
     use super::*;
 
+    use crate::objects::oauth::LegacyErrorCode;
+
     #[test]
     fn test_de() {
         match serde_json::from_str::<ResponseErrorBody>(include_str!(
             "../../../tests/response_body_files/oauth/refresh_token__err.json"
         )) {
             Ok(err_json) => {
-                assert_eq!(err_json.data.error_code, 10002);
+                assert_eq!(err_json.data.error_code, LegacyErrorCode::InvalidParams);
                 assert_eq!(err_json.message, Message::ConstantError);
             }
             x => panic!("{x:?}"),
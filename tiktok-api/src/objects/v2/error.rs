@@ -1,3 +1,7 @@
+// https://developers.tiktok.com/doc/tiktok-api-v2-error-handling/
+// https://developers.tiktok.com/doc/content-posting-api-reference-direct-post/
+// https://developers.tiktok.com/doc/content-posting-api-reference-get-video-status/
+
 use serde::{Deserialize, Serialize};
 use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
 
@@ -20,6 +24,147 @@ pub enum ErrorCode {
     RateLimitExceeded,
     ScopeNotAuthorized,
     ScopePermissionMissed,
+    TokenNotAuthorizedForSpecifiedDeviceid,
+    // Posting
+    SpamRisk,
+    SpamRiskText,
+    SpamRiskTooManyPendingShare,
+    SpamRiskTooManyPosts,
+    SpamRiskUserBannedFromPosting,
+    ReachedActiveUserCap,
+    UnauditedClientCanOnlyPostToPrivateAccounts,
+    UrlOwnershipUnverified,
+    PrivacyLevelOptionMismatch,
+    // Processing, also the publish status `fail_reason`
+    FileFormatCheckFailed,
+    DurationCheckFailed,
+    FrameRateCheckFailed,
+    PictureSizeCheckFailed,
+    VideoPullFailed,
+    PhotoPullFailed,
+    PublishCancelled,
+    AuthRemoved,
     #[serde(other)]
     Other(Box<str>),
 }
+
+impl ErrorCode {
+    /// What went wrong, for logs and support.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            Self::Ok => "The request succeeded.",
+            Self::AccessTokenInvalid => "The access token is invalid, expired or revoked.",
+            Self::InternalError => "TikTok failed to handle the request.",
+            Self::InvalidFileUpload => "The uploaded file or its chunking does not meet the requirements.",
+            Self::InvalidParams => "The request parameters are invalid.",
+            Self::RateLimitExceeded => "The request was rate limited.",
+            Self::ScopeNotAuthorized => "The user did not grant the scope this request needs.",
+            Self::ScopePermissionMissed => "The app has not been approved for the scope this request needs.",
+            Self::TokenNotAuthorizedForSpecifiedDeviceid => "The access token was not issued for the device this request came from.",
+            Self::SpamRisk => "The post was flagged as spam.",
+            Self::SpamRiskText => "The post's text was flagged as spam.",
+            Self::SpamRiskTooManyPendingShare => "The user has too many shares awaiting posting in their inbox, five within 24 hours.",
+            Self::SpamRiskTooManyPosts => "The user reached the daily post cap for apps.",
+            Self::SpamRiskUserBannedFromPosting => "The user is banned from posting.",
+            Self::ReachedActiveUserCap => "The app reached the daily cap of users that can post through it.",
+            Self::UnauditedClientCanOnlyPostToPrivateAccounts => "An app not yet audited can only post to private accounts, with SELF_ONLY.",
+            Self::UrlOwnershipUnverified => "The domain or URL prefix of a PULL_FROM_URL source is not verified for the app.",
+            Self::PrivacyLevelOptionMismatch => "The privacy level is not among the options the creator info allows.",
+            Self::FileFormatCheckFailed => "The file format is not supported.",
+            Self::DurationCheckFailed => "The video is longer or shorter than allowed.",
+            Self::FrameRateCheckFailed => "The video frame rate is not supported.",
+            Self::PictureSizeCheckFailed => "The video or photo dimensions are not supported.",
+            Self::VideoPullFailed => "TikTok could not download the video from the URL.",
+            Self::PhotoPullFailed => "TikTok could not download the photo from the URL.",
+            Self::PublishCancelled => "The publish was cancelled.",
+            Self::AuthRemoved => "The user removed the app's authorization.",
+            Self::Other(_) => "An error this crate does not know.",
+        }
+    }
+
+    /// What to tell the user, None when there is nothing they can do.
+    pub fn suggested_action(&self) -> Option<&'static str> {
+        match self {
+            Self::AccessTokenInvalid
+            | Self::TokenNotAuthorizedForSpecifiedDeviceid
+            | Self::AuthRemoved => Some("Log in with TikTok again."),
+            Self::ScopeNotAuthorized => {
+                Some("Log in with TikTok again and allow the requested permissions.")
+            }
+            Self::InternalError | Self::RateLimitExceeded | Self::ReachedActiveUserCap => {
+                Some("Try again later.")
+            }
+            Self::InvalidFileUpload | Self::FileFormatCheckFailed => {
+                Some("Upload an MP4, MOV or WebM video.")
+            }
+            Self::SpamRisk | Self::SpamRiskText => {
+                Some("Change the caption or the video, and try again.")
+            }
+            Self::SpamRiskTooManyPendingShare => {
+                Some("Post or delete the videos waiting in your TikTok inbox, then try again.")
+            }
+            Self::SpamRiskTooManyPosts => {
+                Some("You reached today's posting limit, try again tomorrow.")
+            }
+            Self::SpamRiskUserBannedFromPosting => {
+                Some("Your TikTok account cannot post at the moment, check TikTok for details.")
+            }
+            Self::UnauditedClientCanOnlyPostToPrivateAccounts => {
+                Some("Make your TikTok account private, or post as only visible to you.")
+            }
+            Self::PrivacyLevelOptionMismatch => Some("Choose one of the offered privacy levels."),
+            Self::DurationCheckFailed => Some("Trim the video to the allowed duration."),
+            Self::FrameRateCheckFailed => Some("Export the video at 23 to 60 frames per second."),
+            Self::PictureSizeCheckFailed => {
+                Some("Export the video at least 360 pixels in height and width.")
+            }
+            Self::VideoPullFailed | Self::PhotoPullFailed => {
+                Some("Try again, or upload the file instead.")
+            }
+            Self::Ok
+            | Self::InvalidParams
+            | Self::ScopePermissionMissed
+            | Self::UrlOwnershipUnverified
+            | Self::PublishCancelled
+            | Self::Other(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_de() {
+        for (s, code) in [
+            ("ok", ErrorCode::Ok),
+            (
+                "token_not_authorized_for_specified_deviceid",
+                ErrorCode::TokenNotAuthorizedForSpecifiedDeviceid,
+            ),
+            (
+                "spam_risk_too_many_pending_share",
+                ErrorCode::SpamRiskTooManyPendingShare,
+            ),
+            (
+                "unaudited_client_can_only_post_to_private_accounts",
+                ErrorCode::UnauditedClientCanOnlyPostToPrivateAccounts,
+            ),
+            (
+                "picture_size_check_failed",
+                ErrorCode::PictureSizeCheckFailed,
+            ),
+            ("foo", ErrorCode::Other("foo".into())),
+        ] {
+            assert_eq!(s.parse::<ErrorCode>().unwrap(), code);
+            assert_eq!(code.to_string(), s);
+        }
+
+        assert!(ErrorCode::SpamRiskTooManyPendingShare
+            .suggested_action()
+            .unwrap()
+            .contains("inbox"));
+        assert_eq!(ErrorCode::Ok.suggested_action(), None);
+    }
+}
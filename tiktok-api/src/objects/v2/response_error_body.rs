@@ -32,7 +32,7 @@ mod tests {
             "../../../tests/response_body_files/v2/video_upload_init__err__spam_risk_too_many_pending_share.json"
         )) {
             Ok(err_json) => {
-                assert_eq!(err_json.error.code, ErrorCode::Other("spam_risk_too_many_pending_share".into()));
+                assert_eq!(err_json.error.code, ErrorCode::SpamRiskTooManyPendingShare);
             }
             x => panic!("{x:?}"),
         }
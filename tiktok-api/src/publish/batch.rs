@@ -0,0 +1,263 @@
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt as _};
+use http_api_client::Client;
+
+use super::{upload, PendingShares, PublishUpload, PublishUploadError};
+use crate::{
+    endpoints::v2::{
+        video_upload_init::VideoUploadInitResponseBody, EndpointRet, VideoUploadInitEndpoint,
+    },
+    media_transfer::{UploadError, UploadOptions, UploadSource},
+    objects::v2::ErrorCode,
+    transport::Transport,
+};
+
+//
+pub const CONCURRENCY_DEFAULT: usize = 2;
+
+//
+#[derive(Debug, Clone)]
+pub struct BatchItem {
+    pub open_id: String,
+    pub access_token: String,
+    pub source: UploadSource,
+    pub content_type: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Items transferred at the same time, across all users.
+    pub concurrency: usize,
+    pub upload: UploadOptions,
+}
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: CONCURRENCY_DEFAULT,
+            upload: Default::default(),
+        }
+    }
+}
+
+//
+#[derive(Debug)]
+pub struct BatchItemResult {
+    /// Position in the `items` passed to `publish_batch`.
+    pub index: usize,
+    pub open_id: String,
+    pub ret: Result<PublishUpload, BatchItemError>,
+}
+
+//
+//
+//
+/// Inits and uploads every item, in input order but up to `options.concurrency` at once.
+/// Items whose user has no free pending share slot are held back without any request.
+pub async fn publish_batch<C, T>(
+    client: &C,
+    upload_client: &T,
+    items: Vec<BatchItem>,
+    pending_shares: &PendingShares,
+    options: &BatchOptions,
+) -> Vec<BatchItemResult>
+where
+    C: Client + Send + Sync,
+    T: Transport + ?Sized,
+{
+    let mut results = stream::iter(items.into_iter().enumerate())
+        .map(|(index, item)| async move {
+            let ret = publish_item(client, upload_client, &item, pending_shares, options).await;
+            BatchItemResult {
+                index,
+                open_id: item.open_id,
+                ret,
+            }
+        })
+        .buffer_unordered(core::cmp::max(options.concurrency, 1))
+        .collect::<Vec<_>>()
+        .await;
+    results.sort_by_key(|x| x.index);
+    results
+}
+
+async fn publish_item<C, T>(
+    client: &C,
+    upload_client: &T,
+    item: &BatchItem,
+    pending_shares: &PendingShares,
+    options: &BatchOptions,
+) -> Result<PublishUpload, BatchItemError>
+where
+    C: Client + Send + Sync,
+    T: Transport + ?Sized,
+{
+    let reservation = pending_shares
+        .try_reserve(&item.open_id)
+        .map_err(|retry_after| BatchItemError::HeldBack { retry_after })?;
+
+    let video_size = match item.source.video_size().await {
+        Ok(x) => x,
+        Err(err) => {
+            pending_shares.release(reservation);
+            return Err(BatchItemError::SourceInfoFailed(err));
+        }
+    };
+
+    let video_upload_init = VideoUploadInitEndpoint::file_upload(
+        &item.access_token,
+        video_size,
+        options.upload.chunk_size,
+    );
+    let init_data = match client.respond_endpoint(&video_upload_init).await {
+        Ok(EndpointRet::Ok(ok_json)) => {
+            pending_shares.confirm(reservation, &ok_json.data.publish_id);
+            ok_json.data
+        }
+        Ok(ret) => {
+            pending_shares.release(reservation);
+            if let EndpointRet::Other((_, Ok(err_json))) = &ret {
+                if err_json.error.code == ErrorCode::SpamRiskTooManyPendingShare {
+                    pending_shares.saturate(&item.open_id);
+                }
+            }
+            return Err(BatchItemError::InitRejected(ret));
+        }
+        Err(err) => {
+            pending_shares.release(reservation);
+            return Err(BatchItemError::InitFailed(Box::new(err)));
+        }
+    };
+
+    upload(
+        client,
+        upload_client,
+        &item.access_token,
+        &init_data,
+        &item.source,
+        &item.content_type,
+        &options.upload,
+    )
+    .await
+    .map_err(BatchItemError::UploadFailed)
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub enum BatchItemError {
+    /// The user has no free pending share slot, nothing was sent.
+    HeldBack {
+        retry_after: DateTime<Utc>,
+    },
+    SourceInfoFailed(UploadError),
+    InitFailed(Box<dyn std::error::Error + Send + Sync>),
+    InitRejected(EndpointRet<VideoUploadInitResponseBody>),
+    UploadFailed(PublishUploadError),
+}
+impl core::fmt::Display for BatchItemError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for BatchItemError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use http_api_client::{async_trait, Body, Request, Response};
+    use http_api_client_endpoint::http::StatusCode;
+
+    use crate::transport::mock::MockTransport;
+
+    #[derive(Default)]
+    struct MockClient {
+        inits: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Client for MockClient {
+        type RespondError = std::io::Error;
+
+        async fn respond(
+            &self,
+            request: Request<Body>,
+        ) -> Result<Response<Body>, Self::RespondError> {
+            assert_eq!(request.uri(), crate::endpoints::v2::video_upload_init::URL);
+
+            // The third init is rejected, as if the user shared elsewhere.
+            if self.inits.fetch_add(1, Ordering::SeqCst) == 2 {
+                let mut response = Response::new(
+                    include_str!(
+                        "../../tests/response_body_files/v2/video_upload_init__err__spam_risk_too_many_pending_share.json"
+                    )
+                    .as_bytes()
+                    .to_vec(),
+                );
+                *response.status_mut() = StatusCode::FORBIDDEN;
+                return Ok(response);
+            }
+
+            Ok(Response::new(
+                include_str!("../../tests/response_body_files/v2/video_upload_init.json")
+                    .as_bytes()
+                    .to_vec(),
+            ))
+        }
+    }
+
+    fn item(open_id: &str) -> BatchItem {
+        BatchItem {
+            open_id: open_id.into(),
+            access_token: "TOKEN".into(),
+            source: vec![0_u8; 16].into(),
+            content_type: "video/mp4".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish_batch() {
+        let client = MockClient::default();
+        let transport = MockTransport::upload_server();
+        let pending_shares = PendingShares::new(2);
+
+        let results = publish_batch(
+            &client,
+            &transport,
+            vec![item("u1"), item("u1"), item("u1"), item("u2"), item("u2")],
+            &pending_shares,
+            &BatchOptions {
+                concurrency: 1,
+                ..Default::default()
+            },
+        )
+        .await;
+
+        assert_eq!(
+            results.iter().map(|x| x.index).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4]
+        );
+        assert!(results[0].ret.is_ok());
+        assert!(results[1].ret.is_ok());
+        match &results[2].ret {
+            Err(BatchItemError::HeldBack { .. }) => {}
+            x => panic!("{x:?}"),
+        }
+        match &results[3].ret {
+            Err(BatchItemError::InitRejected(EndpointRet::Other((StatusCode::FORBIDDEN, _)))) => {}
+            x => panic!("{x:?}"),
+        }
+        match &results[4].ret {
+            Err(BatchItemError::HeldBack { .. }) => {}
+            x => panic!("{x:?}"),
+        }
+
+        assert_eq!(client.inits.load(Ordering::SeqCst), 3);
+        assert_eq!(transport.requests().len(), 2);
+        assert_eq!(pending_shares.pending("u1"), 2);
+        assert_eq!(pending_shares.pending("u2"), 2);
+    }
+}
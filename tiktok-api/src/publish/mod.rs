@@ -0,0 +1,11 @@
+//
+pub mod upload;
+pub use upload::{upload, PublishUpload, PublishUploadError, UploadCancelled};
+
+pub mod pending_shares;
+pub use pending_shares::PendingShares;
+
+#[cfg(feature = "with_publish_batch")]
+pub mod batch;
+#[cfg(feature = "with_publish_batch")]
+pub use batch::{publish_batch, BatchItem, BatchItemError, BatchItemResult, BatchOptions};
@@ -0,0 +1,195 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use chrono::{DateTime, Duration, Utc};
+
+//
+/// TikTok allows this many pending inbox shares per user within `PENDING_SHARES_WINDOW_SECS`.
+pub const PENDING_SHARES_LIMIT_DEFAULT: usize = 5;
+pub const PENDING_SHARES_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+//
+//
+//
+/// Counts the pending inbox shares per open_id, so inits that would fail with
+/// `spam_risk_too_many_pending_share` are held back. Share one across batches.
+#[derive(Debug)]
+pub struct PendingShares {
+    limit: usize,
+    window: Duration,
+    shares: Mutex<HashMap<String, Vec<PendingShare>>>,
+    seq: AtomicU64,
+}
+
+#[derive(Debug, Clone)]
+struct PendingShare {
+    id: u64,
+    at: DateTime<Utc>,
+    publish_id: Option<String>,
+}
+
+/// A slot taken before the init, `confirm` it with the publish_id or `release` it.
+#[derive(Debug)]
+pub struct Reservation {
+    open_id: String,
+    id: u64,
+}
+
+impl Default for PendingShares {
+    fn default() -> Self {
+        Self::new(PENDING_SHARES_LIMIT_DEFAULT)
+    }
+}
+
+impl PendingShares {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            window: Duration::seconds(PENDING_SHARES_WINDOW_SECS),
+            shares: Default::default(),
+            seq: Default::default(),
+        }
+    }
+
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn pending(&self, open_id: &str) -> usize {
+        self.pending_at(open_id, Utc::now())
+    }
+
+    /// On `Err`, no slot is free before the returned time.
+    pub fn try_reserve(&self, open_id: &str) -> Result<Reservation, DateTime<Utc>> {
+        self.try_reserve_at(open_id, Utc::now())
+    }
+
+    pub fn confirm(&self, reservation: Reservation, publish_id: impl AsRef<str>) {
+        let mut shares = self.shares.lock().expect("PendingShares poisoned");
+        if let Some(share) = shares
+            .get_mut(&reservation.open_id)
+            .and_then(|x| x.iter_mut().find(|x| x.id == reservation.id))
+        {
+            share.publish_id = Some(publish_id.as_ref().into());
+        }
+    }
+
+    pub fn release(&self, reservation: Reservation) {
+        let mut shares = self.shares.lock().expect("PendingShares poisoned");
+        if let Some(x) = shares.get_mut(&reservation.open_id) {
+            x.retain(|x| x.id != reservation.id);
+        }
+    }
+
+    /// Frees the slot once the user posted or dismissed the share, e.g. from a webhook.
+    pub fn remove(&self, open_id: &str, publish_id: &str) {
+        let mut shares = self.shares.lock().expect("PendingShares poisoned");
+        if let Some(x) = shares.get_mut(open_id) {
+            x.retain(|x| x.publish_id.as_deref() != Some(publish_id));
+        }
+    }
+
+    /// After TikTok answered `spam_risk_too_many_pending_share`, shares made elsewhere
+    /// count too, so the user is treated as full for a whole window.
+    pub fn saturate(&self, open_id: &str) {
+        self.saturate_at(open_id, Utc::now())
+    }
+
+    //
+    fn pending_at(&self, open_id: &str, now: DateTime<Utc>) -> usize {
+        let mut shares = self.shares.lock().expect("PendingShares poisoned");
+        self.prune(&mut shares, open_id, now).len()
+    }
+
+    fn try_reserve_at(
+        &self,
+        open_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Reservation, DateTime<Utc>> {
+        let mut shares = self.shares.lock().expect("PendingShares poisoned");
+        let pending = self.prune(&mut shares, open_id, now);
+
+        if pending.len() >= self.limit {
+            let oldest = pending.iter().map(|x| x.at).min().unwrap_or(now);
+            return Err(oldest + self.window);
+        }
+
+        let id = self.seq.fetch_add(1, Ordering::Relaxed);
+        pending.push(PendingShare {
+            id,
+            at: now,
+            publish_id: None,
+        });
+
+        Ok(Reservation {
+            open_id: open_id.into(),
+            id,
+        })
+    }
+
+    fn saturate_at(&self, open_id: &str, now: DateTime<Utc>) {
+        let mut shares = self.shares.lock().expect("PendingShares poisoned");
+        let pending = self.prune(&mut shares, open_id, now);
+        while pending.len() < self.limit {
+            pending.push(PendingShare {
+                id: self.seq.fetch_add(1, Ordering::Relaxed),
+                at: now,
+                publish_id: None,
+            });
+        }
+    }
+
+    fn prune<'a>(
+        &self,
+        shares: &'a mut HashMap<String, Vec<PendingShare>>,
+        open_id: &str,
+        now: DateTime<Utc>,
+    ) -> &'a mut Vec<PendingShare> {
+        let pending = shares.entry(open_id.into()).or_default();
+        pending.retain(|x| x.at + self.window > now);
+        pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_shares() {
+        let pending_shares = PendingShares::new(2);
+        let now = Utc::now();
+
+        let reservation_1 = pending_shares.try_reserve_at("u1", now).unwrap();
+        let reservation_2 = pending_shares
+            .try_reserve_at("u1", now + Duration::hours(1))
+            .unwrap();
+        assert_eq!(
+            pending_shares.try_reserve_at("u1", now).unwrap_err(),
+            now + Duration::hours(24)
+        );
+        assert!(pending_shares.try_reserve_at("u2", now).is_ok());
+
+        // A failed init gives the slot back.
+        pending_shares.release(reservation_2);
+        assert_eq!(pending_shares.pending_at("u1", now), 1);
+
+        // Posted by the user.
+        pending_shares.confirm(reservation_1, "p1");
+        pending_shares.remove("u1", "p1");
+        assert_eq!(pending_shares.pending_at("u1", now), 0);
+
+        // Expired by the window.
+        pending_shares.saturate_at("u1", now);
+        assert!(pending_shares.try_reserve_at("u1", now).is_err());
+        assert!(pending_shares
+            .try_reserve_at("u1", now + Duration::hours(24))
+            .is_ok());
+    }
+}
@@ -0,0 +1,436 @@
+use std::borrow::Cow;
+
+use http_api_client::Client;
+
+use crate::{
+    endpoints::v2::{
+        publish_cancel::PublishCancelResponseBody,
+        video_upload_init::{VideoUploadInitResponseBody, VideoUploadInitResponseBodyData},
+        EndpointRet, PublishCancelEndpoint, VideoUploadInitEndpoint,
+    },
+    media_transfer::{UploadError, UploadOptions, UploadReport, UploadSource, UploadStatus},
+    transport::Transport,
+};
+
+//
+/// `upload_url_expires_at` is an estimate, a rejection this close to it counts as an expiry.
+pub const UPLOAD_URL_EXPIRY_MARGIN_SECS: i64 = 5 * 60;
+
+//
+//
+//
+pub async fn upload<C, T>(
+    client: &C,
+    upload_client: &T,
+    access_token: impl AsRef<str>,
+    init_data: &VideoUploadInitResponseBodyData,
+    source: &UploadSource,
+    content_type: &str,
+    options: &UploadOptions,
+) -> Result<PublishUpload, PublishUploadError>
+where
+    C: Client + Send + Sync,
+    T: Transport + ?Sized,
+{
+    let mut init_data = init_data.to_owned();
+    let mut expired_publish_ids = vec![];
+    let mut options = Cow::Borrowed(options);
+
+    let report = loop {
+        let upload_url = init_data
+            .upload_url
+            .to_owned()
+            .ok_or(PublishUploadError::UploadUrlMissing)?;
+
+        let report = source
+            .upload(upload_client, upload_url, content_type, &options)
+            .await
+            .map_err(PublishUploadError::UploadFailed)?;
+
+        if !is_upload_url_expired(&init_data, &report) {
+            break report;
+        }
+
+        expired_publish_ids.push(init_data.publish_id.to_owned());
+        if expired_publish_ids.len() > options.max_reinitializations {
+            return Err(PublishUploadError::UploadUrlExpired {
+                expired_publish_ids,
+                report,
+            });
+        }
+
+        // The source is sent again from the start under a new publish_id, chunks accepted
+        // by an earlier run were accepted on the expired `upload_url` only.
+        options.to_mut().start_chunk = 0;
+        let video_size = source
+            .video_size()
+            .await
+            .map_err(PublishUploadError::UploadFailed)?;
+        let video_upload_init = VideoUploadInitEndpoint::file_upload(
+            access_token.as_ref(),
+            video_size,
+            options.chunk_size,
+        );
+        init_data = match client.respond_endpoint(&video_upload_init).await {
+            Ok(EndpointRet::Ok(ok_json)) => ok_json.data,
+            ret => {
+                return Err(PublishUploadError::ReinitializeFailed {
+                    expired_publish_ids,
+                    ret: ret
+                        .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync>),
+                })
+            }
+        };
+    };
+
+    if report.is_complete() {
+        return Ok(PublishUpload {
+            publish_id: init_data.publish_id,
+            report,
+            expired_publish_ids,
+        });
+    }
+
+    if !options.is_cancelled() {
+        return Err(PublishUploadError::Incomplete {
+            publish_id: init_data.publish_id,
+            report,
+        });
+    }
+
+    let publish_cancel_ret = if options.cancel_publish_on_cancellation {
+        let publish_cancel = PublishCancelEndpoint::new(access_token, &init_data.publish_id);
+        Some(
+            client
+                .respond_endpoint(&publish_cancel)
+                .await
+                .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync>),
+        )
+    } else {
+        None
+    };
+
+    Err(PublishUploadError::Cancelled(UploadCancelled {
+        publish_id: init_data.publish_id,
+        report,
+        publish_cancel_ret,
+    }))
+}
+
+fn is_upload_url_expired(
+    init_data: &VideoUploadInitResponseBodyData,
+    report: &UploadReport,
+) -> bool {
+    match &report.status {
+        UploadStatus::Failed { error, .. } => {
+            error.is_upload_url_expired_response()
+                && init_data.upload_url_remaining()
+                    < chrono::Duration::seconds(UPLOAD_URL_EXPIRY_MARGIN_SECS)
+        }
+        _ => false,
+    }
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub struct PublishUpload {
+    pub publish_id: String,
+    pub report: UploadReport,
+    /// Publish ids whose `upload_url` expired before this one, oldest first.
+    pub expired_publish_ids: Vec<String>,
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub struct UploadCancelled {
+    pub publish_id: String,
+    pub report: UploadReport,
+    /// `None` unless `UploadOptions::cancel_publish_on_cancellation` is set.
+    pub publish_cancel_ret: Option<
+        Result<EndpointRet<PublishCancelResponseBody>, Box<dyn std::error::Error + Send + Sync>>,
+    >,
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub enum PublishUploadError {
+    UploadUrlMissing,
+    UploadFailed(UploadError),
+    Incomplete {
+        publish_id: String,
+        report: UploadReport,
+    },
+    Cancelled(UploadCancelled),
+    /// `UploadOptions::max_reinitializations` exhausted, `report` is of the last attempt.
+    UploadUrlExpired {
+        expired_publish_ids: Vec<String>,
+        report: UploadReport,
+    },
+    ReinitializeFailed {
+        expired_publish_ids: Vec<String>,
+        ret: Result<
+            EndpointRet<VideoUploadInitResponseBody>,
+            Box<dyn std::error::Error + Send + Sync>,
+        >,
+    },
+}
+impl core::fmt::Display for PublishUploadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for PublishUploadError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use http_api_client::{async_trait, Body, Request, Response};
+    use tokio_util::sync::CancellationToken;
+
+    use http_api_client_endpoint::http::StatusCode;
+
+    use crate::transport::{mock::MockTransport, TransportResponse};
+
+    struct MockClient;
+
+    #[async_trait]
+    impl Client for MockClient {
+        type RespondError = std::io::Error;
+
+        async fn respond(
+            &self,
+            request: Request<Body>,
+        ) -> Result<Response<Body>, Self::RespondError> {
+            let body = match request.uri().to_string().as_str() {
+                crate::endpoints::v2::publish_cancel::URL => {
+                    include_str!("../../tests/response_body_files/v2/publish_cancel.json")
+                }
+                crate::endpoints::v2::video_upload_init::URL => {
+                    include_str!("../../tests/response_body_files/v2/video_upload_init.json")
+                }
+                x => panic!("{x}"),
+            };
+
+            Ok(Response::new(body.as_bytes().to_vec()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_cancelled() {
+        let cancellation_token = CancellationToken::new();
+        cancellation_token.cancel();
+
+        let init_data = VideoUploadInitResponseBodyData {
+            publish_id: "v_inbox_file~v2.123456789".into(),
+            upload_url: Some(
+                "https://open-upload.tiktokapis.com/video/?upload_id=67890"
+                    .parse()
+                    .unwrap(),
+            ),
+            upload_url_expires_at: chrono::Utc::now() + chrono::Duration::hours(1),
+        };
+        let options = UploadOptions::new()
+            .with_cancellation_token(cancellation_token)
+            .with_cancel_publish_on_cancellation(true);
+
+        match upload(
+            &MockClient,
+            &MockTransport::upload_server(),
+            "TOKEN",
+            &init_data,
+            &UploadSource::from(vec![0_u8; 16]),
+            "video/mp4",
+            &options,
+        )
+        .await
+        {
+            Err(PublishUploadError::Cancelled(UploadCancelled {
+                publish_id,
+                report,
+                publish_cancel_ret: Some(Ok(EndpointRet::Ok(_))),
+            })) => {
+                assert_eq!(publish_id, "v_inbox_file~v2.123456789");
+                assert_eq!(report.total_bytes, 16);
+                assert!(report.completed_ranges().is_empty());
+            }
+            x => panic!("{x:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_url_expired() {
+        let init_data = VideoUploadInitResponseBodyData {
+            publish_id: "v_inbox_file~v2.111111111".into(),
+            upload_url: Some(
+                "https://open-upload.tiktokapis.com/video/?upload_id=11111"
+                    .parse()
+                    .unwrap(),
+            ),
+            upload_url_expires_at: chrono::Utc::now() - chrono::Duration::minutes(1),
+        };
+        let transport = || {
+            MockTransport::new(|request| TransportResponse {
+                status: if request.url.as_str().contains("upload_id=11111") {
+                    StatusCode::FORBIDDEN
+                } else {
+                    StatusCode::CREATED
+                },
+                body: vec![],
+            })
+        };
+        let source = UploadSource::from(vec![0_u8; 16]);
+
+        match upload(
+            &MockClient,
+            &transport(),
+            "TOKEN",
+            &init_data,
+            &source,
+            "video/mp4",
+            &UploadOptions::new(),
+        )
+        .await
+        {
+            Err(PublishUploadError::UploadUrlExpired {
+                expired_publish_ids,
+                report,
+            }) => {
+                assert_eq!(expired_publish_ids, vec!["v_inbox_file~v2.111111111"]);
+                assert!(!report.is_complete());
+            }
+            x => panic!("{x:?}"),
+        }
+
+        //
+        let transport = transport();
+        match upload(
+            &MockClient,
+            &transport,
+            "TOKEN",
+            &init_data,
+            &source,
+            "video/mp4",
+            &UploadOptions::new().with_max_reinitializations(1),
+        )
+        .await
+        {
+            Ok(PublishUpload {
+                publish_id,
+                report,
+                expired_publish_ids,
+            }) => {
+                assert_eq!(publish_id, "v_inbox_file~v2.123456789");
+                assert!(report.is_complete());
+                assert_eq!(expired_publish_ids, vec!["v_inbox_file~v2.111111111"]);
+            }
+            x => panic!("{x:?}"),
+        }
+        assert_eq!(transport.requests().len(), 2);
+
+        // A 403 long before the expiry is not treated as one.
+        let init_data = VideoUploadInitResponseBodyData {
+            upload_url_expires_at: chrono::Utc::now() + chrono::Duration::hours(1),
+            ..init_data
+        };
+        match upload(
+            &MockClient,
+            &transport,
+            "TOKEN",
+            &init_data,
+            &source,
+            "video/mp4",
+            &UploadOptions::new().with_max_reinitializations(1),
+        )
+        .await
+        {
+            Err(PublishUploadError::Incomplete { publish_id, .. }) => {
+                assert_eq!(publish_id, "v_inbox_file~v2.111111111");
+            }
+            x => panic!("{x:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_url_expired_resumed() {
+        use crate::media_transfer::CHUNK_SIZE_MIN;
+
+        let init_data = VideoUploadInitResponseBodyData {
+            publish_id: "v_inbox_file~v2.111111111".into(),
+            upload_url: Some(
+                "https://open-upload.tiktokapis.com/video/?upload_id=11111"
+                    .parse()
+                    .unwrap(),
+            ),
+            upload_url_expires_at: chrono::Utc::now() - chrono::Duration::minutes(1),
+        };
+        let transport = MockTransport::new(|request| {
+            if request.url.as_str().contains("upload_id=11111") {
+                TransportResponse {
+                    status: StatusCode::FORBIDDEN,
+                    body: vec![],
+                }
+            } else {
+                MockTransport::upload_server_respond(request)
+            }
+        });
+        let checkpoints = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let options = UploadOptions::new()
+            .with_chunk_size(CHUNK_SIZE_MIN)
+            .with_start_chunk(1)
+            .with_max_reinitializations(1)
+            .with_on_chunk({
+                let checkpoints = checkpoints.to_owned();
+                move |chunk_index, _| checkpoints.lock().unwrap().push(chunk_index)
+            });
+
+        match upload(
+            &MockClient,
+            &transport,
+            "TOKEN",
+            &init_data,
+            &UploadSource::from(vec![0_u8; CHUNK_SIZE_MIN * 2]),
+            "video/mp4",
+            &options,
+        )
+        .await
+        {
+            Ok(PublishUpload {
+                publish_id, report, ..
+            }) => {
+                assert_eq!(publish_id, "v_inbox_file~v2.123456789");
+                assert!(report.is_complete());
+                assert_eq!(
+                    report.completed_ranges(),
+                    vec![0..CHUNK_SIZE_MIN, CHUNK_SIZE_MIN..CHUNK_SIZE_MIN * 2]
+                );
+                assert!(report.sha256.is_some());
+            }
+            x => panic!("{x:?}"),
+        }
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(
+            requests[0].headers["Content-Range"],
+            format!(
+                "bytes {}-{}/{}",
+                CHUNK_SIZE_MIN,
+                CHUNK_SIZE_MIN * 2 - 1,
+                CHUNK_SIZE_MIN * 2
+            )
+        );
+        assert!(requests[1].url.as_str().contains("upload_id=67890"));
+        assert_eq!(
+            requests[1].headers["Content-Range"],
+            format!("bytes 0-{}/{}", CHUNK_SIZE_MIN - 1, CHUNK_SIZE_MIN * 2)
+        );
+        assert_eq!(*checkpoints.lock().unwrap(), vec![0, 1]);
+    }
+}
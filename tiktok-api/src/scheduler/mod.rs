@@ -0,0 +1,630 @@
+use core::{future::Future as _, task::Poll};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use chrono::{DateTime, Duration, Utc};
+use futures_util::future::join_all;
+use http_api_client::Client;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    endpoints::v2::{
+        post_video_init::{PostInfo, PostVideoInitResponseBody},
+        EndpointRet, PostVideoInitEndpoint,
+    },
+    media_transfer::{
+        rate_limit::{Clock, TokioClock},
+        UploadError, UploadOptions, UploadSource,
+    },
+    media_type::SniffError,
+    publish::{upload, PublishUpload, PublishUploadError},
+    transport::Transport,
+};
+
+//
+pub const PREPARE_AHEAD_SECS_DEFAULT: i64 = 10 * 60;
+pub const GRACE_SECS_DEFAULT: i64 = 60;
+/// `run` wakes at least this often, so posts scheduled while it sleeps are picked up.
+pub const POLL_INTERVAL_SECS: i64 = 60;
+
+pub type ScheduledPostId = u64;
+
+//
+//
+//
+#[derive(Debug, Clone)]
+pub struct ScheduledPost {
+    pub access_token: String,
+    pub post_info: PostInfo,
+    pub source: UploadSource,
+    pub content_type: String,
+    pub publish_at: DateTime<Utc>,
+    pub missed_window_policy: MissedWindowPolicy,
+}
+
+/// What to do when the post is fired later than `publish_at` plus the grace period,
+/// e.g. after the process was down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissedWindowPolicy {
+    /// Drop the post.
+    #[default]
+    Skip,
+    /// Post it anyway, as soon as possible.
+    PostLate,
+    /// Hand the post back as `ScheduleOutcome::Missed`, to be rescheduled or dropped.
+    Notify,
+}
+
+#[derive(Debug)]
+pub enum ScheduleOutcome {
+    Published(PublishUpload),
+    Skipped {
+        late_by: Duration,
+    },
+    Missed {
+        late_by: Duration,
+        post: Box<ScheduledPost>,
+    },
+    Failed(ScheduleError),
+}
+
+#[derive(Debug)]
+struct Entry {
+    post: ScheduledPost,
+    /// Set once prepared.
+    video_size: Option<usize>,
+    state: EntryState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryState {
+    Waiting,
+    Preparing,
+    /// The direct post init was or is being sent, the post can no longer be cancelled.
+    Firing,
+}
+
+//
+//
+//
+/// Holds posts until their `publish_at`, then runs the direct post init and the upload.
+///
+/// A direct post is published as soon as its upload is processed, so nothing is sent to
+/// TikTok ahead of time. The media is sniffed and sized `prepare_ahead` before, so a bad
+/// source fails early instead of at the scheduled instant.
+#[derive(Debug)]
+pub struct Scheduler<K = TokioClock> {
+    clock: K,
+    prepare_ahead: Duration,
+    grace: Duration,
+    entries: Mutex<BTreeMap<ScheduledPostId, Entry>>,
+    seq: AtomicU64,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::with_clock(TokioClock)
+    }
+}
+
+impl<K> Scheduler<K>
+where
+    K: Clock,
+{
+    /// `publish_at` is compared with `Clock::utc_now`.
+    pub fn with_clock(clock: K) -> Self {
+        Self {
+            clock,
+            prepare_ahead: Duration::seconds(PREPARE_AHEAD_SECS_DEFAULT),
+            grace: Duration::seconds(GRACE_SECS_DEFAULT),
+            entries: Default::default(),
+            seq: AtomicU64::new(1),
+        }
+    }
+
+    pub fn with_prepare_ahead(mut self, prepare_ahead: Duration) -> Self {
+        self.prepare_ahead = prepare_ahead;
+        self
+    }
+
+    /// How late a post may still be fired without applying its `MissedWindowPolicy`.
+    pub fn with_grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    pub fn schedule(&self, post: ScheduledPost) -> ScheduledPostId {
+        let id = self.seq.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.lock().expect("Scheduler poisoned");
+        entries.insert(
+            id,
+            Entry {
+                post,
+                video_size: None,
+                state: EntryState::Waiting,
+            },
+        );
+        id
+    }
+
+    /// Returns None when there is no such post, or when it is already being fired.
+    /// A post cancelled while it is being prepared is not fired.
+    pub fn cancel(&self, id: ScheduledPostId) -> Option<ScheduledPost> {
+        let mut entries = self.entries.lock().expect("Scheduler poisoned");
+        match entries.get(&id) {
+            Some(x) if x.state == EntryState::Firing => None,
+            Some(_) => entries.remove(&id).map(|x| x.post),
+            None => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().expect("Scheduler poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// When the next post is due to be prepared or fired.
+    pub fn next_wakeup(&self) -> Option<DateTime<Utc>> {
+        let entries = self.entries.lock().expect("Scheduler poisoned");
+        entries
+            .values()
+            .filter(|x| x.state == EntryState::Waiting)
+            .map(|x| match x.video_size {
+                Some(_) => x.post.publish_at,
+                None => x.post.publish_at - self.prepare_ahead,
+            })
+            .min()
+    }
+
+    /// Prepares and fires every post that is due, returns the posts that are done with.
+    /// Due posts run concurrently, so a long upload does not delay the next post.
+    pub async fn tick<C, T>(
+        &self,
+        client: &C,
+        upload_client: &T,
+        options: &UploadOptions,
+    ) -> Vec<(ScheduledPostId, ScheduleOutcome)>
+    where
+        C: Client + Send + Sync,
+        T: Transport + ?Sized,
+    {
+        let now = self.clock.utc_now();
+        let due = {
+            let mut entries = self.entries.lock().expect("Scheduler poisoned");
+            entries
+                .iter_mut()
+                .filter(|(_, x)| {
+                    x.state == EntryState::Waiting && x.post.publish_at - self.prepare_ahead <= now
+                })
+                .map(|(id, x)| {
+                    x.state = EntryState::Preparing;
+                    (*id, x.post.to_owned(), x.video_size.to_owned())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        join_all(due.into_iter().map(|(id, post, video_size)| async move {
+            self.tick_entry(client, upload_client, options, id, post, video_size)
+                .await
+                .map(|outcome| (id, outcome))
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    async fn tick_entry<C, T>(
+        &self,
+        client: &C,
+        upload_client: &T,
+        options: &UploadOptions,
+        id: ScheduledPostId,
+        post: ScheduledPost,
+        video_size: Option<usize>,
+    ) -> Option<ScheduleOutcome>
+    where
+        C: Client + Send + Sync,
+        T: Transport + ?Sized,
+    {
+        let video_size = match video_size {
+            Some(x) => Ok(x),
+            None => prepare(&post).await,
+        };
+
+        // Kept in the map while preparing, so a `cancel` in the meantime removes it.
+        let video_size = {
+            let mut entries = self.entries.lock().expect("Scheduler poisoned");
+            let entry = entries.get_mut(&id)?;
+            let video_size = match video_size {
+                Ok(x) => x,
+                Err(err) => {
+                    entries.remove(&id);
+                    return Some(ScheduleOutcome::Failed(err));
+                }
+            };
+
+            let now = self.clock.utc_now();
+            if now < post.publish_at {
+                entry.video_size = Some(video_size);
+                entry.state = EntryState::Waiting;
+                return None;
+            }
+
+            let late_by = now - post.publish_at;
+            if late_by > self.grace {
+                match post.missed_window_policy {
+                    MissedWindowPolicy::Skip => {
+                        entries.remove(&id);
+                        return Some(ScheduleOutcome::Skipped { late_by });
+                    }
+                    MissedWindowPolicy::Notify => {
+                        entries.remove(&id);
+                        return Some(ScheduleOutcome::Missed {
+                            late_by,
+                            post: post.into(),
+                        });
+                    }
+                    MissedWindowPolicy::PostLate => {}
+                }
+            }
+
+            entry.state = EntryState::Firing;
+            video_size
+        };
+
+        let outcome = match fire(client, upload_client, &post, video_size, options).await {
+            Ok(x) => ScheduleOutcome::Published(x),
+            Err(err) => ScheduleOutcome::Failed(err),
+        };
+        self.entries.lock().expect("Scheduler poisoned").remove(&id);
+        Some(outcome)
+    }
+
+    /// Ticks until `shutdown` is cancelled, waking at least every `POLL_INTERVAL_SECS` for
+    /// posts scheduled in the meantime, also while none is left. Posts being fired when
+    /// `shutdown` is cancelled are finished, `options.cancellation_token` aborts them.
+    pub async fn run<C, T>(
+        &self,
+        client: &C,
+        upload_client: &T,
+        options: &UploadOptions,
+        shutdown: &CancellationToken,
+        mut on_outcome: impl FnMut(ScheduledPostId, ScheduleOutcome),
+    ) where
+        C: Client + Send + Sync,
+        T: Transport + ?Sized,
+    {
+        loop {
+            for (id, outcome) in self.tick(client, upload_client, options).await {
+                on_outcome(id, outcome);
+            }
+
+            if shutdown.is_cancelled() {
+                return;
+            }
+
+            let now = self.clock.utc_now();
+            let poll_at = now + Duration::seconds(POLL_INTERVAL_SECS);
+            let wakeup_at = match self.next_wakeup() {
+                Some(x) => core::cmp::min(x, poll_at),
+                None => poll_at,
+            };
+            let mut sleep = self
+                .clock
+                .sleep((wakeup_at - now).to_std().unwrap_or_default());
+            let mut cancelled = Box::pin(shutdown.cancelled());
+            core::future::poll_fn(|cx| {
+                if cancelled.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(());
+                }
+                sleep.as_mut().poll(cx)
+            })
+            .await
+        }
+    }
+}
+
+async fn prepare(post: &ScheduledPost) -> Result<usize, ScheduleError> {
+    post.source
+        .media_type()
+        .await
+        .map_err(ScheduleError::SniffFailed)?;
+    post.source
+        .video_size()
+        .await
+        .map_err(ScheduleError::SourceInfoFailed)
+}
+
+async fn fire<C, T>(
+    client: &C,
+    upload_client: &T,
+    post: &ScheduledPost,
+    video_size: usize,
+    options: &UploadOptions,
+) -> Result<PublishUpload, ScheduleError>
+where
+    C: Client + Send + Sync,
+    T: Transport + ?Sized,
+{
+    let post_video_init = PostVideoInitEndpoint::file_upload(
+        &post.access_token,
+        post.post_info.to_owned(),
+        video_size,
+        options.chunk_size,
+    );
+    let init_data = match client.respond_endpoint(&post_video_init).await {
+        Ok(EndpointRet::Ok(ok_json)) => ok_json.data,
+        Ok(ret) => return Err(ScheduleError::InitRejected(ret)),
+        Err(err) => return Err(ScheduleError::InitFailed(Box::new(err))),
+    };
+
+    // A re-init would go through the inbox, not the direct post.
+    let options = options.to_owned().with_max_reinitializations(0);
+    upload(
+        client,
+        upload_client,
+        &post.access_token,
+        &init_data,
+        &post.source,
+        &post.content_type,
+        &options,
+    )
+    .await
+    .map_err(ScheduleError::UploadFailed)
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub enum ScheduleError {
+    SniffFailed(SniffError),
+    SourceInfoFailed(UploadError),
+    InitFailed(Box<dyn std::error::Error + Send + Sync>),
+    InitRejected(EndpointRet<PostVideoInitResponseBody>),
+    UploadFailed(PublishUploadError),
+}
+impl core::fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for ScheduleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        sync::{atomic::AtomicUsize, Arc},
+        time::Instant,
+    };
+
+    use http_api_client::{async_trait, Body, Request, Response};
+
+    use crate::{
+        endpoints::v2::post_video_init::PrivacyLevel, media_transfer::rate_limit::Sleep,
+        transport::mock::MockTransport,
+    };
+
+    #[derive(Clone)]
+    struct ManualClock {
+        start: (Instant, DateTime<Utc>),
+        now: Arc<Mutex<DateTime<Utc>>>,
+    }
+    impl ManualClock {
+        fn new(start: DateTime<Utc>) -> Self {
+            Self {
+                start: (Instant::now(), start),
+                now: Arc::new(Mutex::new(start)),
+            }
+        }
+    }
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            self.start.0 + (self.utc_now() - self.start.1).to_std().unwrap()
+        }
+
+        fn sleep(&self, duration: core::time::Duration) -> Sleep {
+            *self.now.lock().unwrap() += Duration::from_std(duration).unwrap();
+            Box::pin(core::future::ready(()))
+        }
+
+        fn utc_now(&self) -> DateTime<Utc> {
+            *self.now.lock().unwrap()
+        }
+    }
+
+    #[derive(Default)]
+    struct MockClient {
+        inits: AtomicUsize,
+        on_init: Option<Box<dyn Fn() + Send + Sync>>,
+        /// Holds every init until this many were sent.
+        wait_for_inits: usize,
+    }
+
+    #[async_trait]
+    impl Client for MockClient {
+        type RespondError = std::io::Error;
+
+        async fn respond(
+            &self,
+            request: Request<Body>,
+        ) -> Result<Response<Body>, Self::RespondError> {
+            assert_eq!(request.uri(), crate::endpoints::v2::post_video_init::URL);
+            self.inits.fetch_add(1, Ordering::SeqCst);
+            if let Some(on_init) = self.on_init.as_ref() {
+                on_init();
+            }
+            while self.inits.load(Ordering::SeqCst) < self.wait_for_inits {
+                tokio::task::yield_now().await;
+            }
+            Ok(Response::new(
+                include_str!("../../tests/response_body_files/v2/video_upload_init.json")
+                    .as_bytes()
+                    .to_vec(),
+            ))
+        }
+    }
+
+    fn post(publish_at: DateTime<Utc>, missed_window_policy: MissedWindowPolicy) -> ScheduledPost {
+        let mut bytes = vec![0_u8; 16];
+        bytes[..12].copy_from_slice(b"\x00\x00\x00\x0cftypisom");
+        ScheduledPost {
+            access_token: "TOKEN".into(),
+            post_info: PostInfo::new(PrivacyLevel::SelfOnly),
+            source: bytes.into(),
+            content_type: "video/mp4".into(),
+            publish_at,
+            missed_window_policy,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run() {
+        let start = Utc::now();
+        let clock = ManualClock::new(start);
+        let scheduler = Scheduler::with_clock(clock.to_owned());
+        let client = MockClient::default();
+        let transport = MockTransport::upload_server();
+
+        let on_time = scheduler.schedule(post(start + Duration::hours(2), Default::default()));
+        let skipped =
+            scheduler.schedule(post(start - Duration::hours(1), MissedWindowPolicy::Skip));
+        let missed =
+            scheduler.schedule(post(start - Duration::hours(1), MissedWindowPolicy::Notify));
+        let late = scheduler.schedule(post(
+            start - Duration::hours(1),
+            MissedWindowPolicy::PostLate,
+        ));
+        let bad_media = scheduler.schedule(ScheduledPost {
+            source: vec![0_u8; 16].into(),
+            ..post(start + Duration::hours(2), Default::default())
+        });
+        let cancelled = scheduler.schedule(post(start + Duration::hours(3), Default::default()));
+        assert!(scheduler.cancel(cancelled).is_some());
+
+        let shutdown = CancellationToken::new();
+        let mut outcomes = vec![];
+        scheduler
+            .run(
+                &client,
+                &transport,
+                &UploadOptions::new(),
+                &shutdown,
+                |id, outcome| {
+                    outcomes.push((id, clock.utc_now(), outcome));
+                    if outcomes.len() == 5 {
+                        shutdown.cancel();
+                    }
+                },
+            )
+            .await;
+        assert!(scheduler.is_empty());
+        assert_eq!(client.inits.load(Ordering::SeqCst), 2);
+
+        assert_eq!(outcomes.len(), 5);
+        for (id, at, outcome) in outcomes {
+            match outcome {
+                ScheduleOutcome::Published(_) if id == late => assert_eq!(at, start),
+                ScheduleOutcome::Skipped { late_by } if id == skipped => {
+                    assert_eq!(late_by, Duration::hours(1))
+                }
+                ScheduleOutcome::Missed { post, .. } if id == missed => {
+                    assert_eq!(post.missed_window_policy, MissedWindowPolicy::Notify)
+                }
+                // Fails when prepared, well before the post is due.
+                ScheduleOutcome::Failed(ScheduleError::SniffFailed(_)) if id == bad_media => {
+                    assert_eq!(
+                        at,
+                        start + Duration::hours(2) - Duration::seconds(PREPARE_AHEAD_SECS_DEFAULT)
+                    )
+                }
+                ScheduleOutcome::Published(_) if id == on_time => {
+                    assert_eq!(at, start + Duration::hours(2))
+                }
+                x => panic!("{id} {x:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let start = Utc::now();
+        let scheduler = Arc::new(Scheduler::with_clock(ManualClock::new(start)));
+        let transport = MockTransport::upload_server();
+
+        let firing = scheduler.schedule(post(start, Default::default()));
+        let cancelled = Arc::new(Mutex::new(None));
+        let client = MockClient {
+            on_init: Some(Box::new({
+                let scheduler = scheduler.to_owned();
+                let cancelled = cancelled.to_owned();
+                move || *cancelled.lock().unwrap() = Some(scheduler.cancel(firing).is_some())
+            })),
+            ..Default::default()
+        };
+
+        let outcomes = scheduler
+            .tick(&client, &transport, &UploadOptions::new())
+            .await;
+        // The direct post init was already sent.
+        assert_eq!(*cancelled.lock().unwrap(), Some(false));
+        assert!(matches!(
+            outcomes.as_slice(),
+            [(id, ScheduleOutcome::Published(_))] if *id == firing
+        ));
+        assert!(scheduler.is_empty());
+
+        // Prepared ahead, then cancelled before it is due.
+        let waiting = scheduler.schedule(post(start + Duration::minutes(1), Default::default()));
+        assert!(scheduler
+            .tick(&client, &transport, &UploadOptions::new())
+            .await
+            .is_empty());
+        assert_eq!(scheduler.next_wakeup(), Some(start + Duration::minutes(1)));
+        assert!(scheduler.cancel(waiting).is_some());
+        assert!(scheduler.cancel(waiting).is_none());
+        assert_eq!(client.inits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tick_concurrent() {
+        let start = Utc::now();
+        let scheduler = Scheduler::with_clock(ManualClock::new(start));
+        let transport = MockTransport::upload_server();
+        // Each init waits for the other one, so firing one post after the other never ends.
+        let client = MockClient {
+            wait_for_inits: 2,
+            ..Default::default()
+        };
+
+        scheduler.schedule(post(start, Default::default()));
+        scheduler.schedule(post(start, Default::default()));
+        let outcomes = tokio::time::timeout(
+            core::time::Duration::from_secs(5),
+            scheduler.tick(&client, &transport, &UploadOptions::new()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes
+            .iter()
+            .all(|(_, x)| matches!(x, ScheduleOutcome::Published(_))));
+    }
+}
@@ -0,0 +1,63 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use http_api_client::async_trait;
+
+use crate::objects::oauth::AccessToken;
+
+//
+pub type StorageError = Box<dyn std::error::Error + Send + Sync>;
+
+//
+//
+//
+/// Where the app keeps `AccessToken`s, by `open_id`.
+#[async_trait]
+pub trait TokenStore: Send + Sync + 'static {
+    /// Should return a token that is still valid, refreshing it first when it expired,
+    /// so work that waited, e.g. a queued upload, does not fail on a stale token.
+    async fn get(&self, open_id: &str) -> Result<Option<AccessToken>, StorageError>;
+
+    /// Returns the deleted token, None when there was none.
+    async fn delete(&self, open_id: &str) -> Result<Option<AccessToken>, StorageError>;
+}
+
+/// Tokens are lost with the process, for tests and single-process setups.
+/// Tokens are returned as inserted, never refreshed.
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    tokens: Mutex<HashMap<String, AccessToken>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the token of the same `open_id`.
+    pub fn insert(&self, token: AccessToken) -> Option<AccessToken> {
+        self.tokens
+            .lock()
+            .expect("MemoryTokenStore poisoned")
+            .insert(token.open_id.to_owned(), token)
+    }
+}
+
+#[async_trait]
+impl TokenStore for MemoryTokenStore {
+    async fn get(&self, open_id: &str) -> Result<Option<AccessToken>, StorageError> {
+        Ok(self
+            .tokens
+            .lock()
+            .expect("MemoryTokenStore poisoned")
+            .get(open_id)
+            .cloned())
+    }
+
+    async fn delete(&self, open_id: &str) -> Result<Option<AccessToken>, StorageError> {
+        Ok(self
+            .tokens
+            .lock()
+            .expect("MemoryTokenStore poisoned")
+            .remove(open_id))
+    }
+}
@@ -1,7 +1,4 @@
-use std::{
-    io::{Error as IoError, ErrorKind as IoErrorKind},
-    path::PathBuf,
-};
+use std::{io::Error as IoError, path::PathBuf};
 
 use tokio::fs::metadata;
 
@@ -16,7 +13,7 @@ pub async fn info(path: &PathBuf) -> Result<Info, IoError> {
     let file_metadata = metadata(&path).await?;
 
     if !file_metadata.is_file() {
-        return Err(IoError::new(IoErrorKind::Other, "is_file required"));
+        return Err(IoError::other("is_file required"));
     }
 
     let file_size = file_metadata.len();
@@ -0,0 +1,41 @@
+use http_api_client::async_trait;
+use hyper::{client::connect::Connect, Body, Client, Request};
+
+use super::{Transport, TransportBody, TransportError, TransportRequest, TransportResponse};
+
+//
+#[async_trait]
+impl<C> Transport for Client<C, Body>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    async fn send(&self, request: TransportRequest) -> Result<TransportResponse, TransportError> {
+        let TransportRequest {
+            method,
+            url,
+            headers,
+            body,
+        } = request;
+
+        let body = match body {
+            TransportBody::Bytes(bytes) => Body::from(bytes),
+            #[cfg(feature = "with_tokio")]
+            TransportBody::Reader { reader, length: _ } => {
+                Body::wrap_stream(tokio_util::io::ReaderStream::new(reader))
+            }
+        };
+
+        let mut request = Request::builder()
+            .method(method)
+            .uri(url.as_str())
+            .body(body)?;
+        *request.headers_mut() = headers;
+
+        let response = self.request(request).await?;
+
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await?.to_vec();
+
+        Ok(TransportResponse { status, body })
+    }
+}
@@ -0,0 +1,45 @@
+use http_api_client::async_trait;
+use http_api_client_endpoint::http::Request;
+use isahc::{AsyncBody, AsyncReadResponseExt as _, HttpClient};
+
+use super::{Transport, TransportBody, TransportError, TransportRequest, TransportResponse};
+
+//
+#[async_trait]
+impl Transport for HttpClient {
+    async fn send(&self, request: TransportRequest) -> Result<TransportResponse, TransportError> {
+        let TransportRequest {
+            method,
+            url,
+            headers,
+            body,
+        } = request;
+
+        let body = match body {
+            TransportBody::Bytes(bytes) => AsyncBody::from(bytes.to_vec()),
+            #[cfg(feature = "with_tokio")]
+            TransportBody::Reader { reader, length } => {
+                use tokio_util::compat::TokioAsyncReadCompatExt as _;
+
+                match length {
+                    Some(length) => AsyncBody::from_reader_sized(reader.compat(), length),
+                    None => AsyncBody::from_reader(reader.compat()),
+                }
+            }
+        };
+
+        let mut request = Request::builder()
+            .method(method)
+            .uri(url.as_str())
+            .body(body)?;
+        *request.headers_mut() = headers;
+
+        let mut response = self.send_async(request).await?;
+
+        let status = response.status();
+        let mut body = vec![];
+        response.copy_to(&mut body).await?;
+
+        Ok(TransportResponse { status, body })
+    }
+}
@@ -0,0 +1,37 @@
+use http_api_client::async_trait;
+use reqwest::{Body, Client};
+
+use super::{Transport, TransportBody, TransportError, TransportRequest, TransportResponse};
+
+//
+#[async_trait]
+impl Transport for Client {
+    async fn send(&self, request: TransportRequest) -> Result<TransportResponse, TransportError> {
+        let TransportRequest {
+            method,
+            url,
+            headers,
+            body,
+        } = request;
+
+        let body = match body {
+            TransportBody::Bytes(bytes) => Body::from(bytes),
+            #[cfg(feature = "with_tokio")]
+            TransportBody::Reader { reader, length: _ } => {
+                Body::wrap_stream(tokio_util::io::ReaderStream::new(reader))
+            }
+        };
+
+        let response = self
+            .request(method, url)
+            .headers(headers)
+            .body(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body = response.bytes().await?.to_vec();
+
+        Ok(TransportResponse { status, body })
+    }
+}
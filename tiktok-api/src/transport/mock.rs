@@ -0,0 +1,88 @@
+use std::sync::Mutex;
+
+use http_api_client::async_trait;
+use http_api_client_endpoint::http::{HeaderMap, Method, StatusCode};
+use url::Url;
+
+use super::{Transport, TransportBody, TransportError, TransportRequest, TransportResponse};
+
+//
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub url: Url,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+#[allow(clippy::type_complexity)]
+pub struct MockTransport {
+    pub requests: Mutex<Vec<RecordedRequest>>,
+    respond: Box<dyn Fn(&RecordedRequest) -> TransportResponse + Send + Sync>,
+}
+
+impl MockTransport {
+    pub fn new(
+        respond: impl Fn(&RecordedRequest) -> TransportResponse + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            requests: Default::default(),
+            respond: Box::new(respond),
+        }
+    }
+
+    /// Answers chunk uploads like TikTok does, `201` for the last chunk and `206` otherwise.
+    pub fn upload_server() -> Self {
+        Self::new(Self::upload_server_respond)
+    }
+
+    pub fn upload_server_respond(request: &RecordedRequest) -> TransportResponse {
+        let content_range = request.headers["Content-Range"].to_str().unwrap();
+        let (range, total) = content_range
+            .trim_start_matches("bytes ")
+            .split_once('/')
+            .unwrap();
+        let end = range.split_once('-').unwrap().1;
+        let status = if end.parse::<usize>().unwrap() + 1 == total.parse::<usize>().unwrap() {
+            StatusCode::CREATED
+        } else {
+            StatusCode::PARTIAL_CONTENT
+        };
+        TransportResponse {
+            status,
+            body: vec![],
+        }
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().to_owned()
+    }
+}
+
+#[async_trait]
+impl Transport for MockTransport {
+    async fn send(&self, request: TransportRequest) -> Result<TransportResponse, TransportError> {
+        let body = match request.body {
+            TransportBody::Bytes(bytes) => bytes.to_vec(),
+            #[cfg(feature = "with_tokio")]
+            TransportBody::Reader { mut reader, .. } => {
+                use tokio::io::AsyncReadExt as _;
+
+                let mut buf = vec![];
+                reader.read_to_end(&mut buf).await?;
+                buf
+            }
+        };
+
+        let request = RecordedRequest {
+            method: request.method,
+            url: request.url,
+            headers: request.headers,
+            body,
+        };
+        let response = (self.respond)(&request);
+        self.requests.lock().unwrap().push(request);
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,101 @@
+use bytes::Bytes;
+use http_api_client::async_trait;
+use http_api_client_endpoint::http::{HeaderMap, Method, StatusCode};
+use url::Url;
+
+//
+pub mod multipart;
+
+#[cfg(test)]
+pub(crate) mod mock;
+
+#[cfg(feature = "with_transport_hyper")]
+pub mod impl_hyper;
+#[cfg(feature = "with_transport_isahc")]
+pub mod impl_isahc;
+#[cfg(feature = "with_transport_reqwest")]
+pub mod impl_reqwest;
+
+//
+pub type TransportError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Sends a request with a possibly streaming body and reads the whole response body.
+/// Implemented for `reqwest::Client`, `isahc::HttpClient` and `hyper::Client` behind features.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn send(&self, request: TransportRequest) -> Result<TransportResponse, TransportError>;
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub struct TransportRequest {
+    pub method: Method,
+    pub url: Url,
+    pub headers: HeaderMap,
+    pub body: TransportBody,
+}
+
+#[derive(Debug, Clone)]
+pub struct TransportResponse {
+    pub status: StatusCode,
+    pub body: Vec<u8>,
+}
+
+//
+//
+//
+pub enum TransportBody {
+    Bytes(Bytes),
+    #[cfg(feature = "with_tokio")]
+    Reader {
+        reader: core::pin::Pin<Box<dyn tokio::io::AsyncRead + Send + Sync>>,
+        length: Option<u64>,
+    },
+}
+impl core::fmt::Debug for TransportBody {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Bytes(bytes) => f.debug_tuple("Bytes").field(&bytes.len()).finish(),
+            #[cfg(feature = "with_tokio")]
+            Self::Reader { reader: _, length } => f
+                .debug_struct("Reader")
+                .field("length", length)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl TransportBody {
+    #[cfg(feature = "with_tokio")]
+    pub fn reader<R>(reader: R, length: Option<u64>) -> Self
+    where
+        R: tokio::io::AsyncRead + Send + Sync + 'static,
+    {
+        Self::Reader {
+            reader: Box::pin(reader),
+            length,
+        }
+    }
+
+    pub fn length(&self) -> Option<u64> {
+        match self {
+            Self::Bytes(bytes) => Some(bytes.len() as u64),
+            #[cfg(feature = "with_tokio")]
+            Self::Reader { reader: _, length } => *length,
+        }
+    }
+}
+
+impl From<Bytes> for TransportBody {
+    fn from(bytes: Bytes) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+impl From<Vec<u8>> for TransportBody {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes.into())
+    }
+}
@@ -0,0 +1,140 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bytes::Bytes;
+
+use super::TransportBody;
+
+//
+static BOUNDARY_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+//
+/// A `multipart/form-data` body with a single file field, streamed without buffering the file.
+#[derive(Debug)]
+pub struct MultipartFile {
+    pub boundary: String,
+    pub body: TransportBody,
+}
+
+impl MultipartFile {
+    pub fn new(name: &str, file_name: &str, body: TransportBody) -> Self {
+        let boundary = format!(
+            "{:016x}{:08x}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+            BOUNDARY_SEQ.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let prefix = Bytes::from(format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\r\n",
+            escape(name),
+            escape(file_name),
+        ));
+        let suffix = Bytes::from(format!("\r\n--{boundary}--\r\n"));
+
+        let body = match body {
+            TransportBody::Bytes(bytes) => {
+                let mut buf = Vec::with_capacity(prefix.len() + bytes.len() + suffix.len());
+                buf.extend_from_slice(&prefix);
+                buf.extend_from_slice(&bytes);
+                buf.extend_from_slice(&suffix);
+                TransportBody::Bytes(buf.into())
+            }
+            #[cfg(feature = "with_tokio")]
+            TransportBody::Reader { reader, length } => {
+                use tokio::io::AsyncReadExt as _;
+
+                let length = length.map(|x| (prefix.len() + suffix.len()) as u64 + x);
+                TransportBody::reader(
+                    std::io::Cursor::new(prefix)
+                        .chain(reader)
+                        .chain(std::io::Cursor::new(suffix)),
+                    length,
+                )
+            }
+        };
+
+        Self { boundary, body }
+    }
+
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+}
+
+/// As browsers do, https://html.spec.whatwg.org/multipart-form-data
+fn escape(s: &str) -> String {
+    s.replace('\n', "%0A")
+        .replace('\r', "%0D")
+        .replace('"', "%22")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let multipart = MultipartFile::new("video", "x.mp4", b"DATA".to_vec().into());
+        let boundary = multipart.boundary.to_owned();
+        assert_eq!(
+            multipart.content_type(),
+            format!("multipart/form-data; boundary={boundary}")
+        );
+        match multipart.body {
+            TransportBody::Bytes(bytes) => {
+                assert_eq!(
+                    bytes,
+                    format!("--{boundary}\r\nContent-Disposition: form-data; name=\"video\"; filename=\"x.mp4\"\r\n\r\nDATA\r\n--{boundary}--\r\n")
+                );
+            }
+            #[allow(unreachable_patterns)]
+            x => panic!("{x:?}"),
+        }
+
+        let multipart = MultipartFile::new(
+            "video",
+            "a\".mp4\"\r\nX-Injected: 1\n.mp4",
+            b"DATA".to_vec().into(),
+        );
+        match multipart.body {
+            TransportBody::Bytes(bytes) => {
+                let head = std::str::from_utf8(&bytes)
+                    .unwrap()
+                    .split("\r\n")
+                    .nth(1)
+                    .unwrap()
+                    .to_owned();
+                assert_eq!(
+                    head,
+                    "Content-Disposition: form-data; name=\"video\"; filename=\"a%22.mp4%22%0D%0AX-Injected: 1%0A.mp4\""
+                );
+            }
+            #[allow(unreachable_patterns)]
+            x => panic!("{x:?}"),
+        }
+    }
+
+    #[cfg(feature = "with_tokio")]
+    #[tokio::test]
+    async fn test_new_with_reader() -> Result<(), Box<dyn std::error::Error>> {
+        use tokio::io::AsyncReadExt as _;
+
+        let multipart = MultipartFile::new(
+            "video",
+            "x.mp4",
+            TransportBody::reader(&b"DATA"[..], Some(4)),
+        );
+        let boundary = multipart.boundary.to_owned();
+        let expected = format!("--{boundary}\r\nContent-Disposition: form-data; name=\"video\"; filename=\"x.mp4\"\r\n\r\nDATA\r\n--{boundary}--\r\n");
+        match multipart.body {
+            TransportBody::Reader { mut reader, length } => {
+                assert_eq!(length, Some(expected.len() as u64));
+                let mut buf = String::new();
+                reader.read_to_string(&mut buf).await?;
+                assert_eq!(buf, expected);
+            }
+            x => panic!("{x:?}"),
+        }
+
+        Ok(())
+    }
+}
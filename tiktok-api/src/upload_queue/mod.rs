@@ -0,0 +1,906 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use http_api_client::Client;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use url::Url;
+
+use crate::{
+    classify::Classification,
+    endpoints::v2::{EndpointRet, VideoUploadInitEndpoint},
+    media_transfer::{FileFingerprint, UploadError, UploadOptions, UploadSource, UploadStatus},
+    publish::upload::UPLOAD_URL_EXPIRY_MARGIN_SECS,
+    token_store::TokenStore,
+    transport::Transport,
+};
+
+pub mod storage;
+pub use storage::{JobStorage, MemoryStorage, StorageError};
+
+#[cfg(feature = "with_upload_queue_sqlite")]
+pub mod sqlite;
+#[cfg(feature = "with_upload_queue_sqlite")]
+pub use sqlite::SqliteStorage;
+
+//
+pub const LEASE_SECS_DEFAULT: i64 = 5 * 60;
+pub const MAX_ATTEMPTS_DEFAULT: usize = 3;
+
+pub type JobId = u64;
+
+//
+//
+//
+/// No access token is stored, it is resolved by `open_id` through the queue's `TokenStore`
+/// when the job runs, so a job that waited past the token expiry gets a refreshed one.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct NewUploadJob {
+    pub open_id: String,
+    pub file_path: PathBuf,
+    pub content_type: String,
+    pub chunk_size: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct UploadJob {
+    pub id: JobId,
+    pub open_id: String,
+    pub file_path: PathBuf,
+    pub content_type: String,
+    pub chunk_size: Option<usize>,
+    pub state: JobState,
+    /// Failed runs so far, the job fails for good at `UploadQueue::max_attempts`.
+    pub attempts: usize,
+    pub last_error: Option<String>,
+    pub lease: Option<Lease>,
+    /// Bumped on every write, see `JobStorage::update`.
+    pub version: u64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UploadJob {
+    pub fn new(id: JobId, job: NewUploadJob, now: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            open_id: job.open_id,
+            file_path: job.file_path,
+            content_type: job.content_type,
+            chunk_size: job.chunk_size,
+            state: JobState::Queued,
+            attempts: 0,
+            last_error: None,
+            lease: None,
+            version: 0,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Lease {
+    pub worker_id: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Initialized {
+        upload: InitializedUpload,
+    },
+    Transferring {
+        upload: InitializedUpload,
+        checkpoint: Checkpoint,
+    },
+    /// Uploaded, TikTok is processing it. Moved on by `UploadQueue::mark_completed`
+    /// or `UploadQueue::mark_failed`.
+    AwaitingProcessing {
+        publish_id: String,
+    },
+    Completed {
+        publish_id: String,
+    },
+    Failed {
+        publish_id: Option<String>,
+        error: String,
+    },
+}
+
+impl JobState {
+    /// Still has work for a worker.
+    pub fn is_runnable(&self) -> bool {
+        matches!(
+            self,
+            Self::Queued | Self::Initialized { .. } | Self::Transferring { .. }
+        )
+    }
+
+    pub fn publish_id(&self) -> Option<&str> {
+        match self {
+            Self::Queued => None,
+            Self::Initialized { upload } | Self::Transferring { upload, .. } => {
+                Some(&upload.publish_id)
+            }
+            Self::AwaitingProcessing { publish_id } | Self::Completed { publish_id } => {
+                Some(publish_id)
+            }
+            Self::Failed { publish_id, .. } => publish_id.as_deref(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct InitializedUpload {
+    pub publish_id: String,
+    pub upload_url: Url,
+    pub upload_url_expires_at: DateTime<Utc>,
+    /// A resumed transfer fails if the file changed since the init.
+    pub file_fingerprint: FileFingerprint,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The first chunk not yet accepted.
+    pub next_chunk: usize,
+    pub uploaded_bytes: usize,
+}
+
+//
+//
+//
+/// Runs `NewUploadJob`s from `/inbox/video/init/` to `AwaitingProcessing`, persisting every
+/// step, so a worker that crashes is replaced by the next `run_next` once its lease expires.
+///
+/// `JobStorage` is synchronous, its calls run on the blocking thread pool.
+pub struct UploadQueue<S> {
+    storage: Arc<S>,
+    token_store: Arc<dyn TokenStore>,
+    lease: Duration,
+    max_attempts: usize,
+}
+
+impl<S> core::fmt::Debug for UploadQueue<S>
+where
+    S: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("UploadQueue")
+            .field("storage", &self.storage)
+            .field("lease", &self.lease)
+            .field("max_attempts", &self.max_attempts)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> UploadQueue<S>
+where
+    S: JobStorage + 'static,
+{
+    pub fn new(storage: S, token_store: impl TokenStore) -> Self {
+        Self {
+            storage: Arc::new(storage),
+            token_store: Arc::new(token_store),
+            lease: Duration::seconds(LEASE_SECS_DEFAULT),
+            max_attempts: MAX_ATTEMPTS_DEFAULT,
+        }
+    }
+
+    /// Renewed with every checkpoint, so it must outlast the transfer of one chunk.
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    pub async fn enqueue(&self, job: NewUploadJob) -> Result<UploadJob, UploadQueueError> {
+        self.with_storage(move |storage| storage.insert(job, Utc::now()))
+            .await
+    }
+
+    /// Claims one job and runs it as far as it goes. Returns None when nothing is claimable.
+    /// A job that hit a retryable error is returned still runnable, with its lease released.
+    pub async fn run_next<C, T>(
+        &self,
+        client: &C,
+        upload_client: &T,
+        worker_id: &str,
+        options: &UploadOptions,
+    ) -> Result<Option<UploadJob>, UploadQueueError>
+    where
+        C: Client + Send + Sync,
+        T: Transport + ?Sized,
+    {
+        let worker_id = worker_id.to_owned();
+        let lease = self.lease;
+        let mut job = match self
+            .with_storage(move |storage| {
+                let now = Utc::now();
+                storage.claim(&worker_id, now, now + lease)
+            })
+            .await?
+        {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        while job.state.is_runnable() && job.lease.is_some() {
+            match job.state.to_owned() {
+                JobState::Queued => self.initialize(client, &mut job).await?,
+                JobState::Initialized { upload } => {
+                    self.transfer(upload_client, &mut job, upload, 0, options)
+                        .await?
+                }
+                JobState::Transferring { upload, checkpoint } => {
+                    self.transfer(
+                        upload_client,
+                        &mut job,
+                        upload,
+                        checkpoint.next_chunk,
+                        options,
+                    )
+                    .await?
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(Some(job))
+    }
+
+    pub async fn mark_completed(
+        &self,
+        publish_id: &str,
+    ) -> Result<Option<UploadJob>, UploadQueueError> {
+        self.mark_processed(
+            publish_id,
+            JobState::Completed {
+                publish_id: publish_id.into(),
+            },
+        )
+        .await
+    }
+
+    pub async fn mark_failed(
+        &self,
+        publish_id: &str,
+        error: impl AsRef<str>,
+    ) -> Result<Option<UploadJob>, UploadQueueError> {
+        self.mark_processed(
+            publish_id,
+            JobState::Failed {
+                publish_id: Some(publish_id.into()),
+                error: error.as_ref().into(),
+            },
+        )
+        .await
+    }
+
+    /// Fails every runnable job of `open_id`, e.g. after `authorization.removed`. A worker
+    /// running one of them gets `LeaseLost` at its next checkpoint.
+    pub async fn cancel_for_open_id(
+        &self,
+        open_id: &str,
+        error: impl AsRef<str>,
+    ) -> Result<Vec<JobId>, UploadQueueError> {
+        let open_id = open_id.to_owned();
+        let error = error.as_ref().to_owned();
+        self.with_storage(move |storage| {
+            let ids = storage
+                .list_by_open_id(&open_id)?
+                .into_iter()
+                .filter(|x| x.state.is_runnable())
+                .map(|x| x.id)
+                .collect::<Vec<_>>();
+
+            let mut cancelled = vec![];
+            for id in ids {
+                // Retried when a worker wrote in between.
+                while let Some(mut job) = storage.get(id)? {
+                    if !job.state.is_runnable() {
+                        break;
+                    }
+                    job.state = JobState::Failed {
+                        publish_id: job.state.publish_id().map(Into::into),
+                        error: error.to_owned(),
+                    };
+                    job.last_error = Some(error.to_owned());
+                    job.lease = None;
+                    job.updated_at = Utc::now();
+                    if storage.update(&mut job)? {
+                        cancelled.push(id);
+                        break;
+                    }
+                }
+            }
+            Ok(cancelled)
+        })
+        .await
+    }
+
+    async fn mark_processed(
+        &self,
+        publish_id: &str,
+        state: JobState,
+    ) -> Result<Option<UploadJob>, UploadQueueError> {
+        let publish_id = publish_id.to_owned();
+        let mut job = match self
+            .with_storage(move |storage| {
+                Ok(storage
+                    .get_by_publish_id(&publish_id)?
+                    .filter(|x| matches!(&x.state, JobState::AwaitingProcessing { .. })))
+            })
+            .await?
+        {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        job.state = state;
+        self.save(&mut job).await?;
+        Ok(Some(job))
+    }
+
+    //
+    async fn initialize<C>(&self, client: &C, job: &mut UploadJob) -> Result<(), UploadQueueError>
+    where
+        C: Client + Send + Sync,
+    {
+        let access_token = match self.token_store.get(&job.open_id).await {
+            Ok(Some(x)) => x.access_token,
+            // Deleted, e.g. after `authorization.removed`.
+            Ok(None) => return self.fail(job, "access_token missing".into()).await,
+            Err(err) => return self.retry_later(job, format!("{err:?}")).await,
+        };
+
+        let video_upload_init =
+            match VideoUploadInitEndpoint::with_file(&access_token, &job.file_path, job.chunk_size)
+                .await
+            {
+                Ok(x) => x,
+                Err(err) => return self.fail(job, format!("{err:?}")).await,
+            };
+
+        let data = match client.respond_endpoint(&video_upload_init).await {
+            Ok(EndpointRet::Ok(ok_json)) => ok_json.data,
+            Ok(ret) => {
+                let error = format!("{ret:?}");
+                return match ret.into_result().map(|_| ()).map_err(|err| err.classify()) {
+                    // The token store may have a refreshed token by the next run.
+                    Err(Classification::Retryable { .. } | Classification::Reauthenticate) => {
+                        self.retry_later(job, error).await
+                    }
+                    _ => self.fail(job, error).await,
+                };
+            }
+            Err(err) => return self.retry_later(job, format!("{err:?}")).await,
+        };
+
+        let upload_url = match data.upload_url {
+            Some(x) => x,
+            None => return self.fail(job, "upload_url missing".into()).await,
+        };
+        job.state = JobState::Initialized {
+            upload: InitializedUpload {
+                publish_id: data.publish_id,
+                upload_url,
+                upload_url_expires_at: data.upload_url_expires_at,
+                file_fingerprint: video_upload_init
+                    .file_fingerprint
+                    .expect("set by with_file"),
+            },
+        };
+        self.save(job).await
+    }
+
+    async fn transfer<T>(
+        &self,
+        upload_client: &T,
+        job: &mut UploadJob,
+        upload: InitializedUpload,
+        start_chunk: usize,
+        options: &UploadOptions,
+    ) -> Result<(), UploadQueueError>
+    where
+        T: Transport + ?Sized,
+    {
+        // Too late to finish, a new init is cheaper than a failed transfer.
+        if upload.upload_url_expires_at - Duration::seconds(UPLOAD_URL_EXPIRY_MARGIN_SECS)
+            <= Utc::now()
+        {
+            job.state = JobState::Queued;
+            return self.save(job).await;
+        }
+
+        let cancellation_token = options
+            .cancellation_token
+            .as_ref()
+            .map(|x| x.child_token())
+            .unwrap_or_default();
+        let shared_job = Arc::new(Mutex::new(job.to_owned()));
+        let checkpoint_ret: Arc<Mutex<Result<(), UploadQueueError>>> = Arc::new(Mutex::new(Ok(())));
+        let checkpoint_writes: Arc<Mutex<Vec<JoinHandle<()>>>> = Default::default();
+
+        let mut upload_options = options
+            .to_owned()
+            .with_start_chunk(start_chunk)
+            .with_expected_fingerprint(upload.file_fingerprint.to_owned())
+            .with_cancellation_token(cancellation_token.to_owned())
+            .with_on_chunk({
+                let storage = self.storage.to_owned();
+                let lease = self.lease;
+                let shared_job = shared_job.to_owned();
+                let checkpoint_ret = checkpoint_ret.to_owned();
+                let checkpoint_writes = checkpoint_writes.to_owned();
+                let checkpoint_write_lock: Arc<Mutex<()>> = Default::default();
+                let upload = upload.to_owned();
+                move |chunk_index, chunk| {
+                    {
+                        let mut job = shared_job.lock().expect("UploadJob poisoned");
+                        job.state = JobState::Transferring {
+                            upload: upload.to_owned(),
+                            checkpoint: Checkpoint {
+                                next_chunk: chunk_index + 1,
+                                uploaded_bytes: chunk.byte_range.end,
+                            },
+                        };
+                        renew(&mut job, lease);
+                    }
+
+                    // Writes the latest checkpoint, so a write running late only repeats it.
+                    let write = tokio::task::spawn_blocking({
+                        let storage = storage.to_owned();
+                        let shared_job = shared_job.to_owned();
+                        let checkpoint_ret = checkpoint_ret.to_owned();
+                        let cancellation_token = cancellation_token.to_owned();
+                        let checkpoint_write_lock = checkpoint_write_lock.to_owned();
+                        move || {
+                            // Serializes the writes, `shared_job` is only locked to copy the
+                            // checkpoint and to take the bumped version, so chunks never wait
+                            // on storage.
+                            let _write_guard = checkpoint_write_lock
+                                .lock()
+                                .expect("checkpoint write poisoned");
+                            let mut job = shared_job.lock().expect("UploadJob poisoned").to_owned();
+                            let ret = match storage.update(&mut job) {
+                                Ok(true) => {
+                                    shared_job.lock().expect("UploadJob poisoned").version =
+                                        job.version;
+                                    return;
+                                }
+                                Ok(false) => UploadQueueError::LeaseLost { id: job.id },
+                                Err(err) => UploadQueueError::Storage(err),
+                            };
+                            *checkpoint_ret.lock().expect("checkpoint poisoned") = Err(ret);
+                            cancellation_token.cancel();
+                        }
+                    });
+                    checkpoint_writes
+                        .lock()
+                        .expect("checkpoint writes poisoned")
+                        .push(write);
+                }
+            });
+        if let Some(chunk_size) = job.chunk_size {
+            upload_options = upload_options.with_chunk_size(chunk_size);
+        }
+
+        let ret = UploadSource::File(job.file_path.to_owned())
+            .upload(
+                upload_client,
+                upload.upload_url.to_owned(),
+                &job.content_type,
+                &upload_options,
+            )
+            .await;
+
+        let writes = core::mem::take(
+            &mut *checkpoint_writes
+                .lock()
+                .expect("checkpoint writes poisoned"),
+        );
+        for write in writes {
+            write
+                .await
+                .map_err(|err| UploadQueueError::Storage(err.into()))?;
+        }
+        *job = shared_job.lock().expect("UploadJob poisoned").to_owned();
+        core::mem::replace(
+            &mut *checkpoint_ret.lock().expect("checkpoint poisoned"),
+            Ok(()),
+        )?;
+
+        let report = match ret {
+            Ok(x) => x,
+            Err(err) => return self.retry_later(job, format!("{err:?}")).await,
+        };
+        match report.status {
+            UploadStatus::Complete => {
+                job.state = JobState::AwaitingProcessing {
+                    publish_id: upload.publish_id,
+                };
+                job.lease = None;
+                self.save(job).await
+            }
+            UploadStatus::Partial => {
+                job.lease = None;
+                self.save(job).await
+            }
+            UploadStatus::Failed {
+                error: error @ UploadError::FileModified { .. },
+                ..
+            } => self.fail(job, format!("{error:?}")).await,
+            UploadStatus::Failed { error, .. } => {
+                if error.is_upload_url_expired_response() {
+                    job.state = JobState::Queued;
+                }
+                self.retry_later(job, format!("{error:?}")).await
+            }
+        }
+    }
+
+    async fn retry_later(
+        &self,
+        job: &mut UploadJob,
+        error: String,
+    ) -> Result<(), UploadQueueError> {
+        job.attempts += 1;
+        if job.attempts >= self.max_attempts {
+            return self.fail(job, error).await;
+        }
+
+        job.last_error = Some(error);
+        job.lease = None;
+        self.save(job).await
+    }
+
+    async fn fail(&self, job: &mut UploadJob, error: String) -> Result<(), UploadQueueError> {
+        job.state = JobState::Failed {
+            publish_id: job.state.publish_id().map(Into::into),
+            error: error.to_owned(),
+        };
+        job.last_error = Some(error);
+        job.lease = None;
+        self.save(job).await
+    }
+
+    async fn save(&self, job: &mut UploadJob) -> Result<(), UploadQueueError> {
+        renew(job, self.lease);
+        let (saved, ret) = self
+            .with_storage({
+                let mut job = job.to_owned();
+                move |storage| storage.update(&mut job).map(|ret| (job, ret))
+            })
+            .await?;
+        *job = saved;
+        if ret {
+            Ok(())
+        } else {
+            Err(UploadQueueError::LeaseLost { id: job.id })
+        }
+    }
+
+    async fn with_storage<F, R>(&self, f: F) -> Result<R, UploadQueueError>
+    where
+        F: FnOnce(&S) -> Result<R, StorageError> + Send + 'static,
+        R: Send + 'static,
+    {
+        let storage = self.storage.to_owned();
+        tokio::task::spawn_blocking(move || f(&storage))
+            .await
+            .map_err(|err| UploadQueueError::Storage(err.into()))?
+            .map_err(UploadQueueError::Storage)
+    }
+}
+
+fn renew(job: &mut UploadJob, lease: Duration) {
+    let now = Utc::now();
+    if let Some(x) = job.lease.as_mut() {
+        x.expires_at = now + lease;
+    }
+    job.updated_at = now;
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub enum UploadQueueError {
+    Storage(StorageError),
+    /// Another worker took the job over, this one must stop working on it.
+    LeaseLost {
+        id: JobId,
+    },
+}
+impl core::fmt::Display for UploadQueueError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for UploadQueueError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use http_api_client::{async_trait, Body, Request, Response};
+    use http_api_client_endpoint::http::StatusCode;
+
+    use crate::{
+        media_transfer::CHUNK_SIZE_MIN,
+        objects::oauth::AccessToken,
+        token_store::MemoryTokenStore,
+        transport::{mock::MockTransport, TransportResponse},
+        upload_queue::storage::tests::temp_path,
+    };
+
+    fn token_store() -> MemoryTokenStore {
+        let token_store = MemoryTokenStore::new();
+        token_store.insert(AccessToken {
+            open_id: "u1".into(),
+            scope: "video.upload".into(),
+            access_token: "TOKEN".into(),
+            expires_in: 86400,
+            refresh_token: "REFRESH_TOKEN".into(),
+            refresh_expires_in: 31536000,
+        });
+        token_store
+    }
+
+    struct MockClient;
+
+    #[async_trait]
+    impl Client for MockClient {
+        type RespondError = std::io::Error;
+
+        async fn respond(
+            &self,
+            request: Request<Body>,
+        ) -> Result<Response<Body>, Self::RespondError> {
+            assert_eq!(request.uri(), crate::endpoints::v2::video_upload_init::URL);
+            assert_eq!(request.headers()["Authorization"], "Bearer TOKEN");
+            Ok(Response::new(
+                include_str!("../../tests/response_body_files/v2/video_upload_init.json")
+                    .as_bytes()
+                    .to_vec(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_next() {
+        let file_path = temp_path("x.mp4");
+        let mut bytes = vec![0_u8; CHUNK_SIZE_MIN * 3];
+        bytes[..12].copy_from_slice(b"\x00\x00\x00\x0cftypisom");
+        std::fs::write(&file_path, &bytes).unwrap();
+
+        let queue = UploadQueue::new(MemoryStorage::new(), token_store());
+        let job = queue
+            .enqueue(NewUploadJob {
+                open_id: "u1".into(),
+                file_path: file_path.to_owned(),
+                content_type: "video/mp4".into(),
+                chunk_size: Some(CHUNK_SIZE_MIN),
+            })
+            .await
+            .unwrap();
+
+        // The second chunk fails, the first one is checkpointed.
+        let transport = MockTransport::new(|request| {
+            let content_range = request.headers["Content-Range"].to_str().unwrap();
+            TransportResponse {
+                status: if content_range.starts_with("bytes 0-") {
+                    StatusCode::PARTIAL_CONTENT
+                } else {
+                    StatusCode::BAD_REQUEST
+                },
+                body: vec![],
+            }
+        });
+        let ran = queue
+            .run_next(&MockClient, &transport, "w1", &UploadOptions::new())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ran.id, job.id);
+        assert_eq!(ran.attempts, 1);
+        assert_eq!(ran.lease, None);
+        match &ran.state {
+            JobState::Transferring { upload, checkpoint } => {
+                assert_eq!(upload.publish_id, "v_inbox_file~v2.123456789");
+                assert_eq!(
+                    checkpoint,
+                    &Checkpoint {
+                        next_chunk: 1,
+                        uploaded_bytes: CHUNK_SIZE_MIN
+                    }
+                );
+            }
+            x => panic!("{x:?}"),
+        }
+        assert_eq!(queue.storage().get(job.id).unwrap(), Some(ran));
+
+        // Resumes from the checkpoint, without a new init.
+        let transport = MockTransport::upload_server();
+        let ran = queue
+            .run_next(&MockClient, &transport, "w2", &UploadOptions::new())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            ran.state,
+            JobState::AwaitingProcessing {
+                publish_id: "v_inbox_file~v2.123456789".into()
+            }
+        );
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].headers["Content-Range"]
+            .to_str()
+            .unwrap()
+            .starts_with(&format!("bytes {CHUNK_SIZE_MIN}-")));
+
+        assert!(queue
+            .run_next(&MockClient, &transport, "w2", &UploadOptions::new())
+            .await
+            .unwrap()
+            .is_none());
+
+        let job = queue
+            .mark_completed("v_inbox_file~v2.123456789")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            job.state,
+            JobState::Completed {
+                publish_id: "v_inbox_file~v2.123456789".into()
+            }
+        );
+
+        std::fs::remove_file(&file_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_next_after_crash() {
+        let queue = UploadQueue::new(MemoryStorage::new(), token_store()).with_max_attempts(1);
+        let job = queue
+            .enqueue(NewUploadJob {
+                open_id: "u1".into(),
+                file_path: temp_path("missing.mp4"),
+                content_type: "video/mp4".into(),
+                chunk_size: None,
+            })
+            .await
+            .unwrap();
+
+        // w1 claimed it and died, the lease ran out.
+        let now = Utc::now();
+        let mut stale = queue.storage().claim("w1", now, now).unwrap().unwrap();
+
+        let ran = queue
+            .run_next(
+                &MockClient,
+                &MockTransport::upload_server(),
+                "w2",
+                &UploadOptions::new(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ran.id, job.id);
+        match &ran.state {
+            JobState::Failed {
+                publish_id: None, ..
+            } => {}
+            x => panic!("{x:?}"),
+        }
+
+        // w1 came back, its writes are refused.
+        stale.lease = None;
+        assert!(!queue.storage().update(&mut stale).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_run_next_token_missing() {
+        let file_path = temp_path("x.mp4");
+        std::fs::write(&file_path, b"\x00\x00\x00\x0cftypisom").unwrap();
+
+        let queue = UploadQueue::new(MemoryStorage::new(), token_store());
+        queue
+            .enqueue(NewUploadJob {
+                open_id: "u2".into(),
+                file_path: file_path.to_owned(),
+                content_type: "video/mp4".into(),
+                chunk_size: None,
+            })
+            .await
+            .unwrap();
+
+        let ran = queue
+            .run_next(
+                &MockClient,
+                &MockTransport::upload_server(),
+                "w1",
+                &UploadOptions::new(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+        assert_eq!(
+            ran.state,
+            JobState::Failed {
+                publish_id: None,
+                error: "access_token missing".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_for_open_id() {
+        let queue = UploadQueue::new(MemoryStorage::new(), token_store());
+        let new_job = |open_id: &str| NewUploadJob {
+            open_id: open_id.into(),
+            file_path: temp_path("missing.mp4"),
+            content_type: "video/mp4".into(),
+            chunk_size: None,
+        };
+        let job_1 = queue.enqueue(new_job("u1")).await.unwrap();
+        let job_2 = queue.enqueue(new_job("u1")).await.unwrap();
+        let job_3 = queue.enqueue(new_job("u2")).await.unwrap();
+
+        // w1 is working on the first one.
+        let now = Utc::now();
+        let mut running = queue
+            .storage()
+            .claim("w1", now, now + Duration::minutes(5))
+            .unwrap()
+            .unwrap();
+        assert_eq!(running.id, job_1.id);
+
+        assert_eq!(
+            queue
+                .cancel_for_open_id("u1", "deauthorized")
+                .await
+                .unwrap(),
+            vec![job_1.id, job_2.id]
+        );
+        for id in [job_1.id, job_2.id] {
+            let job = queue.storage().get(id).unwrap().unwrap();
+            assert_eq!(
+                job.state,
+                JobState::Failed {
+                    publish_id: None,
+                    error: "deauthorized".into()
+                }
+            );
+            assert_eq!(job.lease, None);
+        }
+        assert_eq!(
+            queue.storage().get(job_3.id).unwrap().unwrap().state,
+            JobState::Queued
+        );
+
+        assert!(!queue.storage().update(&mut running).unwrap());
+        assert!(queue
+            .cancel_for_open_id("u1", "deauthorized")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
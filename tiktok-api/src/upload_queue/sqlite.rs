@@ -0,0 +1,225 @@
+use std::{path::Path, sync::Mutex};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension as _, TransactionBehavior};
+
+use super::{
+    storage::{is_claimable, JobStorage, StorageError},
+    JobId, Lease, NewUploadJob, UploadJob,
+};
+
+//
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS upload_jobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    open_id TEXT NOT NULL,
+    publish_id TEXT,
+    runnable INTEGER NOT NULL,
+    lease_expires_at INTEGER,
+    version INTEGER NOT NULL,
+    job TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS upload_jobs_claim ON upload_jobs (runnable, lease_expires_at);
+CREATE INDEX IF NOT EXISTS upload_jobs_open_id ON upload_jobs (open_id);
+CREATE INDEX IF NOT EXISTS upload_jobs_publish_id ON upload_jobs (publish_id);
+";
+
+//
+//
+//
+/// Claims run in an immediate transaction, so workers in several processes can share one file.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, StorageError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    pub fn with_connection(conn: Connection) -> Result<Self, StorageError> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+fn from_row(id: JobId, job: String) -> Result<UploadJob, StorageError> {
+    let mut job: UploadJob = serde_json::from_str(&job)?;
+    job.id = id;
+    Ok(job)
+}
+
+fn query(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<UploadJob>, StorageError> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+        .query_map(params, |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    rows.into_iter()
+        .map(|(id, job)| from_row(id, job))
+        .collect()
+}
+
+fn write(conn: &Connection, job: &UploadJob) -> Result<usize, StorageError> {
+    Ok(conn.execute(
+        "UPDATE upload_jobs SET publish_id = ?7, runnable = ?2, lease_expires_at = ?3, version = ?4, job = ?5 WHERE id = ?1 AND version = ?6",
+        params![
+            job.id,
+            job.state.is_runnable(),
+            job.lease.as_ref().map(|x| x.expires_at.timestamp_millis()),
+            job.version + 1,
+            serde_json::to_string(&UploadJob {
+                version: job.version + 1,
+                ..job.to_owned()
+            })?,
+            job.version,
+            job.state.publish_id(),
+        ],
+    )?)
+}
+
+impl JobStorage for SqliteStorage {
+    fn insert(&self, job: NewUploadJob, now: DateTime<Utc>) -> Result<UploadJob, StorageError> {
+        let conn = self.conn.lock().expect("SqliteStorage poisoned");
+        let job = UploadJob::new(0, job, now);
+        conn.execute(
+            "INSERT INTO upload_jobs (open_id, publish_id, runnable, lease_expires_at, version, job) VALUES (?1, ?2, ?3, NULL, ?4, ?5)",
+            params![
+                job.open_id,
+                job.state.publish_id(),
+                job.state.is_runnable(),
+                job.version,
+                serde_json::to_string(&job)?
+            ],
+        )?;
+        Ok(UploadJob {
+            id: conn.last_insert_rowid() as JobId,
+            ..job
+        })
+    }
+
+    fn get(&self, id: JobId) -> Result<Option<UploadJob>, StorageError> {
+        let conn = self.conn.lock().expect("SqliteStorage poisoned");
+        conn.query_row(
+            "SELECT job FROM upload_jobs WHERE id = ?1",
+            params![id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?
+        .map(|job| from_row(id, job))
+        .transpose()
+    }
+
+    fn list(&self) -> Result<Vec<UploadJob>, StorageError> {
+        let conn = self.conn.lock().expect("SqliteStorage poisoned");
+        query(&conn, "SELECT id, job FROM upload_jobs ORDER BY id", [])
+    }
+
+    fn list_by_open_id(&self, open_id: &str) -> Result<Vec<UploadJob>, StorageError> {
+        let conn = self.conn.lock().expect("SqliteStorage poisoned");
+        query(
+            &conn,
+            "SELECT id, job FROM upload_jobs WHERE open_id = ?1 ORDER BY id",
+            params![open_id],
+        )
+    }
+
+    fn get_by_publish_id(&self, publish_id: &str) -> Result<Option<UploadJob>, StorageError> {
+        let conn = self.conn.lock().expect("SqliteStorage poisoned");
+        Ok(query(
+            &conn,
+            "SELECT id, job FROM upload_jobs WHERE publish_id = ?1 ORDER BY id DESC LIMIT 1",
+            params![publish_id],
+        )?
+        .pop())
+    }
+
+    fn claim(
+        &self,
+        worker_id: &str,
+        now: DateTime<Utc>,
+        lease_expires_at: DateTime<Utc>,
+    ) -> Result<Option<UploadJob>, StorageError> {
+        let mut conn = self.conn.lock().expect("SqliteStorage poisoned");
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let row = tx
+            .query_row(
+                "SELECT id, job FROM upload_jobs WHERE runnable = 1 AND (lease_expires_at IS NULL OR lease_expires_at <= ?1) ORDER BY id LIMIT 1",
+                params![now.timestamp_millis()],
+                |row| Ok((row.get(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()?;
+        let mut job = match row {
+            Some((id, job)) => from_row(id, job)?,
+            None => return Ok(None),
+        };
+        debug_assert!(is_claimable(&job, now));
+
+        job.lease = Some(Lease {
+            worker_id: worker_id.into(),
+            expires_at: lease_expires_at,
+        });
+        job.updated_at = now;
+        write(&tx, &job)?;
+        tx.commit()?;
+
+        job.version += 1;
+        Ok(Some(job))
+    }
+
+    fn update(&self, job: &mut UploadJob) -> Result<bool, StorageError> {
+        let conn = self.conn.lock().expect("SqliteStorage poisoned");
+        if write(&conn, job)? == 0 {
+            return Ok(false);
+        }
+        job.version += 1;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_storage() {
+        crate::upload_queue::storage::tests::check_storage(
+            &SqliteStorage::open_in_memory().unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_sqlite_storage_reopen() {
+        let path = crate::upload_queue::storage::tests::temp_path("jobs.sqlite3");
+
+        let storage = SqliteStorage::open(&path).unwrap();
+        let job = storage
+            .insert(
+                NewUploadJob {
+                    open_id: "u1".into(),
+                    file_path: "x.mp4".into(),
+                    content_type: "video/mp4".into(),
+                    chunk_size: None,
+                },
+                Utc::now(),
+            )
+            .unwrap();
+        drop(storage);
+
+        let storage = SqliteStorage::open(&path).unwrap();
+        assert_eq!(storage.get(job.id).unwrap(), Some(job));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Mutex,
+};
+
+use chrono::{DateTime, Utc};
+
+use super::{JobId, Lease, NewUploadJob, UploadJob};
+
+//
+pub type StorageError = Box<dyn std::error::Error + Send + Sync>;
+
+//
+//
+//
+/// Persists upload jobs. Writes are versioned, so a worker that lost its lease
+/// (e.g. presumed crashed) cannot overwrite the worker that took over.
+pub trait JobStorage: Send + Sync {
+    fn insert(&self, job: NewUploadJob, now: DateTime<Utc>) -> Result<UploadJob, StorageError>;
+
+    fn get(&self, id: JobId) -> Result<Option<UploadJob>, StorageError>;
+
+    fn list(&self) -> Result<Vec<UploadJob>, StorageError>;
+
+    /// Jobs of `open_id`, oldest first.
+    fn list_by_open_id(&self, open_id: &str) -> Result<Vec<UploadJob>, StorageError>;
+
+    /// The job whose current state carries `publish_id`.
+    fn get_by_publish_id(&self, publish_id: &str) -> Result<Option<UploadJob>, StorageError>;
+
+    /// Leases the oldest runnable job that is not leased, or whose lease expired before `now`.
+    fn claim(
+        &self,
+        worker_id: &str,
+        now: DateTime<Utc>,
+        lease_expires_at: DateTime<Utc>,
+    ) -> Result<Option<UploadJob>, StorageError>;
+
+    /// Writes `job` only if the stored version is still `job.version`, then bumps it.
+    /// Returns false when the job was changed by someone else.
+    fn update(&self, job: &mut UploadJob) -> Result<bool, StorageError>;
+}
+
+pub(super) fn is_claimable(job: &UploadJob, now: DateTime<Utc>) -> bool {
+    job.state.is_runnable()
+        && job
+            .lease
+            .as_ref()
+            .map(|x| x.expires_at <= now)
+            .unwrap_or(true)
+}
+
+//
+//
+//
+/// Jobs are lost with the process, for tests and single-process setups.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    inner: Mutex<MemoryStorageInner>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryStorageInner {
+    jobs: BTreeMap<JobId, UploadJob>,
+    by_open_id: BTreeMap<String, BTreeSet<JobId>>,
+    by_publish_id: BTreeMap<String, JobId>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl JobStorage for MemoryStorage {
+    fn insert(&self, job: NewUploadJob, now: DateTime<Utc>) -> Result<UploadJob, StorageError> {
+        let mut inner = self.inner.lock().expect("MemoryStorage poisoned");
+        let id = inner.jobs.keys().next_back().map(|x| x + 1).unwrap_or(1);
+        let job = UploadJob::new(id, job, now);
+        inner
+            .by_open_id
+            .entry(job.open_id.to_owned())
+            .or_default()
+            .insert(id);
+        if let Some(publish_id) = job.state.publish_id() {
+            inner.by_publish_id.insert(publish_id.into(), id);
+        }
+        inner.jobs.insert(id, job.to_owned());
+        Ok(job)
+    }
+
+    fn get(&self, id: JobId) -> Result<Option<UploadJob>, StorageError> {
+        let inner = self.inner.lock().expect("MemoryStorage poisoned");
+        Ok(inner.jobs.get(&id).cloned())
+    }
+
+    fn list(&self) -> Result<Vec<UploadJob>, StorageError> {
+        let inner = self.inner.lock().expect("MemoryStorage poisoned");
+        Ok(inner.jobs.values().cloned().collect())
+    }
+
+    fn list_by_open_id(&self, open_id: &str) -> Result<Vec<UploadJob>, StorageError> {
+        let inner = self.inner.lock().expect("MemoryStorage poisoned");
+        Ok(inner
+            .by_open_id
+            .get(open_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| inner.jobs.get(id).cloned())
+            .collect())
+    }
+
+    fn get_by_publish_id(&self, publish_id: &str) -> Result<Option<UploadJob>, StorageError> {
+        let inner = self.inner.lock().expect("MemoryStorage poisoned");
+        Ok(inner
+            .by_publish_id
+            .get(publish_id)
+            .and_then(|id| inner.jobs.get(id).cloned()))
+    }
+
+    fn claim(
+        &self,
+        worker_id: &str,
+        now: DateTime<Utc>,
+        lease_expires_at: DateTime<Utc>,
+    ) -> Result<Option<UploadJob>, StorageError> {
+        let mut inner = self.inner.lock().expect("MemoryStorage poisoned");
+        Ok(inner
+            .jobs
+            .values_mut()
+            .find(|x| is_claimable(x, now))
+            .map(|job| {
+                job.lease = Some(Lease {
+                    worker_id: worker_id.into(),
+                    expires_at: lease_expires_at,
+                });
+                job.version += 1;
+                job.updated_at = now;
+                job.to_owned()
+            }))
+    }
+
+    fn update(&self, job: &mut UploadJob) -> Result<bool, StorageError> {
+        let mut inner = self.inner.lock().expect("MemoryStorage poisoned");
+        let MemoryStorageInner {
+            jobs,
+            by_publish_id,
+            ..
+        } = &mut *inner;
+        match jobs.get_mut(&job.id) {
+            Some(stored) if stored.version == job.version => {
+                if let Some(publish_id) = stored.state.publish_id() {
+                    by_publish_id.remove(publish_id);
+                }
+                if let Some(publish_id) = job.state.publish_id() {
+                    by_publish_id.insert(publish_id.into(), job.id);
+                }
+                job.version += 1;
+                *stored = job.to_owned();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use std::{
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use chrono::Duration;
+
+    use crate::upload_queue::JobState;
+
+    static TEMP_PATH_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+    /// Unique per call, also across processes and test runs.
+    pub(crate) fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "tiktok_api_upload_queue_{}_{}_{}_{name}",
+            std::process::id(),
+            Utc::now().timestamp_micros(),
+            TEMP_PATH_SEQ.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+
+    pub(crate) fn check_storage(storage: &impl JobStorage) {
+        let now = Utc::now();
+        let new_job = |open_id: &str| NewUploadJob {
+            open_id: open_id.into(),
+            file_path: temp_path("x.mp4"),
+            content_type: "video/mp4".into(),
+            chunk_size: None,
+        };
+
+        let job_1 = storage.insert(new_job("u1"), now).unwrap();
+        let job_2 = storage.insert(new_job("u2"), now).unwrap();
+        assert_ne!(job_1.id, job_2.id);
+        assert_eq!(storage.get(job_1.id).unwrap(), Some(job_1.to_owned()));
+        assert_eq!(storage.list().unwrap().len(), 2);
+
+        // Oldest first, a leased job is skipped.
+        let claimed_1 = storage
+            .claim("w1", now, now + Duration::minutes(5))
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed_1.id, job_1.id);
+        let claimed_2 = storage
+            .claim("w2", now, now + Duration::minutes(5))
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed_2.id, job_2.id);
+        assert_eq!(
+            storage
+                .claim("w3", now, now + Duration::minutes(5))
+                .unwrap(),
+            None
+        );
+
+        // w1 crashed, w3 takes over after the lease expired.
+        let mut claimed_1_again = storage
+            .claim(
+                "w3",
+                now + Duration::minutes(6),
+                now + Duration::minutes(11),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed_1_again.id, job_1.id);
+        assert_eq!(claimed_1_again.lease.as_ref().unwrap().worker_id, "w3");
+
+        let mut stale = claimed_1;
+        stale.state = JobState::Failed {
+            publish_id: None,
+            error: "stale".into(),
+        };
+        assert!(!storage.update(&mut stale).unwrap());
+
+        claimed_1_again.state = JobState::AwaitingProcessing {
+            publish_id: "p1".into(),
+        };
+        claimed_1_again.lease = None;
+        assert!(storage.update(&mut claimed_1_again).unwrap());
+        assert_eq!(
+            storage.get(job_1.id).unwrap(),
+            Some(claimed_1_again.to_owned())
+        );
+        assert_eq!(
+            storage.get_by_publish_id("p1").unwrap(),
+            Some(claimed_1_again.to_owned())
+        );
+        assert_eq!(storage.get_by_publish_id("p2").unwrap(), None);
+        assert_eq!(
+            storage
+                .list_by_open_id("u1")
+                .unwrap()
+                .into_iter()
+                .map(|x| x.id)
+                .collect::<Vec<_>>(),
+            vec![job_1.id]
+        );
+        assert!(storage.list_by_open_id("u3").unwrap().is_empty());
+
+        // Not runnable anymore.
+        assert_eq!(
+            storage
+                .claim("w4", now + Duration::hours(1), now + Duration::hours(2))
+                .unwrap()
+                .map(|x| x.id),
+            Some(job_2.id)
+        );
+    }
+
+    #[test]
+    fn test_memory_storage() {
+        check_storage(&MemoryStorage::new());
+    }
+}
@@ -0,0 +1,219 @@
+// https://developers.tiktok.com/doc/content-posting-api-media-transfer-guide/#pull_from_url
+
+use url::Url;
+
+//
+/// Domains and URL prefixes verified for the app in the developer portal.
+/// `PULL_FROM_URL` media must be served over https from one of them.
+#[derive(Debug, Clone, Default)]
+pub struct VerifiedSources {
+    /// Also covers subdomains.
+    pub domains: Vec<String>,
+    pub url_prefixes: Vec<Url>,
+}
+
+impl VerifiedSources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_domain(mut self, domain: impl AsRef<str>) -> Self {
+        self.domains
+            .push(domain.as_ref().trim_end_matches('.').to_ascii_lowercase());
+        self
+    }
+
+    /// Matches the same scheme, host and port, and the path on `/` boundaries,
+    /// so `https://cdn.example.com/tiktok` does not cover `/tiktok-other/`.
+    pub fn with_url_prefix(mut self, url_prefix: Url) -> Self {
+        self.url_prefixes.push(url_prefix);
+        self
+    }
+
+    pub fn verify(&self, url: &Url) -> Result<(), UnverifiedUrl> {
+        if url.scheme() != "https" {
+            return Err(UnverifiedUrl::HttpsRequired {
+                url: url.to_owned(),
+            });
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| UnverifiedUrl::HostMissing {
+                url: url.to_owned(),
+            })?
+            .to_ascii_lowercase();
+
+        if self
+            .domains
+            .iter()
+            .any(|domain| host == *domain || host.ends_with(&format!(".{domain}")))
+        {
+            return Ok(());
+        }
+        if self
+            .url_prefixes
+            .iter()
+            .any(|url_prefix| is_under_url_prefix(url, url_prefix))
+        {
+            return Ok(());
+        }
+
+        Err(UnverifiedUrl::Unverified {
+            url: url.to_owned(),
+            closest: self.closest(&host, url.path()),
+        })
+    }
+
+    /// Ranks by matching trailing host labels, then by the common path prefix,
+    /// a domain covers every path.
+    fn closest(&self, host: &str, path: &str) -> Option<String> {
+        let domains = self
+            .domains
+            .iter()
+            .map(|domain| ((host_score(host, domain), usize::MAX), domain.to_owned()));
+        let url_prefixes = self.url_prefixes.iter().map(|url_prefix| {
+            let score = (
+                host_score(host, url_prefix.host_str().unwrap_or_default()),
+                path.chars()
+                    .zip(url_prefix.path().chars())
+                    .take_while(|(a, b)| a == b)
+                    .count(),
+            );
+            (score, url_prefix.to_string())
+        });
+
+        domains
+            .chain(url_prefixes)
+            .fold(
+                None,
+                |closest: Option<((usize, usize), String)>, x| match closest {
+                    Some(closest) if closest.0 >= x.0 => Some(closest),
+                    _ => Some(x),
+                },
+            )
+            .map(|(_, x)| x)
+    }
+}
+
+fn is_under_url_prefix(url: &Url, url_prefix: &Url) -> bool {
+    if url.scheme() != url_prefix.scheme()
+        || url.host_str().map(|x| x.to_ascii_lowercase())
+            != url_prefix.host_str().map(|x| x.to_ascii_lowercase())
+        || url.port_or_known_default() != url_prefix.port_or_known_default()
+    {
+        return false;
+    }
+
+    let prefix_path = url_prefix.path();
+    match url.path().strip_prefix(prefix_path) {
+        Some(rest) => prefix_path.ends_with('/') || rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+fn host_score(host: &str, other: &str) -> usize {
+    host.rsplit('.')
+        .zip(other.to_ascii_lowercase().rsplit('.'))
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+//
+//
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnverifiedUrl {
+    HttpsRequired {
+        url: Url,
+    },
+    HostMissing {
+        url: Url,
+    },
+    /// TikTok would reject it with `url_ownership_unverified`.
+    Unverified {
+        url: Url,
+        /// The verified domain or URL prefix most similar to `url`.
+        closest: Option<String>,
+    },
+}
+impl core::fmt::Display for UnverifiedUrl {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for UnverifiedUrl {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let verified_sources = VerifiedSources::new()
+            .with_domain("Example.com")
+            .with_url_prefix("https://cdn.other.com/tiktok/".parse().unwrap())
+            .with_url_prefix("https://media.other.com/videos/".parse().unwrap());
+
+        for url in [
+            "https://example.com/x.mp4",
+            "https://www.example.com/a/b/x.mp4",
+            "https://cdn.other.com/tiktok/x.mp4",
+        ] {
+            assert_eq!(verified_sources.verify(&url.parse().unwrap()), Ok(()));
+        }
+
+        match verified_sources.verify(&"http://example.com/x.mp4".parse().unwrap()) {
+            Err(UnverifiedUrl::HttpsRequired { .. }) => {}
+            x => panic!("{x:?}"),
+        }
+        match verified_sources.verify(&"https://notexample.com/x.mp4".parse().unwrap()) {
+            Err(UnverifiedUrl::Unverified { closest, .. }) => {
+                assert_eq!(closest.as_deref(), Some("example.com"))
+            }
+            x => panic!("{x:?}"),
+        }
+        match verified_sources.verify(&"https://media.other.com/video/x.mp4".parse().unwrap()) {
+            Err(UnverifiedUrl::Unverified { closest, .. }) => {
+                assert_eq!(closest.as_deref(), Some("https://media.other.com/videos/"))
+            }
+            x => panic!("{x:?}"),
+        }
+        match VerifiedSources::new().verify(&"https://example.com/x.mp4".parse().unwrap()) {
+            Err(UnverifiedUrl::Unverified { closest: None, .. }) => {}
+            x => panic!("{x:?}"),
+        }
+    }
+
+    #[test]
+    fn test_verify_url_prefix_boundaries() {
+        let verified_sources = VerifiedSources::new()
+            .with_url_prefix("https://cdn.other.com/tiktok".parse().unwrap())
+            .with_url_prefix("https://media.other.com:8443/videos/".parse().unwrap());
+
+        for url in [
+            "https://cdn.other.com/tiktok",
+            "https://cdn.other.com/tiktok/x.mp4",
+            "https://CDN.other.com:443/tiktok/a/x.mp4",
+            "https://media.other.com:8443/videos/x.mp4",
+        ] {
+            assert_eq!(
+                verified_sources.verify(&url.parse().unwrap()),
+                Ok(()),
+                "{url}"
+            );
+        }
+
+        for url in [
+            "https://cdn.other.com/tiktok-evil/x.mp4",
+            "https://cdn.other.com/tiktokx.mp4",
+            "https://cdn.other.com:8443/tiktok/x.mp4",
+            "https://cdn.other.com.evil.com/tiktok/x.mp4",
+            "https://media.other.com/videos/x.mp4",
+        ] {
+            match verified_sources.verify(&url.parse().unwrap()) {
+                Err(UnverifiedUrl::Unverified { .. }) => {}
+                x => panic!("{url} {x:?}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,679 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use http_api_client::Client;
+use tokio::{sync::oneshot, time::Instant};
+
+use super::WebhookEvent;
+use crate::endpoints::v2::{
+    publish_status_fetch::{PublishStatus, PublishStatusFetchResponseBody},
+    EndpointRet, PublishStatusFetchEndpoint,
+};
+
+//
+//
+//
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CorrelationKey {
+    /// `VideoUploadInitResponseBodyData::publish_id`
+    PublishId(String),
+    /// The legacy `VideoUploadResponseBodyData::share_id`, which has no status endpoint.
+    ShareId(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Completed,
+    /// Final for inbox uploads, the user finishes the post in the app.
+    InboxDelivered,
+    Failed {
+        reason: Option<String>,
+    },
+}
+
+impl Outcome {
+    pub fn from_event(event: &WebhookEvent) -> Option<(CorrelationKey, Self)> {
+        match event {
+            WebhookEvent::PostPublishComplete(x) => Some((
+                CorrelationKey::PublishId(x.publish_id.to_owned()),
+                Self::Completed,
+            )),
+            WebhookEvent::PostPublishInboxDelivered(x) => Some((
+                CorrelationKey::PublishId(x.publish_id.to_owned()),
+                Self::InboxDelivered,
+            )),
+            WebhookEvent::PostPublishFailed(x) => Some((
+                CorrelationKey::PublishId(x.publish_id.to_owned()),
+                Self::Failed {
+                    reason: Some(x.reason.to_owned()),
+                },
+            )),
+            WebhookEvent::VideoPublishCompleted(x) => Some((
+                CorrelationKey::ShareId(x.share_id.to_owned()),
+                Self::Completed,
+            )),
+            WebhookEvent::VideoUploadFailed(x) => Some((
+                CorrelationKey::ShareId(x.share_id.to_owned()),
+                Self::Failed { reason: None },
+            )),
+            _ => None,
+        }
+    }
+
+    /// None while TikTok is still processing.
+    pub fn from_status(status: &PublishStatus, fail_reason: Option<String>) -> Option<Self> {
+        match status {
+            PublishStatus::PublishComplete => Some(Self::Completed),
+            PublishStatus::SendToUserInbox => Some(Self::InboxDelivered),
+            PublishStatus::Failed => Some(Self::Failed {
+                reason: fail_reason,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolvedBy {
+    Webhook,
+    Polling,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolution<T> {
+    pub context: T,
+    pub outcome: Outcome,
+    pub resolved_by: ResolvedBy,
+}
+
+type Callback<T> = Box<dyn FnOnce(Resolution<T>) + Send>;
+
+enum Waiter<T> {
+    Future(oneshot::Sender<Resolution<T>>),
+    Callback(Callback<T>),
+}
+
+struct Entry<T> {
+    id: u64,
+    context: T,
+    waiter: Waiter<T>,
+}
+
+struct State<T> {
+    next_id: u64,
+    entries: HashMap<CorrelationKey, Entry<T>>,
+}
+
+//
+//
+//
+/// Maps outstanding publish_ids and share_ids to caller context `T`, until the matching
+/// completed or failed webhook arrives.
+///
+/// Feed it from a `WebhookHandler` with `on_event`, or pass it to `WebhookReceiver` as is.
+pub struct CorrelationRegistry<T> {
+    state: Arc<Mutex<State<T>>>,
+}
+
+impl<T> Clone for CorrelationRegistry<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.to_owned(),
+        }
+    }
+}
+
+impl<T> Default for CorrelationRegistry<T> {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                next_id: 0,
+                entries: HashMap::new(),
+            })),
+        }
+    }
+}
+
+impl<T> core::fmt::Debug for CorrelationRegistry<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CorrelationRegistry")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<T> CorrelationRegistry<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State<T>> {
+        self.state.lock().expect("CorrelationRegistry poisoned")
+    }
+
+    fn insert(&self, key: CorrelationKey, context: T, waiter: Waiter<T>) -> u64 {
+        let mut state = self.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        // A replaced future resolves to `CorrelationError::Removed`.
+        state.entries.insert(
+            key,
+            Entry {
+                id,
+                context,
+                waiter,
+            },
+        );
+        id
+    }
+
+    /// Register right after the init response, webhooks can arrive before the upload returns.
+    pub fn register(&self, key: CorrelationKey, context: T) -> Pending<T> {
+        let (tx, rx) = oneshot::channel();
+        let id = self.insert(key.to_owned(), context, Waiter::Future(tx));
+        Pending {
+            key,
+            id,
+            rx,
+            registry: self.to_owned(),
+        }
+    }
+
+    /// `callback` runs on the task that resolves, keep it short.
+    /// There is no timeout, call `poll_status` to fall back.
+    pub fn register_callback<F>(&self, key: CorrelationKey, context: T, callback: F)
+    where
+        F: FnOnce(Resolution<T>) + Send + 'static,
+    {
+        self.insert(key, context, Waiter::Callback(Box::new(callback)));
+    }
+
+    /// Returns false when `key` is not outstanding, e.g. a duplicate or someone else's post.
+    pub fn resolve(&self, key: &CorrelationKey, outcome: Outcome) -> bool {
+        self.resolve_by(key, outcome, ResolvedBy::Webhook)
+    }
+
+    fn resolve_by(&self, key: &CorrelationKey, outcome: Outcome, resolved_by: ResolvedBy) -> bool {
+        let Some(entry) = self.lock().entries.remove(key) else {
+            return false;
+        };
+
+        let resolution = Resolution {
+            context: entry.context,
+            outcome,
+            resolved_by,
+        };
+        match entry.waiter {
+            Waiter::Future(tx) => {
+                let _ = tx.send(resolution);
+            }
+            Waiter::Callback(callback) => callback(resolution),
+        }
+        true
+    }
+
+    pub fn on_event(&self, event: &WebhookEvent) -> bool {
+        match Outcome::from_event(event) {
+            Some((key, outcome)) => self.resolve(&key, outcome),
+            None => false,
+        }
+    }
+
+    pub fn remove(&self, key: &CorrelationKey) -> Option<T> {
+        self.lock().entries.remove(key).map(|entry| entry.context)
+    }
+
+    pub fn contains(&self, key: &CorrelationKey) -> bool {
+        self.lock().entries.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fetches the status once, and resolves `publish_id` when it is terminal.
+    pub async fn poll_status<C>(
+        &self,
+        client: &C,
+        access_token: &str,
+        publish_id: &str,
+    ) -> Result<PublishStatus, CorrelationError>
+    where
+        C: Client + Send + Sync,
+    {
+        let endpoint = PublishStatusFetchEndpoint::new(access_token, publish_id);
+        let data = match client.respond_endpoint(&endpoint).await {
+            Ok(EndpointRet::Ok(body)) => body.data,
+            Ok(ret) => return Err(CorrelationError::PollRejected(ret)),
+            Err(err) => return Err(CorrelationError::PollFailed(Box::new(err))),
+        };
+
+        if let Some(outcome) = Outcome::from_status(&data.status, data.fail_reason) {
+            self.resolve_by(
+                &CorrelationKey::PublishId(publish_id.into()),
+                outcome,
+                ResolvedBy::Polling,
+            );
+        }
+        Ok(data.status)
+    }
+}
+
+//
+//
+//
+/// Dropping it deregisters the key.
+pub struct Pending<T> {
+    key: CorrelationKey,
+    id: u64,
+    rx: oneshot::Receiver<Resolution<T>>,
+    registry: CorrelationRegistry<T>,
+}
+
+impl<T> core::fmt::Debug for Pending<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Pending").field("key", &self.key).finish()
+    }
+}
+
+impl<T> Pending<T> {
+    pub fn key(&self) -> &CorrelationKey {
+        &self.key
+    }
+
+    /// Waits for the webhook only.
+    pub async fn wait(mut self) -> Result<Resolution<T>, CorrelationError> {
+        (&mut self.rx).await.map_err(|_| CorrelationError::Removed)
+    }
+
+    /// Waits `timeout` for the webhook, then polls the status endpoint every `poll_interval`
+    /// until it is terminal. A webhook arriving meanwhile still wins.
+    ///
+    /// Fails with `TimedOut` once `max_wait`, counted from the call, has passed.
+    /// Retryable poll errors are passed to `on_poll_error` and polled again, other ones are returned.
+    ///
+    /// A share_id cannot be polled, it fails with `TimedOut`.
+    pub async fn wait_or_poll<C>(
+        mut self,
+        client: &C,
+        access_token: &str,
+        timeout: Duration,
+        poll_interval: Duration,
+        max_wait: Duration,
+        mut on_poll_error: impl FnMut(&CorrelationError),
+    ) -> Result<Resolution<T>, CorrelationError>
+    where
+        C: Client + Send + Sync,
+    {
+        let deadline = Instant::now() + max_wait;
+
+        if let Ok(ret) = tokio::time::timeout_at(
+            core::cmp::min(Instant::now() + timeout, deadline),
+            &mut self.rx,
+        )
+        .await
+        {
+            return ret.map_err(|_| CorrelationError::Removed);
+        }
+
+        let publish_id = match &self.key {
+            CorrelationKey::PublishId(x) => x.to_owned(),
+            CorrelationKey::ShareId(_) => return Err(CorrelationError::TimedOut),
+        };
+
+        while Instant::now() < deadline {
+            match self
+                .registry
+                .poll_status(client, access_token, &publish_id)
+                .await
+            {
+                Ok(_) => {}
+                Err(err) if err.is_retryable() => on_poll_error(&err),
+                Err(err) => return Err(err),
+            }
+
+            if let Ok(ret) = tokio::time::timeout_at(
+                core::cmp::min(Instant::now() + poll_interval, deadline),
+                &mut self.rx,
+            )
+            .await
+            {
+                return ret.map_err(|_| CorrelationError::Removed);
+            }
+        }
+
+        Err(CorrelationError::TimedOut)
+    }
+}
+
+impl<T> Drop for Pending<T> {
+    fn drop(&mut self) {
+        let mut state = self.registry.lock();
+        // Not when it was resolved, or replaced by a newer registration.
+        if state.entries.get(&self.key).map(|entry| entry.id) == Some(self.id) {
+            state.entries.remove(&self.key);
+        }
+    }
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub enum CorrelationError {
+    /// Removed or registered again before it resolved.
+    Removed,
+    TimedOut,
+    PollFailed(Box<dyn std::error::Error + Send + Sync>),
+    PollRejected(EndpointRet<PublishStatusFetchResponseBody>),
+}
+impl CorrelationError {
+    /// Whether polling again may succeed, e.g. after a transport error or a rate limit.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::PollFailed(_) => true,
+            Self::PollRejected(ret) => match ret.to_owned().into_result() {
+                Ok(_) => false,
+                Err(err) => err.classify().is_retryable(),
+            },
+            Self::Removed | Self::TimedOut => false,
+        }
+    }
+}
+impl core::fmt::Display for CorrelationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for CorrelationError {}
+
+//
+//
+//
+#[cfg(feature = "with_webhook_receiver")]
+mod impl_webhook_handler {
+    use http_api_client::async_trait;
+
+    use super::*;
+    use crate::webhooks::{
+        PostPublishContent, PostPublishFailed, ShareContent, WebhookEnvelope, WebhookHandler,
+    };
+
+    #[async_trait]
+    impl<T> WebhookHandler for CorrelationRegistry<T>
+    where
+        T: Send + 'static,
+    {
+        async fn video_upload_failed(&self, _envelope: &WebhookEnvelope, content: ShareContent) {
+            self.on_event(&WebhookEvent::VideoUploadFailed(content));
+        }
+        async fn video_publish_completed(
+            &self,
+            _envelope: &WebhookEnvelope,
+            content: ShareContent,
+        ) {
+            self.on_event(&WebhookEvent::VideoPublishCompleted(content));
+        }
+        async fn post_publish_failed(
+            &self,
+            _envelope: &WebhookEnvelope,
+            content: PostPublishFailed,
+        ) {
+            self.on_event(&WebhookEvent::PostPublishFailed(content));
+        }
+        async fn post_publish_complete(
+            &self,
+            _envelope: &WebhookEnvelope,
+            content: PostPublishContent,
+        ) {
+            self.on_event(&WebhookEvent::PostPublishComplete(content));
+        }
+        async fn post_publish_inbox_delivered(
+            &self,
+            _envelope: &WebhookEnvelope,
+            content: PostPublishContent,
+        ) {
+            self.on_event(&WebhookEvent::PostPublishInboxDelivered(content));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use http_api_client::async_trait;
+    use http_api_client_endpoint::{http::StatusCode, Body, Request, Response};
+
+    use crate::webhooks::{PostPublishContent, PublishType, ShareContent};
+
+    #[derive(Default)]
+    struct MockClient {
+        fetches: AtomicUsize,
+        /// The first fetches fail with a transport error.
+        failures: usize,
+        response: Option<(StatusCode, &'static str)>,
+    }
+
+    #[async_trait]
+    impl Client for MockClient {
+        type RespondError = std::io::Error;
+
+        async fn respond(
+            &self,
+            request: Request<Body>,
+        ) -> Result<Response<Body>, Self::RespondError> {
+            assert_eq!(
+                request.uri(),
+                crate::endpoints::v2::publish_status_fetch::URL
+            );
+            if self.fetches.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(std::io::Error::other("connection reset"));
+            }
+            let (status, body) = self.response.unwrap_or((
+                StatusCode::OK,
+                include_str!("../../tests/response_body_files/v2/publish_status_fetch.json"),
+            ));
+            Ok(Response::builder()
+                .status(status)
+                .body(body.as_bytes().to_vec())
+                .unwrap())
+        }
+    }
+
+    fn publish_id(x: &str) -> CorrelationKey {
+        CorrelationKey::PublishId(x.into())
+    }
+
+    #[tokio::test]
+    async fn test_webhook() {
+        let registry = CorrelationRegistry::new();
+
+        let pending = registry.register(publish_id("p1"), "post 1");
+        let (tx, rx) = std::sync::mpsc::channel();
+        registry.register_callback(
+            CorrelationKey::ShareId("s1".into()),
+            "share 1",
+            move |resolution| tx.send(resolution).unwrap(),
+        );
+        assert_eq!(registry.len(), 2);
+
+        assert!(registry.on_event(&WebhookEvent::PostPublishInboxDelivered(
+            PostPublishContent {
+                publish_id: "p1".into(),
+                publish_type: PublishType::InboxShare,
+            }
+        )));
+        // Duplicate.
+        assert!(
+            !registry.on_event(&WebhookEvent::PostPublishComplete(PostPublishContent {
+                publish_id: "p1".into(),
+                publish_type: PublishType::InboxShare,
+            }))
+        );
+        assert_eq!(
+            pending.wait().await.unwrap(),
+            Resolution {
+                context: "post 1",
+                outcome: Outcome::InboxDelivered,
+                resolved_by: ResolvedBy::Webhook,
+            }
+        );
+
+        assert!(
+            registry.on_event(&WebhookEvent::VideoUploadFailed(ShareContent {
+                share_id: "s1".into()
+            }))
+        );
+        assert_eq!(
+            rx.try_recv().unwrap().outcome,
+            Outcome::Failed { reason: None }
+        );
+        assert!(registry.is_empty());
+
+        // Dropped unresolved.
+        drop(registry.register(publish_id("p2"), "post 2"));
+        assert!(registry.is_empty());
+
+        // Replaced.
+        let first = registry.register(publish_id("p3"), "post 3");
+        let second = registry.register(publish_id("p3"), "post 3 again");
+        assert!(matches!(first.wait().await, Err(CorrelationError::Removed)));
+        assert!(registry.contains(&publish_id("p3")));
+        drop(second);
+        assert!(registry.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wait_or_poll() {
+        let registry = CorrelationRegistry::new();
+        let client = MockClient::default();
+
+        let resolution = registry
+            .register(publish_id("v_pub_file~v2.123456789"), 1)
+            .wait_or_poll(
+                &client,
+                "TOKEN",
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+                Duration::from_secs(10),
+                |err| panic!("{err:?}"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resolution.context, 1);
+        assert_eq!(resolution.outcome, Outcome::Completed);
+        assert_eq!(resolution.resolved_by, ResolvedBy::Polling);
+        assert_eq!(client.fetches.load(Ordering::SeqCst), 1);
+        assert!(registry.is_empty());
+
+        match registry
+            .register(CorrelationKey::ShareId("s1".into()), 2)
+            .wait_or_poll(
+                &client,
+                "TOKEN",
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+                Duration::from_secs(10),
+                |err| panic!("{err:?}"),
+            )
+            .await
+        {
+            Err(CorrelationError::TimedOut) => {}
+            x => panic!("{x:?}"),
+        }
+        assert!(registry.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wait_or_poll_errors() {
+        let registry = CorrelationRegistry::new();
+
+        // Retryable, polled again.
+        let client = MockClient {
+            failures: 2,
+            ..Default::default()
+        };
+        let mut poll_errors = 0;
+        let resolution = registry
+            .register(publish_id("v_pub_file~v2.123456789"), 1)
+            .wait_or_poll(
+                &client,
+                "TOKEN",
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+                Duration::from_secs(10),
+                |err| {
+                    assert!(matches!(err, CorrelationError::PollFailed(_)));
+                    poll_errors += 1;
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(resolution.outcome, Outcome::Completed);
+        assert_eq!(poll_errors, 2);
+        assert_eq!(client.fetches.load(Ordering::SeqCst), 3);
+
+        // Still processing.
+        let client = MockClient {
+            response: Some((
+                StatusCode::OK,
+                r#"{"data":{"status":"PROCESSING_UPLOAD"},"error":{"code":"ok","message":"","log_id":"1"}}"#,
+            )),
+            ..Default::default()
+        };
+        match registry
+            .register(publish_id("v_pub_file~v2.123456789"), 2)
+            .wait_or_poll(
+                &client,
+                "TOKEN",
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+                Duration::from_millis(100),
+                |err| panic!("{err:?}"),
+            )
+            .await
+        {
+            Err(CorrelationError::TimedOut) => {}
+            x => panic!("{x:?}"),
+        }
+        assert!(client.fetches.load(Ordering::SeqCst) >= 1);
+
+        // Not retryable.
+        let client = MockClient {
+            response: Some((
+                StatusCode::UNAUTHORIZED,
+                include_str!(
+                    "../../tests/response_body_files/v2/user_info__err__access_token_invalid.json"
+                ),
+            )),
+            ..Default::default()
+        };
+        match registry
+            .register(publish_id("v_pub_file~v2.123456789"), 3)
+            .wait_or_poll(
+                &client,
+                "TOKEN",
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+                Duration::from_secs(10),
+                |err| panic!("{err:?}"),
+            )
+            .await
+        {
+            Err(err @ CorrelationError::PollRejected(_)) => assert!(!err.is_retryable()),
+            x => panic!("{x:?}"),
+        }
+        assert_eq!(client.fetches.load(Ordering::SeqCst), 1);
+        assert!(registry.is_empty());
+    }
+}
@@ -0,0 +1,484 @@
+use http_api_client::{async_trait, Client};
+
+use super::{
+    AuthorizationRemoved, AuthorizationRemovedReason, PostPublishContent, PostPublishFailed,
+    PostPublishPublicContent, ShareContent, WebhookEnvelope, WebhookHandler,
+};
+pub use crate::token_store::{StorageError, TokenStore};
+use crate::{
+    endpoints::oauth::{EndpointRet, RevokeEndpoint},
+    objects::oauth::{AccessToken, LegacyErrorCode},
+};
+
+pub type RevokeError = Box<dyn std::error::Error + Send + Sync>;
+
+//
+//
+//
+/// Revokes a deauthorized user's access token, so it cannot be used even if a copy is left.
+#[async_trait]
+pub trait TokenRevoker: Send + Sync + 'static {
+    /// A token that is already revoked or expired counts as revoked.
+    async fn revoke(&self, token: &AccessToken) -> Result<(), RevokeError>;
+}
+
+/// Calls the oauth revoke endpoint.
+#[async_trait]
+impl<C> TokenRevoker for C
+where
+    C: Client + Send + Sync + 'static,
+{
+    async fn revoke(&self, token: &AccessToken) -> Result<(), RevokeError> {
+        let endpoint = RevokeEndpoint::new(&token.open_id, &token.access_token);
+        let ret = self
+            .respond_endpoint(&endpoint)
+            .await
+            .map_err(|err| Box::new(err) as RevokeError)?;
+        match ret {
+            EndpointRet::Other((_, Ok(err_json)))
+                if err_json.data.error_code == LegacyErrorCode::AccessTokenInvalid =>
+            {
+                Ok(())
+            }
+            ret => ret.into_result().map(|_| ()).map_err(Into::into),
+        }
+    }
+}
+
+//
+//
+//
+/// Stops uploads of a deauthorized user, their access token is already rejected.
+#[async_trait]
+pub trait UploadCanceller: Send + Sync + 'static {
+    /// Returns how many were cancelled.
+    async fn cancel_uploads(
+        &self,
+        open_id: &str,
+        reason: AuthorizationRemovedReason,
+    ) -> Result<usize, StorageError>;
+}
+
+#[cfg(feature = "with_upload_queue")]
+#[async_trait]
+impl<S> UploadCanceller for crate::upload_queue::UploadQueue<S>
+where
+    S: crate::upload_queue::JobStorage + 'static,
+{
+    async fn cancel_uploads(
+        &self,
+        open_id: &str,
+        reason: AuthorizationRemovedReason,
+    ) -> Result<usize, StorageError> {
+        self.cancel_for_open_id(open_id, format!("deauthorized: {reason:?}"))
+            .await
+            .map(|ids| ids.len())
+            .map_err(Into::into)
+    }
+}
+
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deauthorized {
+    pub open_id: String,
+    pub reason: AuthorizationRemovedReason,
+}
+
+#[derive(Debug, Clone)]
+pub struct Purged {
+    /// Deleted and revoked.
+    pub token: Option<AccessToken>,
+    pub cancelled_uploads: usize,
+}
+
+//
+//
+//
+/// Wraps the app's handler. On `authorization.removed` it deletes and revokes the user's
+/// token, cancels their uploads and calls `H::deauthorized` instead of
+/// `H::authorization_removed`. Other events are passed through.
+pub struct DeauthorizationHandler<H, T> {
+    inner: H,
+    token_store: T,
+    token_revoker: Box<dyn TokenRevoker>,
+    upload_cancellers: Vec<Box<dyn UploadCanceller>>,
+}
+
+impl<H, T> core::fmt::Debug for DeauthorizationHandler<H, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeauthorizationHandler")
+            .field("upload_cancellers", &self.upload_cancellers.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<H, T> DeauthorizationHandler<H, T>
+where
+    H: WebhookHandler,
+    T: TokenStore,
+{
+    /// `token_revoker` is usually the `http_api_client::Client`.
+    pub fn new(inner: H, token_store: T, token_revoker: impl TokenRevoker) -> Self {
+        Self {
+            inner,
+            token_store,
+            token_revoker: Box::new(token_revoker),
+            upload_cancellers: vec![],
+        }
+    }
+
+    pub fn with_upload_canceller(mut self, upload_canceller: impl UploadCanceller) -> Self {
+        self.upload_cancellers.push(Box::new(upload_canceller));
+        self
+    }
+
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+
+    pub fn token_store(&self) -> &T {
+        &self.token_store
+    }
+
+    /// Every step is attempted, the first error is returned.
+    pub async fn purge(&self, deauthorized: &Deauthorized) -> Result<Purged, DeauthorizationError> {
+        let mut err = None;
+
+        let token = self
+            .token_store
+            .delete(&deauthorized.open_id)
+            .await
+            .unwrap_or_else(|x| {
+                err = Some(DeauthorizationError::DeleteTokenFailed(x));
+                None
+            });
+
+        if let Some(token) = token.as_ref() {
+            if let Err(x) = self.token_revoker.revoke(token).await {
+                err.get_or_insert(DeauthorizationError::RevokeTokenFailed(x));
+            }
+        }
+
+        let mut cancelled_uploads = 0;
+        for upload_canceller in &self.upload_cancellers {
+            match upload_canceller
+                .cancel_uploads(&deauthorized.open_id, deauthorized.reason)
+                .await
+            {
+                Ok(n) => cancelled_uploads += n,
+                Err(x) => {
+                    err.get_or_insert(DeauthorizationError::CancelUploadsFailed(x));
+                }
+            }
+        }
+
+        match err {
+            Some(err) => Err(err),
+            None => Ok(Purged {
+                token,
+                cancelled_uploads,
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl<H, T> WebhookHandler for DeauthorizationHandler<H, T>
+where
+    H: WebhookHandler,
+    T: TokenStore,
+{
+    async fn authorization_removed(
+        &self,
+        envelope: &WebhookEnvelope,
+        content: AuthorizationRemoved,
+    ) {
+        let deauthorized = Deauthorized {
+            open_id: envelope.user_openid.to_owned(),
+            reason: content.reason,
+        };
+        let purged = self.purge(&deauthorized).await;
+        self.inner
+            .deauthorized(envelope, deauthorized, purged)
+            .await
+    }
+    async fn video_upload_failed(&self, envelope: &WebhookEnvelope, content: ShareContent) {
+        self.inner.video_upload_failed(envelope, content).await
+    }
+    async fn video_publish_completed(&self, envelope: &WebhookEnvelope, content: ShareContent) {
+        self.inner.video_publish_completed(envelope, content).await
+    }
+    async fn post_publish_failed(&self, envelope: &WebhookEnvelope, content: PostPublishFailed) {
+        self.inner.post_publish_failed(envelope, content).await
+    }
+    async fn post_publish_complete(&self, envelope: &WebhookEnvelope, content: PostPublishContent) {
+        self.inner.post_publish_complete(envelope, content).await
+    }
+    async fn post_publish_inbox_delivered(
+        &self,
+        envelope: &WebhookEnvelope,
+        content: PostPublishContent,
+    ) {
+        self.inner
+            .post_publish_inbox_delivered(envelope, content)
+            .await
+    }
+    async fn post_publish_publicly_available(
+        &self,
+        envelope: &WebhookEnvelope,
+        content: PostPublishPublicContent,
+    ) {
+        self.inner
+            .post_publish_publicly_available(envelope, content)
+            .await
+    }
+    async fn post_publish_no_longer_publicly_available(
+        &self,
+        envelope: &WebhookEnvelope,
+        content: PostPublishPublicContent,
+    ) {
+        self.inner
+            .post_publish_no_longer_publicly_available(envelope, content)
+            .await
+    }
+    async fn other(&self, envelope: &WebhookEnvelope, event: &str, content: serde_json::Value) {
+        self.inner.other(envelope, event, content).await
+    }
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub enum DeauthorizationError {
+    DeleteTokenFailed(StorageError),
+    RevokeTokenFailed(RevokeError),
+    CancelUploadsFailed(StorageError),
+}
+impl core::fmt::Display for DeauthorizationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for DeauthorizationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use http_api_client::{Body, Request, Response};
+    use http_api_client_endpoint::http::StatusCode;
+
+    use crate::token_store::MemoryTokenStore;
+
+    #[derive(Clone)]
+    struct MockClient {
+        response: (StatusCode, &'static str),
+        revoked: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockClient {
+        fn new(status: StatusCode, body: &'static str) -> Self {
+            Self {
+                response: (status, body),
+                revoked: Default::default(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Client for MockClient {
+        type RespondError = std::io::Error;
+
+        async fn respond(
+            &self,
+            request: Request<Body>,
+        ) -> Result<Response<Body>, Self::RespondError> {
+            assert!(request
+                .uri()
+                .to_string()
+                .starts_with(crate::endpoints::oauth::revoke::URL));
+            self.revoked.lock().unwrap().push(request.uri().to_string());
+            let (status, body) = self.response;
+            Ok(Response::builder()
+                .status(status)
+                .body(body.as_bytes().to_vec())
+                .unwrap())
+        }
+    }
+
+    struct FailingUploadCanceller;
+
+    #[async_trait]
+    impl UploadCanceller for FailingUploadCanceller {
+        async fn cancel_uploads(
+            &self,
+            _open_id: &str,
+            _reason: AuthorizationRemovedReason,
+        ) -> Result<usize, StorageError> {
+            Err("unavailable".into())
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingHandler {
+        events: Mutex<Vec<(Deauthorized, Result<Purged, DeauthorizationError>)>>,
+    }
+
+    #[async_trait]
+    impl WebhookHandler for RecordingHandler {
+        async fn authorization_removed(
+            &self,
+            _envelope: &WebhookEnvelope,
+            _content: AuthorizationRemoved,
+        ) {
+            panic!("replaced by deauthorized")
+        }
+
+        async fn deauthorized(
+            &self,
+            _envelope: &WebhookEnvelope,
+            event: Deauthorized,
+            purged: Result<Purged, DeauthorizationError>,
+        ) {
+            self.events.lock().unwrap().push((event, purged));
+        }
+    }
+
+    fn access_token(open_id: &str) -> AccessToken {
+        AccessToken {
+            open_id: open_id.into(),
+            scope: "user.info.basic".into(),
+            access_token: "TOKEN".into(),
+            expires_in: 86400,
+            refresh_token: "REFRESH_TOKEN".into(),
+            refresh_expires_in: 31536000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_authorization_removed() {
+        let envelope = WebhookEnvelope::from_slice(include_bytes!(
+            "../../tests/webhook_body_files/authorization_removed.json"
+        ))
+        .unwrap();
+        let open_id = envelope.user_openid.to_owned();
+        let removed = AuthorizationRemoved {
+            reason: AuthorizationRemovedReason::UserDisconnected,
+        };
+
+        let token_store = MemoryTokenStore::new();
+        token_store.insert(access_token(&open_id));
+        let client = MockClient::new(
+            StatusCode::OK,
+            include_str!("../../tests/response_body_files/oauth/revoke.json"),
+        );
+        let handler = DeauthorizationHandler::new(
+            RecordingHandler::default(),
+            token_store,
+            client.to_owned(),
+        );
+
+        handler
+            .authorization_removed(&envelope, removed.to_owned())
+            .await;
+        assert!(handler.token_store().get(&open_id).await.unwrap().is_none());
+        assert_eq!(client.revoked.lock().unwrap().len(), 1);
+        // Delivered again, nothing left to delete.
+        let handler = handler.with_upload_canceller(FailingUploadCanceller);
+        handler.authorization_removed(&envelope, removed).await;
+
+        let events = handler.inner().events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0].0,
+            Deauthorized {
+                open_id: open_id.to_owned(),
+                reason: AuthorizationRemovedReason::UserDisconnected,
+            }
+        );
+        match &events[0].1 {
+            Ok(Purged {
+                token: Some(token),
+                cancelled_uploads: 0,
+            }) => assert_eq!(token.open_id, open_id),
+            x => panic!("{x:?}"),
+        }
+        match &events[1].1 {
+            Err(DeauthorizationError::CancelUploadsFailed(_)) => {}
+            x => panic!("{x:?}"),
+        }
+        assert_eq!(client.revoked.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_revoke() {
+        let token = access_token("u1");
+
+        // Already revoked.
+        let client = MockClient::new(
+            StatusCode::OK,
+            include_str!(
+                "../../tests/response_body_files/oauth/revoke__err__with_refresh_token.json"
+            ),
+        );
+        client.revoke(&token).await.unwrap();
+        assert_eq!(
+            client.revoked.lock().unwrap().as_slice(),
+            ["https://open-api.tiktok.com/oauth/revoke/?open_id=u1&access_token=TOKEN"]
+        );
+
+        let token_store = MemoryTokenStore::new();
+        token_store.insert(token);
+        let handler = DeauthorizationHandler::new(
+            RecordingHandler::default(),
+            token_store,
+            MockClient::new(StatusCode::INTERNAL_SERVER_ERROR, ""),
+        );
+        match handler
+            .purge(&Deauthorized {
+                open_id: "u1".into(),
+                reason: AuthorizationRemovedReason::UserDisconnected,
+            })
+            .await
+        {
+            Err(DeauthorizationError::RevokeTokenFailed(_)) => {}
+            x => panic!("{x:?}"),
+        }
+        // Deleted anyway.
+        assert!(handler.token_store().get("u1").await.unwrap().is_none());
+    }
+
+    #[cfg(feature = "with_upload_queue")]
+    #[tokio::test]
+    async fn test_cancel_uploads() {
+        use crate::upload_queue::{
+            JobState, JobStorage as _, MemoryStorage, NewUploadJob, UploadQueue,
+        };
+
+        let queue = UploadQueue::new(MemoryStorage::new(), MemoryTokenStore::new());
+        let job = queue
+            .enqueue(NewUploadJob {
+                open_id: "u1".into(),
+                file_path: crate::upload_queue::storage::tests::temp_path("missing.mp4"),
+                content_type: "video/mp4".into(),
+                chunk_size: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            queue
+                .cancel_uploads("u1", AuthorizationRemovedReason::AccountBanned)
+                .await
+                .unwrap(),
+            1
+        );
+        match queue.storage().get(job.id).unwrap().unwrap().state {
+            JobState::Failed { error, .. } => assert_eq!(error, "deauthorized: AccountBanned"),
+            x => panic!("{x:?}"),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
+
+//
+/// `authorization.removed`
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct AuthorizationRemoved {
+    pub reason: AuthorizationRemovedReason,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(from = "isize", into = "isize")]
+pub enum AuthorizationRemovedReason {
+    Unknown,
+    UserDisconnected,
+    AccountDeleted,
+    AgeChanged,
+    AccountBanned,
+    DeveloperRevoked,
+    Other(isize),
+}
+
+impl From<isize> for AuthorizationRemovedReason {
+    fn from(x: isize) -> Self {
+        match x {
+            0 => Self::Unknown,
+            1 => Self::UserDisconnected,
+            2 => Self::AccountDeleted,
+            3 => Self::AgeChanged,
+            4 => Self::AccountBanned,
+            5 => Self::DeveloperRevoked,
+            x => Self::Other(x),
+        }
+    }
+}
+
+impl From<AuthorizationRemovedReason> for isize {
+    fn from(x: AuthorizationRemovedReason) -> Self {
+        match x {
+            AuthorizationRemovedReason::Unknown => 0,
+            AuthorizationRemovedReason::UserDisconnected => 1,
+            AuthorizationRemovedReason::AccountDeleted => 2,
+            AuthorizationRemovedReason::AgeChanged => 3,
+            AuthorizationRemovedReason::AccountBanned => 4,
+            AuthorizationRemovedReason::DeveloperRevoked => 5,
+            AuthorizationRemovedReason::Other(x) => x,
+        }
+    }
+}
+
+/// `video.upload.failed` and `video.publish.completed`
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ShareContent {
+    pub share_id: String,
+}
+
+/// `post.publish.failed`
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PostPublishFailed {
+    pub publish_id: String,
+    pub reason: String,
+    pub publish_type: PublishType,
+}
+
+/// `post.publish.complete` and `post.publish.inbox_delivered`
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PostPublishContent {
+    pub publish_id: String,
+    pub publish_type: PublishType,
+}
+
+/// `post.publish.publicly_available` and `post.publish.no_longer_publicaly_available`
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PostPublishPublicContent {
+    pub publish_id: String,
+    pub post_id: String,
+    pub publish_type: PublishType,
+}
+
+#[derive(Deserialize_enum_str, Serialize_enum_str, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PublishType {
+    DirectPublish,
+    InboxShare,
+    #[serde(other)]
+    Other(Box<str>),
+}
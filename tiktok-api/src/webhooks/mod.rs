@@ -0,0 +1,299 @@
+// https://developers.tiktok.com/doc/webhooks-overview/
+// https://developers.tiktok.com/doc/webhooks-events/
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
+
+use crate::objects::share::VideoUploadState;
+
+pub mod events;
+pub use events::{
+    AuthorizationRemoved, AuthorizationRemovedReason, PostPublishContent, PostPublishFailed,
+    PostPublishPublicContent, PublishType, ShareContent,
+};
+
+#[cfg(feature = "with_webhook_signature")]
+pub mod signature;
+#[cfg(feature = "with_webhook_signature")]
+pub use signature::{verify_webhook_signature, SignatureError};
+
+#[cfg(feature = "with_webhook_receiver")]
+pub mod receiver;
+#[cfg(feature = "with_webhook_receiver")]
+pub use receiver::{IdempotencyStore, MemoryIdempotencyStore, WebhookHandler, WebhookReceiver};
+
+#[cfg(feature = "with_webhook_receiver")]
+pub mod deauthorization;
+#[cfg(feature = "with_webhook_receiver")]
+pub use deauthorization::{
+    DeauthorizationHandler, Deauthorized, TokenRevoker, TokenStore, UploadCanceller,
+};
+
+#[cfg(feature = "with_webhook_correlation")]
+pub mod correlation;
+#[cfg(feature = "with_webhook_correlation")]
+pub use correlation::{CorrelationKey, CorrelationRegistry};
+
+//
+/// The body TikTok POSTs to the callback URL, `content` is a JSON string.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WebhookEnvelope {
+    pub client_key: String,
+    pub event: EventName,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub create_time: DateTime<Utc>,
+    pub user_openid: String,
+    pub content: String,
+}
+
+impl WebhookEnvelope {
+    pub fn from_slice(body: &[u8]) -> Result<Self, WebhookError> {
+        serde_json::from_slice(body).map_err(WebhookError::DeEnvelopeFailed)
+    }
+
+    /// Decodes `content` by `event`.
+    pub fn event(&self) -> Result<WebhookEvent, WebhookError> {
+        WebhookEvent::from_content(&self.event, &self.content)
+    }
+}
+
+#[derive(Deserialize_enum_str, Serialize_enum_str, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EventName {
+    #[serde(rename = "authorization.removed")]
+    AuthorizationRemoved,
+    #[serde(rename = "video.upload.failed")]
+    VideoUploadFailed,
+    #[serde(rename = "video.publish.completed")]
+    VideoPublishCompleted,
+    #[serde(rename = "post.publish.failed")]
+    PostPublishFailed,
+    #[serde(rename = "post.publish.complete")]
+    PostPublishComplete,
+    #[serde(rename = "post.publish.inbox_delivered")]
+    PostPublishInboxDelivered,
+    #[serde(rename = "post.publish.publicly_available")]
+    PostPublishPubliclyAvailable,
+    /// Spelled as TikTok sends it.
+    #[serde(rename = "post.publish.no_longer_publicaly_available")]
+    PostPublishNoLongerPubliclyAvailable,
+    #[serde(other)]
+    Other(Box<str>),
+}
+
+//
+//
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebhookEvent {
+    AuthorizationRemoved(AuthorizationRemoved),
+    VideoUploadFailed(ShareContent),
+    VideoPublishCompleted(ShareContent),
+    PostPublishFailed(PostPublishFailed),
+    PostPublishComplete(PostPublishContent),
+    PostPublishInboxDelivered(PostPublishContent),
+    PostPublishPubliclyAvailable(PostPublishPublicContent),
+    PostPublishNoLongerPubliclyAvailable(PostPublishPublicContent),
+    /// An event this crate does not know yet.
+    Other {
+        event: Box<str>,
+        content: serde_json::Value,
+    },
+}
+
+impl WebhookEvent {
+    pub fn from_content(event: &EventName, content: &str) -> Result<Self, WebhookError> {
+        fn de<T: serde::de::DeserializeOwned>(
+            event: &EventName,
+            content: &str,
+        ) -> Result<T, WebhookError> {
+            serde_json::from_str(content).map_err(|err| WebhookError::DeContentFailed {
+                event: event.to_owned(),
+                err,
+            })
+        }
+
+        Ok(match event {
+            EventName::AuthorizationRemoved => Self::AuthorizationRemoved(de(event, content)?),
+            EventName::VideoUploadFailed => Self::VideoUploadFailed(de(event, content)?),
+            EventName::VideoPublishCompleted => Self::VideoPublishCompleted(de(event, content)?),
+            EventName::PostPublishFailed => Self::PostPublishFailed(de(event, content)?),
+            EventName::PostPublishComplete => Self::PostPublishComplete(de(event, content)?),
+            EventName::PostPublishInboxDelivered => {
+                Self::PostPublishInboxDelivered(de(event, content)?)
+            }
+            EventName::PostPublishPubliclyAvailable => {
+                Self::PostPublishPubliclyAvailable(de(event, content)?)
+            }
+            EventName::PostPublishNoLongerPubliclyAvailable => {
+                Self::PostPublishNoLongerPubliclyAvailable(de(event, content)?)
+            }
+            EventName::Other(name) => Self::Other {
+                event: name.to_owned(),
+                content: de(event, content)?,
+            },
+        })
+    }
+
+    /// For the share (Web Video Kit) events.
+    pub fn video_upload_state(&self) -> Option<VideoUploadState> {
+        match self {
+            Self::VideoUploadFailed(_) => Some(VideoUploadState::Failed),
+            Self::VideoPublishCompleted(_) => Some(VideoUploadState::Completed),
+            _ => None,
+        }
+    }
+
+    pub fn publish_id(&self) -> Option<&str> {
+        match self {
+            Self::PostPublishFailed(x) => Some(&x.publish_id),
+            Self::PostPublishComplete(x) | Self::PostPublishInboxDelivered(x) => {
+                Some(&x.publish_id)
+            }
+            Self::PostPublishPubliclyAvailable(x)
+            | Self::PostPublishNoLongerPubliclyAvailable(x) => Some(&x.publish_id),
+            _ => None,
+        }
+    }
+}
+
+//
+//
+//
+#[derive(Debug)]
+pub enum WebhookError {
+    DeEnvelopeFailed(serde_json::Error),
+    DeContentFailed {
+        event: EventName,
+        err: serde_json::Error,
+    },
+}
+impl core::fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for WebhookError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_de_envelope() {
+        let envelope = WebhookEnvelope::from_slice(include_bytes!(
+            "../../tests/webhook_body_files/authorization_removed.json"
+        ))
+        .unwrap();
+        assert_eq!(envelope.client_key, "bwo2m45353a6k85");
+        assert_eq!(envelope.event, EventName::AuthorizationRemoved);
+        assert_eq!(envelope.create_time.timestamp(), 1615338610);
+        assert_eq!(envelope.user_openid, "act.example12345Example12345Example");
+        assert_eq!(
+            envelope.event().unwrap(),
+            WebhookEvent::AuthorizationRemoved(AuthorizationRemoved {
+                reason: AuthorizationRemovedReason::UserDisconnected
+            })
+        );
+
+        let envelope = WebhookEnvelope::from_slice(include_bytes!(
+            "../../tests/webhook_body_files/video_publish_completed.json"
+        ))
+        .unwrap();
+        let event = envelope.event().unwrap();
+        assert_eq!(
+            event,
+            WebhookEvent::VideoPublishCompleted(ShareContent {
+                share_id: "video.6974245311675353080.VDCxrcMJ".into()
+            })
+        );
+        assert_eq!(
+            event.video_upload_state(),
+            Some(VideoUploadState::Completed)
+        );
+
+        let envelope = WebhookEnvelope::from_slice(include_bytes!(
+            "../../tests/webhook_body_files/post_publish_failed.json"
+        ))
+        .unwrap();
+        match envelope.event().unwrap() {
+            WebhookEvent::PostPublishFailed(x) => {
+                assert_eq!(x.publish_id, "v_pub_file~v2.123456789");
+                assert_eq!(x.reason, "file_format_check_failed");
+                assert_eq!(x.publish_type, PublishType::DirectPublish);
+            }
+            x => panic!("{x:?}"),
+        }
+
+        let envelope = WebhookEnvelope::from_slice(include_bytes!(
+            "../../tests/webhook_body_files/post_publish_publicly_available.json"
+        ))
+        .unwrap();
+        let event = envelope.event().unwrap();
+        assert_eq!(event.publish_id(), Some("v_pub_file~v2.123456789"));
+        match event {
+            WebhookEvent::PostPublishPubliclyAvailable(x) => {
+                assert_eq!(x.post_id, "7182846487765412654");
+            }
+            x => panic!("{x:?}"),
+        }
+    }
+
+    #[test]
+    fn test_de_event() {
+        for (event, content) in [
+            (
+                "post.publish.complete",
+                r#"{"publish_id":"p1","publish_type":"INBOX_SHARE"}"#,
+            ),
+            (
+                "post.publish.inbox_delivered",
+                r#"{"publish_id":"p1","publish_type":"INBOX_SHARE"}"#,
+            ),
+            (
+                "post.publish.no_longer_publicaly_available",
+                r#"{"publish_id":"p1","post_id":"1","publish_type":"DIRECT_PUBLISH"}"#,
+            ),
+        ] {
+            let event: EventName = event.parse().unwrap();
+            assert!(!matches!(event, EventName::Other(_)));
+            let event = WebhookEvent::from_content(&event, content).unwrap();
+            assert_eq!(event.publish_id(), Some("p1"));
+        }
+
+        match WebhookEvent::from_content(&"video.upload.failed".parse().unwrap(), "{}") {
+            Err(WebhookError::DeContentFailed {
+                event: EventName::VideoUploadFailed,
+                ..
+            }) => {}
+            x => panic!("{x:?}"),
+        }
+
+        match WebhookEvent::from_content(&"foo.bar".parse().unwrap(), r#"{"x":1}"#).unwrap() {
+            WebhookEvent::Other { event, content } => {
+                assert_eq!(&*event, "foo.bar");
+                assert_eq!(content, serde_json::json!({"x": 1}));
+            }
+            x => panic!("{x:?}"),
+        }
+
+        for (content, reason) in [
+            (r#"{"reason":0}"#, AuthorizationRemovedReason::Unknown),
+            (
+                r#"{"reason":5}"#,
+                AuthorizationRemovedReason::DeveloperRevoked,
+            ),
+            (r#"{"reason":9}"#, AuthorizationRemovedReason::Other(9)),
+        ] {
+            let event = WebhookEvent::from_content(&EventName::AuthorizationRemoved, content);
+            assert_eq!(
+                event.unwrap(),
+                WebhookEvent::AuthorizationRemoved(AuthorizationRemoved { reason })
+            );
+            assert_eq!(
+                serde_json::to_string(&AuthorizationRemoved { reason }).unwrap(),
+                content
+            );
+        }
+    }
+}
@@ -0,0 +1,462 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration as StdDuration, Instant},
+};
+
+use chrono::Duration;
+use http_api_client::async_trait;
+use hyper::{
+    body::HttpBody as _, header::CONTENT_LENGTH, Body, Method, Request, Response, StatusCode,
+};
+use sha2::{Digest as _, Sha256};
+
+use crate::hex;
+
+use super::{
+    deauthorization::{DeauthorizationError, Deauthorized, Purged},
+    signature::{verify_webhook_signature, TOLERANCE_SECS_DEFAULT},
+    AuthorizationRemoved, PostPublishContent, PostPublishFailed, PostPublishPublicContent,
+    ShareContent, WebhookEnvelope, WebhookEvent,
+};
+
+//
+pub const BODY_SIZE_MAX: usize = 1024 * 1024;
+/// TikTok retries a delivery for up to 72 hours.
+pub const IDEMPOTENCY_TTL_SECS: u64 = 72 * 60 * 60;
+
+//
+//
+//
+/// One method per event, called in the background after the delivery was acknowledged.
+#[async_trait]
+#[allow(unused_variables)]
+pub trait WebhookHandler: Send + Sync + 'static {
+    async fn authorization_removed(
+        &self,
+        envelope: &WebhookEnvelope,
+        content: AuthorizationRemoved,
+    ) {
+    }
+    async fn video_upload_failed(&self, envelope: &WebhookEnvelope, content: ShareContent) {}
+    async fn video_publish_completed(&self, envelope: &WebhookEnvelope, content: ShareContent) {}
+    async fn post_publish_failed(&self, envelope: &WebhookEnvelope, content: PostPublishFailed) {}
+    async fn post_publish_complete(&self, envelope: &WebhookEnvelope, content: PostPublishContent) {
+    }
+    async fn post_publish_inbox_delivered(
+        &self,
+        envelope: &WebhookEnvelope,
+        content: PostPublishContent,
+    ) {
+    }
+    async fn post_publish_publicly_available(
+        &self,
+        envelope: &WebhookEnvelope,
+        content: PostPublishPublicContent,
+    ) {
+    }
+    async fn post_publish_no_longer_publicly_available(
+        &self,
+        envelope: &WebhookEnvelope,
+        content: PostPublishPublicContent,
+    ) {
+    }
+    async fn other(&self, envelope: &WebhookEnvelope, event: &str, content: serde_json::Value) {}
+    /// Called by `DeauthorizationHandler` instead of `authorization_removed`, after purging.
+    async fn deauthorized(
+        &self,
+        envelope: &WebhookEnvelope,
+        event: Deauthorized,
+        purged: Result<Purged, DeauthorizationError>,
+    ) {
+    }
+}
+
+/// Remembers deliveries, so a retried one is acknowledged without being handled twice.
+///
+/// Delivery is at-most-once: the key is recorded before the handler runs, and the delivery
+/// was already acknowledged, so an event whose handler fails or is lost with the process is
+/// not redelivered. Handlers needing more should persist the event before acting on it.
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync + 'static {
+    /// Returns false when `key` was seen before.
+    async fn first_seen(&self, key: &str) -> bool;
+}
+
+/// Forgets keys after `IDEMPOTENCY_TTL_SECS`, and on restart.
+#[derive(Debug, Default)]
+pub struct MemoryIdempotencyStore {
+    seen: Mutex<HashMap<String, Instant>>,
+}
+
+#[async_trait]
+impl IdempotencyStore for MemoryIdempotencyStore {
+    async fn first_seen(&self, key: &str) -> bool {
+        let now = Instant::now();
+        let ttl = StdDuration::from_secs(IDEMPOTENCY_TTL_SECS);
+
+        let mut seen = self.seen.lock().expect("MemoryIdempotencyStore poisoned");
+        seen.retain(|_, at| now.duration_since(*at) < ttl);
+        seen.insert(key.into(), now).is_none()
+    }
+}
+
+/// TikTok sends no delivery id, a retry has the same event, time, user and content.
+pub fn idempotency_key(envelope: &WebhookEnvelope) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        envelope.event.to_string().as_bytes(),
+        envelope.create_time.timestamp().to_string().as_bytes(),
+        envelope.user_openid.as_bytes(),
+        envelope.content.as_bytes(),
+    ] {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hex::encode(&hasher.finalize())
+}
+
+//
+//
+//
+/// Verifies, parses and acknowledges deliveries, then dispatches them to `H` on a spawned task.
+///
+/// Call `handle` from a hyper `service_fn`, or from an axum handler taking `Request<Body>`.
+pub struct WebhookReceiver<H, I = MemoryIdempotencyStore> {
+    inner: Arc<Inner<H, I>>,
+}
+
+struct Inner<H, I> {
+    client_secret: String,
+    tolerance: Duration,
+    handler: H,
+    idempotency_store: I,
+}
+
+impl<H, I> Clone for WebhookReceiver<H, I> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.to_owned(),
+        }
+    }
+}
+
+impl<H, I> core::fmt::Debug for WebhookReceiver<H, I> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WebhookReceiver")
+            .field("tolerance", &self.inner.tolerance)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<H> WebhookReceiver<H>
+where
+    H: WebhookHandler,
+{
+    pub fn new(client_secret: impl AsRef<str>, handler: H) -> Self {
+        Self::with_idempotency_store(client_secret, handler, Default::default())
+    }
+}
+
+impl<H, I> WebhookReceiver<H, I>
+where
+    H: WebhookHandler,
+    I: IdempotencyStore,
+{
+    pub fn with_idempotency_store(
+        client_secret: impl AsRef<str>,
+        handler: H,
+        idempotency_store: I,
+    ) -> Self {
+        Self::with_tolerance(
+            client_secret,
+            handler,
+            idempotency_store,
+            Duration::seconds(TOLERANCE_SECS_DEFAULT),
+        )
+    }
+
+    pub fn with_tolerance(
+        client_secret: impl AsRef<str>,
+        handler: H,
+        idempotency_store: I,
+        tolerance: Duration,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                client_secret: client_secret.as_ref().into(),
+                tolerance,
+                handler,
+                idempotency_store,
+            }),
+        }
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.inner.handler
+    }
+
+    pub async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        if request.method() != Method::POST {
+            return status(StatusCode::METHOD_NOT_ALLOWED);
+        }
+        let too_large = request
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|x| x.to_str().ok()?.parse::<usize>().ok())
+            .map(|x| x > BODY_SIZE_MAX)
+            .unwrap_or(false);
+        if too_large {
+            return status(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+
+        // Also caps bodies without Content-Length, e.g. chunked.
+        let (parts, mut body) = request.into_parts();
+        let mut buf = vec![];
+        while let Some(data) = body.data().await {
+            match data {
+                Ok(x) if buf.len() + x.len() <= BODY_SIZE_MAX => buf.extend_from_slice(&x),
+                Ok(_) => return status(StatusCode::PAYLOAD_TOO_LARGE),
+                Err(_) => return status(StatusCode::BAD_REQUEST),
+            }
+        }
+        let body = buf;
+
+        if verify_webhook_signature(
+            &parts.headers,
+            &body,
+            &self.inner.client_secret,
+            self.inner.tolerance,
+        )
+        .is_err()
+        {
+            return status(StatusCode::UNAUTHORIZED);
+        }
+
+        let (envelope, event) = match WebhookEnvelope::from_slice(&body)
+            .and_then(|envelope| envelope.event().map(|event| (envelope, event)))
+        {
+            Ok(x) => x,
+            Err(_) => return status(StatusCode::BAD_REQUEST),
+        };
+
+        if self
+            .inner
+            .idempotency_store
+            .first_seen(&idempotency_key(&envelope))
+            .await
+        {
+            let inner = self.inner.to_owned();
+            tokio::spawn(async move { dispatch(&inner.handler, &envelope, event).await });
+        }
+
+        status(StatusCode::OK)
+    }
+}
+
+async fn dispatch<H>(handler: &H, envelope: &WebhookEnvelope, event: WebhookEvent)
+where
+    H: WebhookHandler,
+{
+    match event {
+        WebhookEvent::AuthorizationRemoved(x) => handler.authorization_removed(envelope, x).await,
+        WebhookEvent::VideoUploadFailed(x) => handler.video_upload_failed(envelope, x).await,
+        WebhookEvent::VideoPublishCompleted(x) => {
+            handler.video_publish_completed(envelope, x).await
+        }
+        WebhookEvent::PostPublishFailed(x) => handler.post_publish_failed(envelope, x).await,
+        WebhookEvent::PostPublishComplete(x) => handler.post_publish_complete(envelope, x).await,
+        WebhookEvent::PostPublishInboxDelivered(x) => {
+            handler.post_publish_inbox_delivered(envelope, x).await
+        }
+        WebhookEvent::PostPublishPubliclyAvailable(x) => {
+            handler.post_publish_publicly_available(envelope, x).await
+        }
+        WebhookEvent::PostPublishNoLongerPubliclyAvailable(x) => {
+            handler
+                .post_publish_no_longer_publicly_available(envelope, x)
+                .await
+        }
+        WebhookEvent::Other { event, content } => handler.other(envelope, &event, content).await,
+    }
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::convert::Infallible;
+    use std::net::SocketAddr;
+
+    use hmac::{Hmac, Mac as _};
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Server,
+    };
+    use tokio::{
+        io::{AsyncReadExt as _, AsyncWriteExt as _},
+        net::TcpStream,
+    };
+
+    #[derive(Default)]
+    struct RecordingHandler {
+        events: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl WebhookHandler for RecordingHandler {
+        async fn authorization_removed(
+            &self,
+            envelope: &WebhookEnvelope,
+            content: AuthorizationRemoved,
+        ) {
+            self.events.lock().unwrap().push(format!(
+                "authorization_removed {} {:?}",
+                envelope.user_openid, content.reason
+            ));
+        }
+
+        async fn post_publish_failed(
+            &self,
+            _envelope: &WebhookEnvelope,
+            content: PostPublishFailed,
+        ) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("post_publish_failed {}", content.publish_id));
+        }
+    }
+
+    async fn post(
+        addr: SocketAddr,
+        signature: Option<String>,
+        body: &[u8],
+    ) -> Result<u16, Box<dyn std::error::Error>> {
+        let mut stream = TcpStream::connect(addr).await?;
+        let signature = signature
+            .map(|x| format!("TikTok-Signature: {x}\r\n"))
+            .unwrap_or_default();
+        stream
+            .write_all(
+                format!(
+                    "POST /webhook HTTP/1.1\r\nHost: localhost\r\n{signature}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .as_bytes(),
+            )
+            .await?;
+        stream.write_all(body).await?;
+
+        let mut buf = vec![];
+        stream.read_to_end(&mut buf).await?;
+        Ok(std::str::from_utf8(&buf[9..12])?.parse()?)
+    }
+
+    fn sign(body: &[u8]) -> String {
+        let t = chrono::Utc::now().timestamp();
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"CLIENT_SECRET").unwrap();
+        mac.update(format!("{t}.").as_bytes());
+        mac.update(body);
+        format!("t={t},s={}", hex::encode(&mac.finalize().into_bytes()))
+    }
+
+    #[tokio::test]
+    async fn test_receiver() -> Result<(), Box<dyn std::error::Error>> {
+        let receiver = WebhookReceiver::new("CLIENT_SECRET", RecordingHandler::default());
+
+        let make_service = {
+            let receiver = receiver.to_owned();
+            make_service_fn(move |_| {
+                let receiver = receiver.to_owned();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| {
+                        let receiver = receiver.to_owned();
+                        async move { Ok::<_, Infallible>(receiver.handle(request).await) }
+                    }))
+                }
+            })
+        };
+        let server = Server::bind(&"127.0.0.1:0".parse()?).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        let authorization_removed =
+            include_bytes!("../../tests/webhook_body_files/authorization_removed.json");
+        let post_publish_failed =
+            include_bytes!("../../tests/webhook_body_files/post_publish_failed.json");
+
+        assert_eq!(post(addr, None, authorization_removed).await?, 401);
+        assert_eq!(
+            post(addr, Some(sign(b"{}")), authorization_removed).await?,
+            401
+        );
+        assert_eq!(post(addr, Some(sign(b"{")), b"{").await?, 400);
+
+        assert_eq!(
+            post(
+                addr,
+                Some(sign(authorization_removed)),
+                authorization_removed
+            )
+            .await?,
+            200
+        );
+        // Retried by TikTok, acknowledged but not handled again.
+        assert_eq!(
+            post(
+                addr,
+                Some(sign(authorization_removed)),
+                authorization_removed
+            )
+            .await?,
+            200
+        );
+        assert_eq!(
+            post(addr, Some(sign(post_publish_failed)), post_publish_failed).await?,
+            200
+        );
+
+        for _ in 0..100 {
+            if receiver.handler().events.lock().unwrap().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(StdDuration::from_millis(10)).await;
+        }
+        let mut events = receiver.handler().events.lock().unwrap().to_owned();
+        events.sort();
+        assert_eq!(
+            events,
+            vec![
+                "authorization_removed act.example12345Example12345Example UserDisconnected",
+                "post_publish_failed v_pub_file~v2.123456789",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_receiver_body_size_max() {
+        let receiver = WebhookReceiver::new("CLIENT_SECRET", RecordingHandler::default());
+
+        // No Content-Length and no end, only the cap stops reading.
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            while sender.send_data(vec![b' '; 64 * 1024].into()).await.is_ok() {}
+        });
+        let mut request = Request::new(body);
+        *request.method_mut() = Method::POST;
+
+        let response = tokio::time::timeout(StdDuration::from_secs(5), receiver.handle(request))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
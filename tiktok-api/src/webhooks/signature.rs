@@ -0,0 +1,189 @@
+// https://developers.tiktok.com/doc/webhooks-verification/
+
+use chrono::{DateTime, Duration, TimeZone as _, Utc};
+use hmac::{Hmac, Mac as _};
+use http_api_client_endpoint::http::HeaderMap;
+use sha2::Sha256;
+
+//
+pub const HEADER_NAME: &str = "TikTok-Signature";
+pub const TOLERANCE_SECS_DEFAULT: i64 = 5 * 60;
+
+//
+//
+//
+/// Checks the `TikTok-Signature: t=<ts>,s=<hmac>` header against `raw_body`, the bytes
+/// exactly as received. Returns the signed timestamp.
+pub fn verify_webhook_signature(
+    headers: &HeaderMap,
+    raw_body: &[u8],
+    client_secret: &str,
+    tolerance: Duration,
+) -> Result<DateTime<Utc>, SignatureError> {
+    verify_webhook_signature_at(headers, raw_body, client_secret, tolerance, Utc::now())
+}
+
+pub fn verify_webhook_signature_at(
+    headers: &HeaderMap,
+    raw_body: &[u8],
+    client_secret: &str,
+    tolerance: Duration,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>, SignatureError> {
+    let header = headers
+        .get(HEADER_NAME)
+        .ok_or(SignatureError::HeaderMissing)?
+        .to_str()
+        .map_err(|_| SignatureError::HeaderInvalid)?;
+
+    let mut t = None;
+    let mut s = None;
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", v)) => t = Some(v),
+            Some(("s", v)) => s = Some(v),
+            _ => {}
+        }
+    }
+    let t = t.ok_or(SignatureError::TimestampMissing)?;
+    let s = s.ok_or(SignatureError::SignatureMissing)?;
+
+    let timestamp = t
+        .parse::<i64>()
+        .ok()
+        .and_then(|x| Utc.timestamp_opt(x, 0).single())
+        .ok_or(SignatureError::TimestampInvalid)?;
+    let signature = crate::hex::decode(s).ok_or(SignatureError::SignatureInvalid)?;
+
+    // Before the HMAC, a replayed body is rejected however well it is signed.
+    if (now - timestamp).abs() > tolerance {
+        return Err(SignatureError::TimestampOutOfTolerance { timestamp, now });
+    }
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(client_secret.as_bytes())
+        .expect("HMAC takes keys of any size");
+    mac.update(t.as_bytes());
+    mac.update(b".");
+    mac.update(raw_body);
+    // Constant-time.
+    mac.verify_slice(&signature)
+        .map_err(|_| SignatureError::Mismatch)?;
+
+    Ok(timestamp)
+}
+
+//
+//
+//
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    HeaderMissing,
+    HeaderInvalid,
+    TimestampMissing,
+    SignatureMissing,
+    TimestampInvalid,
+    SignatureInvalid,
+    TimestampOutOfTolerance {
+        timestamp: DateTime<Utc>,
+        now: DateTime<Utc>,
+    },
+    Mismatch,
+}
+impl core::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+impl std::error::Error for SignatureError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNATURE: &str = "0c7dbbb1faa685a1b7204ecbbe183cdb2f425b5a66b7a4ee7265ba292d4f31a9";
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(HEADER_NAME, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_verify_webhook_signature() {
+        let now = Utc.timestamp_opt(1615338610 + 10, 0).unwrap();
+        let tolerance = Duration::seconds(TOLERANCE_SECS_DEFAULT);
+        let verify = |headers: &HeaderMap, raw_body: &[u8], client_secret: &str| {
+            verify_webhook_signature_at(headers, raw_body, client_secret, tolerance, now)
+        };
+
+        let ok = headers(&format!("t=1615338610,s={SIGNATURE}"));
+        assert_eq!(
+            verify(&ok, b"{}", "CLIENT_SECRET"),
+            Ok(Utc.timestamp_opt(1615338610, 0).unwrap())
+        );
+        assert_eq!(
+            verify(
+                &headers(&format!("s={}, t=1615338610", SIGNATURE.to_uppercase())),
+                b"{}",
+                "CLIENT_SECRET"
+            ),
+            Ok(Utc.timestamp_opt(1615338610, 0).unwrap())
+        );
+
+        assert_eq!(
+            verify(&ok, b"{ }", "CLIENT_SECRET"),
+            Err(SignatureError::Mismatch)
+        );
+        assert_eq!(
+            verify(&ok, b"{}", "OTHER_SECRET"),
+            Err(SignatureError::Mismatch)
+        );
+        assert_eq!(
+            verify(
+                &headers(&format!("t=1615338611,s={SIGNATURE}")),
+                b"{}",
+                "CLIENT_SECRET"
+            ),
+            Err(SignatureError::Mismatch)
+        );
+
+        assert_eq!(
+            verify(&HeaderMap::new(), b"{}", "CLIENT_SECRET"),
+            Err(SignatureError::HeaderMissing)
+        );
+        assert_eq!(
+            verify(&headers(&format!("s={SIGNATURE}")), b"{}", "CLIENT_SECRET"),
+            Err(SignatureError::TimestampMissing)
+        );
+        assert_eq!(
+            verify(&headers("t=1615338610"), b"{}", "CLIENT_SECRET"),
+            Err(SignatureError::SignatureMissing)
+        );
+        assert_eq!(
+            verify(
+                &headers(&format!("t=x,s={SIGNATURE}")),
+                b"{}",
+                "CLIENT_SECRET"
+            ),
+            Err(SignatureError::TimestampInvalid)
+        );
+        assert_eq!(
+            verify(&headers("t=1615338610,s=0c7g"), b"{}", "CLIENT_SECRET"),
+            Err(SignatureError::SignatureInvalid)
+        );
+
+        // Replayed an hour later.
+        match verify_webhook_signature_at(
+            &ok,
+            b"{}",
+            "CLIENT_SECRET",
+            tolerance,
+            now + Duration::hours(1),
+        ) {
+            Err(SignatureError::TimestampOutOfTolerance { timestamp, .. }) => {
+                assert_eq!(timestamp.timestamp(), 1615338610)
+            }
+            x => panic!("{x:?}"),
+        }
+    }
+}